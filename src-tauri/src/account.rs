@@ -0,0 +1,337 @@
+use crate::base_url::UrlTarget;
+use crate::config::{get_claude_settings_path, get_codex_auth_path, get_codex_config_path};
+use crate::error::{AppError, AppResult, ErrorKind, SuggestedAction};
+use crate::i18n::t;
+use crate::presets;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// 缓存有效期，期间重复查询不会请求服务器
+const CACHE_TTL: Duration = Duration::from_secs(60);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 单个模型的近期用量
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModelUsage {
+    pub model: String,
+    pub requests: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    /// 消耗的额度
+    pub cost: f64,
+}
+
+/// 账户接口返回的数据，缺少的字段为空
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AccountInfo {
+    /// 套餐名称
+    pub plan: Option<String>,
+    pub credits_remaining: Option<f64>,
+    pub credits_total: Option<f64>,
+    /// 额度重置时间（服务器返回的 ISO 8601 字符串）
+    pub reset_at: Option<String>,
+    /// 近期（当日）按模型统计的用量
+    pub usage: Vec<ModelUsage>,
+}
+
+/// 返回给界面的账户状态
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountStatus {
+    pub target: UrlTarget,
+    #[serde(flatten)]
+    pub info: AccountInfo,
+    /// 打码后的密钥，便于确认查询的是哪个密钥
+    pub key_hint: String,
+    pub api_url: String,
+    /// 数据获取时间（毫秒时间戳）
+    pub fetched_at: u64,
+    /// 是否来自缓存
+    pub cached: bool,
+}
+
+struct CacheEntry {
+    at: Instant,
+    fetched_at: u64,
+    info: AccountInfo,
+}
+
+/// 按接口地址和密钥缓存的查询结果（键为二者的 SHA-256，不保存密钥原文）
+fn cache() -> &'static Mutex<HashMap<String, CacheEntry>> {
+    static CACHE: OnceLock<Mutex<HashMap<String, CacheEntry>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn cache_key(api_url: &str, api_key: &str) -> String {
+    Sha256::digest(format!("{}\n{}", api_url, api_key).as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 读取客户端当前配置的 Base URL 和密钥
+pub(crate) fn configured_credentials(target: UrlTarget) -> Option<(String, String)> {
+    match target {
+        UrlTarget::Claude => {
+            let settings = std::fs::read_to_string(get_claude_settings_path())
+                .ok()
+                .and_then(|c| crate::jsonc::parse_jsonc(&c).ok())?;
+            let env = settings.get("env")?;
+            let get = |key: &str| env.get(key).and_then(|v| v.as_str()).map(|s| s.to_string());
+            Some((get("ANTHROPIC_BASE_URL")?, get("ANTHROPIC_AUTH_TOKEN")?))
+        }
+        UrlTarget::Codex => {
+            let api_key = std::fs::read_to_string(get_codex_auth_path())
+                .ok()
+                .and_then(|c| serde_json::from_str::<Value>(&c).ok())
+                .and_then(|v| v.get("OPENAI_API_KEY")?.as_str().map(|s| s.to_string()))?;
+            let base_url = std::fs::read_to_string(get_codex_config_path())
+                .ok()
+                .and_then(|c| toml::from_str::<toml::Value>(&c).ok())
+                .and_then(|v| {
+                    v.get("model_providers")?
+                        .get("88code")?
+                        .get("base_url")?
+                        .as_str()
+                        .map(|s| s.to_string())
+                })?;
+            Some((base_url, api_key))
+        }
+    }
+}
+
+/// 账户接口地址：只使用 Base URL 所属预设中明确配置的 account_api_url，
+/// 不属于任何预设或预设未配置时不猜测地址（避免把密钥发给不提供该接口的服务）
+pub fn account_api_url(target: UrlTarget, base_url: &str) -> AppResult<String> {
+    let base_url = base_url.trim_end_matches('/');
+    presets::load()
+        .presets
+        .iter()
+        .find(|p| p.url(target).is_some_and(|url| url.trim_end_matches('/') == base_url))
+        .and_then(|p| p.account_api_url.clone())
+        .ok_or_else(|| AppError::not_found(t!("account.unsupported", url = base_url)))
+}
+
+/// 查询客户端当前配置的密钥对应的账户信息；缓存有效期内直接返回缓存，refresh 为 true 时强制刷新
+pub fn status(target: UrlTarget, refresh: bool) -> AppResult<AccountStatus> {
+    let (base_url, api_key) =
+        configured_credentials(target).ok_or_else(|| AppError::not_found(t!("account.not_configured")))?;
+    let api_url = account_api_url(target, &base_url)?;
+    let key_hint = crate::bundle::mask(&api_key);
+    let (info, fetched_at, cached) = cached_fetch(&api_url, &api_key, refresh)?;
+
+    Ok(AccountStatus {
+        target,
+        info,
+        key_hint,
+        api_url,
+        fetched_at,
+        cached,
+    })
+}
+
+/// 按接口地址和密钥查询账户信息，返回（数据, 获取时间, 是否来自缓存）
+fn cached_fetch(api_url: &str, api_key: &str, refresh: bool) -> AppResult<(AccountInfo, u64, bool)> {
+    let key = cache_key(api_url, api_key);
+    if !refresh {
+        let cache = cache().lock().unwrap();
+        if let Some(entry) = cache.get(&key).filter(|e| e.at.elapsed() < CACHE_TTL) {
+            return Ok((entry.info.clone(), entry.fetched_at, true));
+        }
+    }
+
+    let info = fetch(api_url, api_key)?;
+    let fetched_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64;
+    cache().lock().unwrap().insert(
+        key,
+        CacheEntry {
+            at: Instant::now(),
+            fetched_at,
+            info: info.clone(),
+        },
+    );
+    Ok((info, fetched_at, false))
+}
+
+/// 请求账户接口（不经过缓存）。响应可以直接是账户数据，也可以包在 data 字段中
+pub fn fetch(api_url: &str, api_key: &str) -> AppResult<AccountInfo> {
+    let agent = ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).build();
+    let response = agent
+        .get(api_url)
+        .set("Authorization", &format!("Bearer {}", api_key))
+        .set("Accept", "application/json")
+        .call()
+        .map_err(|e| match e {
+            ureq::Error::Status(401 | 403, _) => {
+                AppError::invalid_input(t!("account.unauthorized")).with_action(SuggestedAction::CheckInput)
+            }
+            ureq::Error::Status(404, _) => AppError::not_found(t!("account.unsupported", url = api_url)),
+            ureq::Error::Status(status, _) => AppError::new(ErrorKind::Other, t!("account.http_error", status = status))
+                .with_action(SuggestedAction::Retry),
+            ureq::Error::Transport(e) => AppError::new(ErrorKind::Other, t!("account.request_failed"))
+                .with_source(e)
+                .with_action(SuggestedAction::Retry),
+        })?;
+    let body = response.into_string().map_err(|e| {
+        AppError::new(ErrorKind::Other, t!("account.request_failed"))
+            .with_source(e)
+            .with_action(SuggestedAction::Retry)
+    })?;
+
+    let value: Value = serde_json::from_str(&body).map_err(|e| AppError::json(t!("account.invalid_response"), &e))?;
+    parse_response(value)
+}
+
+/// AccountInfo 中的字段名，响应至少包含其中之一才视为账户数据
+const KNOWN_FIELDS: &[&str] = &["plan", "credits_remaining", "credits_total", "reset_at", "usage"];
+
+/// 解析账户接口响应：先检查 {code, success, message} 形式的外层状态，再要求数据中至少有一个已知字段，
+/// 避免把 `{}` 或 HTTP 200 包装的错误响应当作空账户
+fn parse_response(value: Value) -> AppResult<AccountInfo> {
+    let failed = value.get("success").and_then(Value::as_bool) == Some(false)
+        || value.get("code").and_then(Value::as_i64).is_some_and(|code| code != 0 && code != 200);
+    if failed {
+        let code = value.get("code").and_then(Value::as_i64);
+        if matches!(code, Some(401 | 403)) {
+            return Err(AppError::invalid_input(t!("account.unauthorized")).with_action(SuggestedAction::CheckInput));
+        }
+        let message = ["message", "msg", "error"]
+            .iter()
+            .find_map(|k| value.get(*k).and_then(Value::as_str))
+            .unwrap_or_default();
+        return Err(AppError::new(ErrorKind::Other, t!("account.rejected", message = message))
+            .with_action(SuggestedAction::Retry));
+    }
+
+    let data = match value.get("data") {
+        Some(data) if data.is_object() => data.clone(),
+        _ => value,
+    };
+    if !KNOWN_FIELDS.iter().any(|k| data.get(*k).is_some_and(|v| !v.is_null())) {
+        return Err(AppError::new(ErrorKind::Parse, t!("account.invalid_response")));
+    }
+    serde_json::from_value(data).map_err(|e| AppError::json(t!("account.invalid_response"), &e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    /// 本地账户接口：固定返回指定状态码和响应体，并统计请求次数
+    fn stub(status: u16, body: &'static str) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://127.0.0.1:{}/api/usage", listener.local_addr().unwrap().port());
+        let hits = Arc::new(AtomicUsize::new(0));
+        let counter = hits.clone();
+        std::thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut buf = [0u8; 4096];
+                let _ = stream.read(&mut buf);
+                counter.fetch_add(1, Ordering::SeqCst);
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+            }
+        });
+        (url, hits)
+    }
+
+    #[test]
+    fn fetches_account_info() {
+        let (url, _) = stub(
+            200,
+            r#"{"code":0,"data":{"plan":"Pro","credits_remaining":12.5,"usage":[{"model":"claude-sonnet","requests":3}]}}"#,
+        );
+        let info = fetch(&url, "88_test").unwrap();
+        assert_eq!(info.plan.as_deref(), Some("Pro"));
+        assert_eq!(info.credits_remaining, Some(12.5));
+        assert_eq!(info.credits_total, None);
+        assert_eq!(info.usage.len(), 1);
+        assert_eq!(info.usage[0].requests, 3);
+    }
+
+    #[test]
+    fn maps_http_errors() {
+        let (url, _) = stub(401, "{}");
+        let err = fetch(&url, "88_test").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidInput);
+        assert_eq!(err.message, t!("account.unauthorized"));
+
+        let (url, _) = stub(404, "");
+        let err = fetch(&url, "88_test").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::NotFound);
+        assert_eq!(err.message, t!("account.unsupported", url = url));
+    }
+
+    #[test]
+    fn rejects_malformed_bodies() {
+        for body in ["not json", "{}", r#"{"data":{}}"#, r#"{"plan":null}"#] {
+            let (url, _) = stub(200, body);
+            let err = fetch(&url, "88_test").unwrap_err();
+            assert_eq!(err.kind, ErrorKind::Parse, "{}", body);
+            assert_eq!(err.message, t!("account.invalid_response"));
+        }
+    }
+
+    #[test]
+    fn rejects_error_envelopes() {
+        let (url, _) = stub(200, r#"{"code":401,"message":"token expired"}"#);
+        assert_eq!(fetch(&url, "88_test").unwrap_err().message, t!("account.unauthorized"));
+
+        let (url, _) = stub(200, r#"{"success":false,"msg":"quota service down","data":{"plan":"Pro"}}"#);
+        let err = fetch(&url, "88_test").unwrap_err();
+        assert_eq!(err.message, t!("account.rejected", message = "quota service down"));
+    }
+
+    #[test]
+    fn caches_within_ttl() {
+        let (url, hits) = stub(200, r#"{"plan":"Free"}"#);
+        let (info, fetched_at, cached) = cached_fetch(&url, "88_cache", false).unwrap();
+        assert!(!cached);
+        assert_eq!(info.plan.as_deref(), Some("Free"));
+
+        let (_, again_at, cached) = cached_fetch(&url, "88_cache", false).unwrap();
+        assert!(cached);
+        assert_eq!(again_at, fetched_at);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+
+        // 密钥不同或强制刷新时重新请求
+        assert!(!cached_fetch(&url, "88_other", false).unwrap().2);
+        assert!(!cached_fetch(&url, "88_cache", true).unwrap().2);
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    fn account_url_comes_only_from_presets() {
+        assert_eq!(
+            account_api_url(UrlTarget::Claude, "https://www.88code.org/api/").unwrap(),
+            "https://www.88code.org/api/usage"
+        );
+        assert_eq!(
+            account_api_url(UrlTarget::Codex, "https://88code.org/openai/v1").unwrap(),
+            "https://www.88code.org/api/usage"
+        );
+
+        let err = account_api_url(UrlTarget::Claude, "https://relay.example.com/api").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::NotFound);
+        assert_eq!(err.message, t!("account.unsupported", url = "https://relay.example.com/api"));
+        // Claude 地址不能匹配预设中的 Codex 地址
+        assert!(account_api_url(UrlTarget::Claude, "https://88code.org/openai/v1").is_err());
+    }
+}
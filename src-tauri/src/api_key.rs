@@ -0,0 +1,238 @@
+use crate::base_url::{self, UrlTarget};
+use crate::error::{AppError, AppResult};
+use crate::i18n::t;
+use serde::Serialize;
+
+/// 88code 密钥前缀
+const CODE88_PREFIX: &str = "88_";
+/// 88code 密钥前缀之后的最短长度，短于此值通常是复制不完整
+const CODE88_MIN_BODY_LEN: usize = 32;
+
+/// 模板或界面默认值中的占位密钥
+const PLACEHOLDERS: &[&str] = &["key", "apikey", "api_key", "your-api-key", "你的API密钥", "sk-xxx"];
+
+/// 识别出的密钥类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyFormat {
+    #[serde(rename = "88code")]
+    Code88,
+    /// Anthropic 官方密钥（sk-ant-）
+    Anthropic,
+    /// OpenAI 官方密钥（sk-、sk-proj-）
+    #[serde(rename = "openai")]
+    OpenAi,
+    Unknown,
+}
+
+/// 密钥检查发现的问题类别，前端可据此决定提示方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyWarning {
+    /// 首尾带有引号
+    Quoted,
+    /// 首尾或中间带有空白
+    Whitespace,
+    /// 粘贴了 "Bearer " 前缀
+    BearerPrefix,
+    /// 模板中的占位值
+    Placeholder,
+    /// 88code 密钥长度异常
+    UnexpectedLength,
+    /// OpenAI 密钥填到了 Claude Code
+    OpenAiKeyForClaude,
+    /// Anthropic 密钥填到了 Codex
+    AnthropicKeyForCodex,
+    /// 使用 88code 地址但密钥不是 88code 密钥
+    NotCode88Key,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyIssue {
+    pub code: KeyWarning,
+    pub message: String,
+    /// 是否已自动修正（false 表示仅提示）
+    pub fixed: bool,
+}
+
+/// 密钥检查结果，key 为清理后的密钥
+#[derive(Debug, Clone, Serialize)]
+pub struct KeyCheck {
+    pub key: String,
+    pub format: KeyFormat,
+    pub issues: Vec<KeyIssue>,
+}
+
+impl KeyCheck {
+    /// 在提示信息后附加密钥检查发现的问题
+    pub fn annotate(&self, message: String) -> String {
+        self.issues.iter().fold(message, |mut acc, issue| {
+            acc.push('\n');
+            acc.push_str(&issue.message);
+            acc
+        })
+    }
+}
+
+/// 识别密钥类型
+pub fn detect_format(key: &str) -> KeyFormat {
+    if key.starts_with(CODE88_PREFIX) {
+        KeyFormat::Code88
+    } else if key.starts_with("sk-ant-") {
+        KeyFormat::Anthropic
+    } else if key.starts_with("sk-") {
+        KeyFormat::OpenAi
+    } else {
+        KeyFormat::Unknown
+    }
+}
+
+/// 检查并清理用户输入的密钥：去除引号、空白和 Bearer 前缀，
+/// 并结合使用方和 Base URL 检查密钥是否填错位置；只有空密钥会返回错误
+pub fn check(target: UrlTarget, input: &str, base_url: Option<&str>) -> AppResult<KeyCheck> {
+    let mut issues = Vec::new();
+    let mut push = |code: KeyWarning, message: String, fixed: bool| {
+        issues.push(KeyIssue { code, message, fixed })
+    };
+
+    let mut key = input.trim();
+    if key != input {
+        push(KeyWarning::Whitespace, t!("key.trimmed_whitespace"), true);
+    }
+    let unquoted = key.trim_matches(['"', '\'', '`']).trim();
+    if unquoted != key {
+        push(KeyWarning::Quoted, t!("key.removed_quotes"), true);
+        key = unquoted;
+    }
+    if key.get(..7).is_some_and(|prefix| prefix.eq_ignore_ascii_case("bearer ")) {
+        push(KeyWarning::BearerPrefix, t!("key.removed_bearer"), true);
+        key = key[7..].trim_start();
+    }
+    if key.is_empty() {
+        return Err(AppError::invalid_input(t!("input.api_key_empty")));
+    }
+    if key.chars().any(char::is_whitespace) {
+        push(KeyWarning::Whitespace, t!("key.inner_whitespace"), false);
+    }
+    let placeholder = PLACEHOLDERS.iter().any(|p| p.eq_ignore_ascii_case(key));
+    if placeholder {
+        push(KeyWarning::Placeholder, t!("key.placeholder"), false);
+    }
+
+    let format = detect_format(key);
+    let mut mismatched = false;
+    match (format, target) {
+        (KeyFormat::Code88, _) => {
+            let body = &key[CODE88_PREFIX.len()..];
+            if body.len() < CODE88_MIN_BODY_LEN || !body.chars().all(|c| c.is_ascii_alphanumeric()) {
+                push(KeyWarning::UnexpectedLength, t!("key.truncated"), false);
+            }
+        }
+        (KeyFormat::OpenAi, UrlTarget::Claude) => {
+            push(KeyWarning::OpenAiKeyForClaude, t!("key.openai_for_claude"), false);
+            mismatched = true;
+        }
+        (KeyFormat::Anthropic, UrlTarget::Codex) => {
+            push(KeyWarning::AnthropicKeyForCodex, t!("key.anthropic_for_codex"), false);
+            mismatched = true;
+        }
+        _ => {}
+    }
+    // 官方密钥不能用于 88code 服务（填错位置的情况上面已经提示）
+    if format != KeyFormat::Code88 && !mismatched && !placeholder && base_url.is_some_and(base_url::is_official) {
+        push(KeyWarning::NotCode88Key, t!("key.not_code88"), false);
+    }
+
+    Ok(KeyCheck {
+        key: key.to_string(),
+        format,
+        issues,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "88_0123456789abcdef0123456789abcdef";
+    const OFFICIAL: Option<&str> = Some("https://www.88code.org/api");
+
+    fn codes(check: &KeyCheck) -> Vec<KeyWarning> {
+        check.issues.iter().map(|i| i.code).collect()
+    }
+
+    #[test]
+    fn accepts_clean_88code_key() {
+        let c = check(UrlTarget::Claude, KEY, OFFICIAL).unwrap();
+        assert_eq!(c.key, KEY);
+        assert_eq!(c.format, KeyFormat::Code88);
+        assert!(c.issues.is_empty());
+    }
+
+    #[test]
+    fn strips_whitespace_quotes_and_bearer() {
+        let c = check(UrlTarget::Codex, &format!(" \"Bearer {}\"\n", KEY), None).unwrap();
+        assert_eq!(c.key, KEY);
+        assert_eq!(codes(&c), vec![KeyWarning::Whitespace, KeyWarning::Quoted, KeyWarning::BearerPrefix]);
+        assert!(c.issues.iter().all(|i| i.fixed));
+
+        let c = check(UrlTarget::Codex, &format!("bearer {}", KEY), None).unwrap();
+        assert_eq!(c.key, KEY);
+    }
+
+    #[test]
+    fn rejects_only_empty_keys() {
+        for input in ["", "   ", "''", "`  `"] {
+            assert!(check(UrlTarget::Claude, input, None).is_err(), "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn warns_about_inner_whitespace_and_placeholders() {
+        let c = check(UrlTarget::Claude, "88_abc def", None).unwrap();
+        assert!(codes(&c).contains(&KeyWarning::Whitespace));
+        assert!(c.issues.iter().any(|i| !i.fixed));
+
+        let c = check(UrlTarget::Claude, "your-api-key", OFFICIAL).unwrap();
+        assert_eq!(codes(&c), vec![KeyWarning::Placeholder]);
+    }
+
+    #[test]
+    fn warns_about_truncated_88code_key() {
+        let c = check(UrlTarget::Claude, "88_abc", None).unwrap();
+        assert_eq!(codes(&c), vec![KeyWarning::UnexpectedLength]);
+        let c = check(UrlTarget::Claude, &format!("{}-", KEY), None).unwrap();
+        assert_eq!(codes(&c), vec![KeyWarning::UnexpectedLength]);
+    }
+
+    #[test]
+    fn warns_about_key_for_the_wrong_client() {
+        let c = check(UrlTarget::Claude, "sk-proj-abc", OFFICIAL).unwrap();
+        assert_eq!(c.format, KeyFormat::OpenAi);
+        assert_eq!(codes(&c), vec![KeyWarning::OpenAiKeyForClaude]);
+
+        let c = check(UrlTarget::Codex, "sk-ant-abc", Some("https://88code.org/openai/v1")).unwrap();
+        assert_eq!(c.format, KeyFormat::Anthropic);
+        assert_eq!(codes(&c), vec![KeyWarning::AnthropicKeyForCodex]);
+    }
+
+    #[test]
+    fn warns_about_official_key_only_for_88code_urls() {
+        let c = check(UrlTarget::Claude, "sk-ant-abc", OFFICIAL).unwrap();
+        assert_eq!(codes(&c), vec![KeyWarning::NotCode88Key]);
+
+        let c = check(UrlTarget::Claude, "sk-ant-abc", Some("https://api.anthropic.com")).unwrap();
+        assert!(c.issues.is_empty());
+        let c = check(UrlTarget::Claude, "sk-ant-abc", None).unwrap();
+        assert!(c.issues.is_empty());
+    }
+
+    #[test]
+    fn detects_key_formats() {
+        assert_eq!(detect_format(KEY), KeyFormat::Code88);
+        assert_eq!(detect_format("sk-ant-api03-x"), KeyFormat::Anthropic);
+        assert_eq!(detect_format("sk-proj-x"), KeyFormat::OpenAi);
+        assert_eq!(detect_format("sk-x"), KeyFormat::OpenAi);
+        assert_eq!(detect_format("abc"), KeyFormat::Unknown);
+    }
+}
@@ -0,0 +1,377 @@
+use crate::error::{AppError, AppResult};
+use crate::i18n::t;
+use crate::presets::{self, PresetList};
+use serde::{Deserialize, Serialize};
+
+/// 预设中没有对应地址时使用的 Base URL
+const FALLBACK_CLAUDE_BASE_URL: &str = "https://www.88code.org/api";
+const FALLBACK_CODEX_BASE_URL: &str = "https://88code.org/openai/v1";
+
+/// 88code 官方域名（含子域名），可安全地自动升级为 https
+const OFFICIAL_DOMAIN: &str = "88code.org";
+
+/// Base URL 的使用方（Claude Code 与 Codex 的路径约定不同）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UrlTarget {
+    Claude,
+    Codex,
+}
+
+impl UrlTarget {
+    fn other(self) -> Self {
+        match self {
+            UrlTarget::Claude => UrlTarget::Codex,
+            UrlTarget::Codex => UrlTarget::Claude,
+        }
+    }
+
+    /// 默认地址（来自预设文件）
+    pub fn default_url(self, presets: &PresetList) -> String {
+        let fallback = match self {
+            UrlTarget::Claude => FALLBACK_CLAUDE_BASE_URL,
+            UrlTarget::Codex => FALLBACK_CODEX_BASE_URL,
+        };
+        presets.default_url(self).unwrap_or(fallback).to_string()
+    }
+}
+
+/// 规范化时发现的问题
+#[derive(Debug, Clone, Serialize)]
+pub struct UrlIssue {
+    pub message: String,
+    /// 是否已自动修正（false 表示仅提示）
+    pub fixed: bool,
+}
+
+/// 规范化结果
+#[derive(Debug, Clone, Serialize)]
+pub struct NormalizedUrl {
+    pub url: String,
+    pub issues: Vec<UrlIssue>,
+}
+
+impl NormalizedUrl {
+    /// 在提示信息后附加自动修正和警告内容
+    pub fn annotate(&self, message: String) -> String {
+        self.issues.iter().fold(message, |mut acc, issue| {
+            acc.push('\n');
+            acc.push_str(&issue.message);
+            acc
+        })
+    }
+}
+
+/// 解析用户输入的 Base URL：为空时使用对应客户端的默认值，否则规范化
+pub fn resolve(target: UrlTarget, input: &str) -> AppResult<NormalizedUrl> {
+    if input.trim().is_empty() {
+        return Ok(NormalizedUrl {
+            url: target.default_url(&presets::load()),
+            issues: Vec::new(),
+        });
+    }
+    normalize(target, input)
+}
+
+/// URL 中不允许出现的 ASCII 字符（RFC 3986），其中引号和反斜杠还会破坏写入的 TOML/JSON
+const INVALID_URL_CHARS: &[char] = &['"', '\\', '<', '>', '^', '`', '{', '|', '}'];
+
+/// 拒绝控制字符和 URL 中不允许出现的字符
+pub fn ensure_url_chars(url: &str) -> AppResult<()> {
+    match url.chars().find(|c| c.is_control() || INVALID_URL_CHARS.contains(c)) {
+        Some(c) => Err(AppError::invalid_input(t!("url.invalid_char", ch = c.escape_default()))),
+        None => Ok(()),
+    }
+}
+
+/// 规范化 Base URL：去除空白、补全协议、移除查询参数和多余斜杠、修正常见的路径错误；
+/// 无法安全修正的错误（如把 Claude Code 地址填到 Codex）直接返回错误
+pub fn normalize(target: UrlTarget, input: &str) -> AppResult<NormalizedUrl> {
+    let mut issues = Vec::new();
+    let mut fixed = |message: String| issues.push(UrlIssue { message, fixed: true });
+
+    let trimmed = input.trim();
+    if trimmed.is_empty() {
+        return Err(AppError::invalid_input(t!("url.empty")));
+    }
+    if trimmed != input {
+        fixed(t!("url.trimmed_whitespace"));
+    }
+    if trimmed.chars().any(char::is_whitespace) {
+        return Err(AppError::invalid_input(t!("url.inner_whitespace")));
+    }
+    ensure_url_chars(trimmed)?;
+
+    let (mut scheme, rest) = match trimmed.split_once("://") {
+        Some((scheme, rest)) => (scheme.to_ascii_lowercase(), rest),
+        None => {
+            fixed(t!("url.added_scheme"));
+            ("https".to_string(), trimmed)
+        }
+    };
+    if scheme != "http" && scheme != "https" {
+        return Err(AppError::invalid_input(t!("url.unsupported_scheme", scheme = scheme)));
+    }
+
+    let rest = match rest.find(['?', '#']) {
+        Some(index) => {
+            fixed(t!("url.removed_query", part = &rest[index..]));
+            &rest[..index]
+        }
+        None => rest,
+    };
+
+    let (host, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let host = host.to_ascii_lowercase();
+    let hostname = host_without_port(&host);
+    if hostname.is_empty() {
+        return Err(AppError::invalid_input(t!("url.missing_host")));
+    }
+    let official = is_official_host(hostname);
+
+    let mut warnings = Vec::new();
+    if scheme == "http" && !is_local(hostname) {
+        if official {
+            scheme = "https".to_string();
+            fixed(t!("url.upgraded_https"));
+        } else {
+            warnings.push(t!("url.insecure_http"));
+        }
+    }
+
+    let mut segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+    if segments.iter().map(|s| s.len() + 1).sum::<usize>() != path.len() {
+        fixed(t!("url.trimmed_slash"));
+    }
+
+    // 与另一客户端的预设地址相同，说明填错了位置
+    let presets = presets::load();
+    let build = |segments: &[&str]| {
+        let mut url = format!("{}://{}", scheme, host);
+        for segment in segments {
+            url.push('/');
+            url.push_str(segment);
+        }
+        url
+    };
+    let candidate = build(&segments);
+    let swapped = presets.urls(target.other()).any(|url| url.trim_end_matches('/') == candidate);
+    if swapped || target == UrlTarget::Claude && segments.contains(&"openai") {
+        let message = match target {
+            UrlTarget::Claude => t!("url.codex_url_for_claude"),
+            UrlTarget::Codex => t!("url.claude_url_for_codex"),
+        };
+        let suggestion = if official {
+            t!("url.use_default", url = target.default_url(&presets))
+        } else {
+            String::new()
+        };
+        return Err(AppError::invalid_input(format!("{}{}", message, suggestion)));
+    }
+
+    match target {
+        UrlTarget::Claude => {
+            // Claude Code 会自动追加 /v1/messages
+            if strip_suffix(&mut segments, &["v1", "messages"]) || strip_suffix(&mut segments, &["v1"]) {
+                fixed(t!("url.removed_endpoint", suffix = "/v1/messages"));
+            }
+        }
+        UrlTarget::Codex => {
+            // Codex 会自动追加 /responses 或 /chat/completions
+            for endpoint in [&["responses"][..], &["chat", "completions"], &["completions"], &["models"]] {
+                if strip_suffix(&mut segments, endpoint) {
+                    fixed(t!("url.removed_endpoint", suffix = format!("/{}", endpoint.join("/"))));
+                    break;
+                }
+            }
+            if segments.ends_with(&["v1", "v1"]) {
+                while segments.ends_with(&["v1", "v1"]) {
+                    segments.pop();
+                }
+                fixed(t!("url.duplicate_v1"));
+            }
+            if segments.last() != Some(&"v1") {
+                // 补全 /v1 后与预设地址一致时自动补全，否则只提示
+                let with_v1 = format!("{}/v1", build(&segments));
+                if presets.urls(UrlTarget::Codex).any(|url| url.trim_end_matches('/') == with_v1) {
+                    segments.push("v1");
+                    fixed(t!("url.added_v1"));
+                } else {
+                    warnings.push(t!("url.missing_v1"));
+                }
+            }
+        }
+    }
+
+    let url = build(&segments);
+
+    issues.extend(warnings.into_iter().map(|message| UrlIssue { message, fixed: false }));
+    Ok(NormalizedUrl { url, issues })
+}
+
+/// 是否为 88code 官方地址
+pub fn is_official(url: &str) -> bool {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let host = rest.split(['/', '?', '#']).next().unwrap_or("").to_ascii_lowercase();
+    is_official_host(host_without_port(&host))
+}
+
+fn is_official_host(hostname: &str) -> bool {
+    hostname == OFFICIAL_DOMAIN
+        || hostname.strip_suffix(OFFICIAL_DOMAIN).is_some_and(|sub| sub.ends_with('.'))
+}
+
+/// 路径以指定的段结尾时移除这些段
+fn strip_suffix(segments: &mut Vec<&str>, suffix: &[&str]) -> bool {
+    if segments.ends_with(suffix) {
+        segments.truncate(segments.len() - suffix.len());
+        true
+    } else {
+        false
+    }
+}
+
+/// 去掉端口号（兼容 IPv6 的 [::1]:8080 写法）
+fn host_without_port(host: &str) -> &str {
+    let host = host.rsplit_once('@').map_or(host, |(_, h)| h);
+    if let Some(end) = host.find(']') {
+        return &host[..=end];
+    }
+    host.split(':').next().unwrap_or(host)
+}
+
+/// 本地地址（本地中转等场景）允许使用 http
+fn is_local(hostname: &str) -> bool {
+    hostname == "localhost" || hostname == "[::1]" || hostname.starts_with("127.") || hostname == "0.0.0.0"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixed_count(n: &NormalizedUrl) -> usize {
+        n.issues.iter().filter(|i| i.fixed).count()
+    }
+
+    #[test]
+    fn keeps_clean_urls_unchanged() {
+        let n = normalize(UrlTarget::Claude, "https://www.88code.org/api").unwrap();
+        assert_eq!(n.url, "https://www.88code.org/api");
+        assert!(n.issues.is_empty());
+
+        let n = normalize(UrlTarget::Codex, "https://88code.org/openai/v1").unwrap();
+        assert_eq!(n.url, "https://88code.org/openai/v1");
+        assert!(n.issues.is_empty());
+    }
+
+    #[test]
+    fn fixes_whitespace_scheme_query_and_slashes() {
+        let n = normalize(UrlTarget::Claude, "  www.88code.org//api/?x=1#top ").unwrap();
+        assert_eq!(n.url, "https://www.88code.org/api");
+        assert_eq!(fixed_count(&n), 4);
+        assert!(n.issues.iter().all(|i| i.fixed));
+    }
+
+    #[test]
+    fn lowercases_scheme_and_host_only() {
+        let n = normalize(UrlTarget::Claude, "HTTPS://Example.COM/Api").unwrap();
+        assert_eq!(n.url, "https://example.com/Api");
+    }
+
+    #[test]
+    fn rejects_unusable_input() {
+        for input in ["", "   ", "https://88code.org/a b", "ftp://88code.org/api", "https:///api"] {
+            let err = normalize(UrlTarget::Claude, input).unwrap_err();
+            assert_eq!(err.kind, crate::error::ErrorKind::InvalidInput, "{:?}", input);
+        }
+    }
+
+    #[test]
+    fn upgrades_official_http_and_warns_for_others() {
+        let n = normalize(UrlTarget::Claude, "http://www.88code.org/api").unwrap();
+        assert_eq!(n.url, "https://www.88code.org/api");
+        assert_eq!(n.issues[0].message, t!("url.upgraded_https"));
+
+        let n = normalize(UrlTarget::Claude, "http://relay.example.com/api").unwrap();
+        assert_eq!(n.url, "http://relay.example.com/api");
+        assert_eq!(n.issues.len(), 1);
+        assert!(!n.issues[0].fixed);
+
+        let n = normalize(UrlTarget::Claude, "http://127.0.0.1:8788").unwrap();
+        assert_eq!(n.url, "http://127.0.0.1:8788");
+        assert!(n.issues.is_empty());
+    }
+
+    #[test]
+    fn strips_claude_endpoint_suffix() {
+        let n = normalize(UrlTarget::Claude, "https://www.88code.org/api/v1/messages").unwrap();
+        assert_eq!(n.url, "https://www.88code.org/api");
+        let n = normalize(UrlTarget::Claude, "https://www.88code.org/api/v1").unwrap();
+        assert_eq!(n.url, "https://www.88code.org/api");
+    }
+
+    #[test]
+    fn strips_codex_endpoint_and_duplicate_v1() {
+        for input in [
+            "https://88code.org/openai/v1/responses",
+            "https://88code.org/openai/v1/chat/completions",
+            "https://88code.org/openai/v1/models",
+            "https://88code.org/openai/v1/v1",
+        ] {
+            assert_eq!(normalize(UrlTarget::Codex, input).unwrap().url, "https://88code.org/openai/v1", "{}", input);
+        }
+    }
+
+    #[test]
+    fn adds_v1_only_when_it_matches_a_preset() {
+        let n = normalize(UrlTarget::Codex, "https://88code.org/openai").unwrap();
+        assert_eq!(n.url, "https://88code.org/openai/v1");
+        assert_eq!(n.issues[0].message, t!("url.added_v1"));
+
+        let n = normalize(UrlTarget::Codex, "https://relay.example.com/openai").unwrap();
+        assert_eq!(n.url, "https://relay.example.com/openai");
+        assert_eq!(n.issues.len(), 1);
+        assert_eq!(n.issues[0].message, t!("url.missing_v1"));
+        assert!(!n.issues[0].fixed);
+    }
+
+    #[test]
+    fn rejects_url_of_the_other_client() {
+        assert!(normalize(UrlTarget::Claude, "https://88code.org/openai/v1").is_err());
+        assert!(normalize(UrlTarget::Claude, "https://relay.example.com/openai").is_err());
+        assert!(normalize(UrlTarget::Codex, "https://www.88code.org/api").is_err());
+    }
+
+    #[test]
+    fn resolves_empty_input_to_default() {
+        assert_eq!(resolve(UrlTarget::Claude, " ").unwrap().url, "https://www.88code.org/api");
+        assert_eq!(resolve(UrlTarget::Codex, "").unwrap().url, "https://88code.org/openai/v1");
+    }
+
+    #[test]
+    fn detects_official_hosts() {
+        assert!(is_official("https://88code.org/api"));
+        assert!(is_official("https://www.88code.org:443/api"));
+        assert!(!is_official("https://not88code.org/api"));
+        assert!(!is_official("https://88code.org.evil.com/api"));
+    }
+
+    #[test]
+    fn rejects_characters_that_are_not_valid_in_urls() {
+        for input in [
+            "https://88code.org/api\"",
+            "https://88code.org/api\"\nmodel = \"x",
+            "https://88code.org\\api",
+            "https://88code.org/api\u{7f}",
+            "https://88code.org/api\u{0}",
+            "https://88code.org/{api}",
+            "https://88code.org/<api>",
+        ] {
+            let err = normalize(UrlTarget::Claude, input).unwrap_err();
+            assert_eq!(err.kind, crate::error::ErrorKind::InvalidInput, "{input:?}");
+        }
+        assert!(ensure_url_chars("https://88code.org/api").is_ok());
+        assert!(ensure_url_chars("https://例子.测试/api").is_ok());
+        assert!(normalize(UrlTarget::Codex, "https://88code.org/openai/v1?").is_ok());
+    }
+}
@@ -0,0 +1,485 @@
+use crate::base_url::{self, UrlTarget};
+use crate::error::{AppError, AppResult, ErrorKind};
+use crate::i18n::t;
+use crate::presets;
+use rustls::pki_types::ServerName;
+use rustls::{ClientConfig, ClientConnection, RootCertStore, StreamOwned};
+use serde::Serialize;
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 默认每个地址探测次数
+pub const DEFAULT_PROBES: u32 = 5;
+const MAX_PROBES: u32 = 20;
+/// 默认单步（连接、握手、读写）超时
+pub const DEFAULT_TIMEOUT_MS: u64 = 5000;
+const MIN_TIMEOUT_MS: u64 = 100;
+const MAX_TIMEOUT_MS: u64 = 30_000;
+
+/// 测速参数
+#[derive(Debug, Clone, Copy)]
+pub struct BenchmarkOptions {
+    pub probes: u32,
+    pub timeout: Duration,
+}
+
+impl BenchmarkOptions {
+    /// 未指定时使用默认值，超出范围时取边界值
+    pub fn new(probes: Option<u32>, timeout_ms: Option<u64>) -> Self {
+        Self {
+            probes: probes.unwrap_or(DEFAULT_PROBES).clamp(1, MAX_PROBES),
+            timeout: Duration::from_millis(
+                timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS).clamp(MIN_TIMEOUT_MS, MAX_TIMEOUT_MS),
+            ),
+        }
+    }
+}
+
+/// 单次探测的耗时（毫秒），均从开始解析地址起算，与 curl 的
+/// time_connect、time_appconnect、time_starttransfer 含义相同
+#[derive(Debug, Clone, Copy)]
+struct ProbeTiming {
+    connect_ms: f64,
+    tls_ms: Option<f64>,
+    first_byte_ms: f64,
+}
+
+/// 单个地址的测速结果，耗时为成功探测的中位数
+#[derive(Debug, Clone, Serialize)]
+pub struct EndpointResult {
+    pub url: String,
+    pub probes: u32,
+    pub errors: u32,
+    pub error_rate: f64,
+    pub connect_ms: Option<f64>,
+    /// http 地址没有 TLS 握手，为 None
+    pub tls_ms: Option<f64>,
+    pub first_byte_ms: Option<f64>,
+    /// 最近一次失败的原因
+    pub last_error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkReport {
+    pub target: UrlTarget,
+    /// 按错误率、首字节耗时从优到劣排序
+    pub results: Vec<EndpointResult>,
+    /// 最快的可用地址，全部失败时为 None
+    pub winner: Option<String>,
+    /// 写入配置后的提示信息，未写入时为 None
+    pub applied: Option<String>,
+}
+
+/// 解析后的探测目标
+struct Endpoint {
+    tls: bool,
+    /// 用于解析地址和 TLS 校验的主机名（IPv6 不含方括号）
+    host: String,
+    port: u16,
+    /// Host 请求头（含非默认端口）
+    authority: String,
+    path: String,
+}
+
+/// 对候选地址测速并排序；未提供候选地址时使用预设中该客户端的所有地址。
+/// 各地址并行测试，同一地址的多次探测依次进行
+pub fn run(target: UrlTarget, urls: &[String], options: BenchmarkOptions) -> AppResult<BenchmarkReport> {
+    let candidates: Vec<String> = if urls.iter().all(|url| url.trim().is_empty()) {
+        presets::load().urls(target).map(str::to_string).collect()
+    } else {
+        urls.iter().filter(|url| !url.trim().is_empty()).cloned().collect()
+    };
+    if candidates.is_empty() {
+        return Err(AppError::invalid_input(t!("bench.no_candidates")));
+    }
+
+    // 规范化后去重，无效地址直接记为失败
+    let mut normalized: Vec<Result<String, (String, String)>> = Vec::new();
+    for url in candidates {
+        match base_url::normalize(target, &url) {
+            Ok(n) if normalized.iter().any(|r| r.as_ref() == Ok(&n.url)) => {}
+            Ok(n) => normalized.push(Ok(n.url)),
+            Err(e) => normalized.push(Err((url, e.to_string()))),
+        }
+    }
+
+    let config = tls_config();
+    let mut results: Vec<EndpointResult> = thread::scope(|scope| {
+        let handles: Vec<_> = normalized
+            .into_iter()
+            .map(|candidate| {
+                let config = &config;
+                scope.spawn(move || match candidate {
+                    Ok(url) => measure(url, config, options),
+                    Err((url, error)) => failed(url, options.probes, error),
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|handle| handle.join().expect("测速线程异常退出"))
+            .collect()
+    });
+
+    results.sort_by(|a, b| {
+        a.error_rate
+            .total_cmp(&b.error_rate)
+            .then(a.first_byte_ms.unwrap_or(f64::INFINITY).total_cmp(&b.first_byte_ms.unwrap_or(f64::INFINITY)))
+    });
+    let winner = results
+        .first()
+        .filter(|r| r.first_byte_ms.is_some())
+        .map(|r| r.url.clone());
+
+    Ok(BenchmarkReport {
+        target,
+        results,
+        winner,
+        applied: None,
+    })
+}
+
+fn tls_config() -> Arc<ClientConfig> {
+    let roots = RootCertStore {
+        roots: webpki_roots::TLS_SERVER_ROOTS.to_vec(),
+    };
+    let config = ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()
+        .expect("TLS 协议版本配置错误")
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Arc::new(config)
+}
+
+fn failed(url: String, probes: u32, error: String) -> EndpointResult {
+    EndpointResult {
+        url,
+        probes,
+        errors: probes,
+        error_rate: 1.0,
+        connect_ms: None,
+        tls_ms: None,
+        first_byte_ms: None,
+        last_error: Some(error),
+    }
+}
+
+/// 对单个地址探测多次并汇总
+fn measure(url: String, config: &Arc<ClientConfig>, options: BenchmarkOptions) -> EndpointResult {
+    let endpoint = match parse_endpoint(&url) {
+        Ok(endpoint) => endpoint,
+        Err(e) => return failed(url, options.probes, e.to_string()),
+    };
+
+    let mut timings = Vec::new();
+    let mut last_error = None;
+    for _ in 0..options.probes {
+        match probe(&endpoint, config, options.timeout) {
+            Ok(timing) => timings.push(timing),
+            Err(e) => last_error = Some(e.to_string()),
+        }
+    }
+
+    let errors = options.probes - timings.len() as u32;
+    EndpointResult {
+        url,
+        probes: options.probes,
+        errors,
+        error_rate: errors as f64 / options.probes as f64,
+        connect_ms: median(timings.iter().map(|t| t.connect_ms).collect()),
+        tls_ms: median(timings.iter().filter_map(|t| t.tls_ms).collect()),
+        first_byte_ms: median(timings.iter().map(|t| t.first_byte_ms).collect()),
+        last_error,
+    }
+}
+
+/// 拆分规范化后的地址（scheme://host[:port]/path）
+fn parse_endpoint(url: &str) -> AppResult<Endpoint> {
+    let invalid = || AppError::invalid_input(t!("bench.invalid_url", url = url));
+    let (scheme, rest) = url.split_once("://").ok_or_else(invalid)?;
+    let tls = scheme == "https";
+    let (authority, path) = rest.split_at(rest.find('/').unwrap_or(rest.len()));
+    let authority = authority.rsplit_once('@').map_or(authority, |(_, a)| a);
+
+    let (host, port) = match authority.strip_prefix('[') {
+        // IPv6：[::1]:8080
+        Some(v6) => {
+            let (host, after) = v6.split_once(']').ok_or_else(invalid)?;
+            (host, after.strip_prefix(':'))
+        }
+        None => match authority.split_once(':') {
+            Some((host, port)) => (host, Some(port)),
+            None => (authority, None),
+        },
+    };
+    let port = match port {
+        Some(port) => port.parse().map_err(|_| invalid())?,
+        None if tls => 443,
+        None => 80,
+    };
+
+    Ok(Endpoint {
+        tls,
+        host: host.to_string(),
+        port,
+        authority: authority.to_string(),
+        path: if path.is_empty() { "/".to_string() } else { path.to_string() },
+    })
+}
+
+/// 一次完整探测：解析地址并连接、TLS 握手、发送请求、等待响应首字节。
+/// 5xx 响应算作失败，其他状态码（如未带密钥的 401）只要服务有响应就算成功
+fn probe(endpoint: &Endpoint, config: &Arc<ClientConfig>, timeout: Duration) -> AppResult<ProbeTiming> {
+    let start = Instant::now();
+    let addrs: Vec<SocketAddr> = (endpoint.host.as_str(), endpoint.port)
+        .to_socket_addrs()
+        .map_err(|e| AppError::new(ErrorKind::Io, t!("bench.resolve_failed")).with_source(e))?
+        .collect();
+    let stream = connect(&addrs, timeout)?;
+    stream
+        .set_read_timeout(Some(timeout))
+        .and_then(|_| stream.set_write_timeout(Some(timeout)))
+        .map_err(|e| io_error(t!("bench.connect_failed"), e))?;
+    let connect_ms = elapsed_ms(start);
+
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: {}\r\nUser-Agent: 88code-desktop\r\nAccept: */*\r\nConnection: close\r\n\r\n",
+        endpoint.path, endpoint.authority
+    );
+
+    if !endpoint.tls {
+        let mut stream = stream;
+        let first_byte_ms = first_byte(&mut stream, &request, start)?;
+        return Ok(ProbeTiming {
+            connect_ms,
+            tls_ms: None,
+            first_byte_ms,
+        });
+    }
+
+    let name = ServerName::try_from(endpoint.host.clone())
+        .map_err(|_| AppError::invalid_input(t!("bench.invalid_url", url = &endpoint.host)))?;
+    let connection =
+        ClientConnection::new(config.clone(), name).map_err(|e| AppError::new(ErrorKind::Io, t!("bench.tls_failed")).with_source(e))?;
+    let mut tls = StreamOwned::new(connection, stream);
+    while tls.conn.is_handshaking() {
+        tls.conn
+            .complete_io(&mut tls.sock)
+            .map_err(|e| io_error(t!("bench.tls_failed"), e))?;
+    }
+    let tls_ms = elapsed_ms(start);
+    let first_byte_ms = first_byte(&mut tls, &request, start)?;
+
+    Ok(ProbeTiming {
+        connect_ms,
+        tls_ms: Some(tls_ms),
+        first_byte_ms,
+    })
+}
+
+/// 依次尝试解析出的地址，返回第一个连接成功的
+fn connect(addrs: &[SocketAddr], timeout: Duration) -> AppResult<TcpStream> {
+    let mut last_error = None;
+    for addr in addrs {
+        match TcpStream::connect_timeout(addr, timeout) {
+            Ok(stream) => return Ok(stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(match last_error {
+        Some(e) => io_error(t!("bench.connect_failed"), e),
+        None => AppError::new(ErrorKind::Io, t!("bench.resolve_failed")),
+    })
+}
+
+/// 发送请求并等待响应首字节，然后读取状态行检查状态码
+fn first_byte<S: Read + Write>(stream: &mut S, request: &str, start: Instant) -> AppResult<f64> {
+    stream
+        .write_all(request.as_bytes())
+        .and_then(|_| stream.flush())
+        .map_err(|e| io_error(t!("bench.request_failed"), e))?;
+
+    // "HTTP/1.1 200" 共 12 个字节
+    let mut head = [0u8; 12];
+    let mut read = 0;
+    let mut first_byte_ms = None;
+    while read < head.len() {
+        match stream.read(&mut head[read..]) {
+            Ok(0) => break,
+            Ok(n) => {
+                first_byte_ms.get_or_insert_with(|| elapsed_ms(start));
+                read += n;
+            }
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(io_error(t!("bench.request_failed"), e)),
+        }
+    }
+    let first_byte_ms = first_byte_ms.ok_or_else(|| AppError::new(ErrorKind::Io, t!("bench.no_response")))?;
+
+    let head = String::from_utf8_lossy(&head[..read]);
+    let status: u16 = head
+        .strip_prefix("HTTP/")
+        .and_then(|rest| rest.split_once(' '))
+        .and_then(|(_, code)| code.trim().parse().ok())
+        .ok_or_else(|| AppError::new(ErrorKind::Other, t!("bench.not_http")))?;
+    if status >= 500 {
+        return Err(AppError::new(ErrorKind::Other, t!("bench.server_error", status = status)));
+    }
+    Ok(first_byte_ms)
+}
+
+/// 超时单独提示，其他错误附带所在阶段
+fn io_error(message: String, e: io::Error) -> AppError {
+    match e.kind() {
+        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock => AppError::new(ErrorKind::Io, t!("bench.timeout")),
+        _ => AppError::new(ErrorKind::Io, message).with_source(e),
+    }
+}
+
+fn elapsed_ms(start: Instant) -> f64 {
+    (start.elapsed().as_secs_f64() * 10_000.0).round() / 10.0
+}
+
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    Some(if values.len().is_multiple_of(2) {
+        (values[mid - 1] + values[mid]) / 2.0
+    } else {
+        values[mid]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    /// 本地 HTTP 服务：每个连接读取请求后等待 delay，再返回指定状态码
+    fn stub(delay: Duration, status: u16) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                thread::spawn(move || {
+                    let mut buf = [0u8; 1024];
+                    let _ = stream.read(&mut buf);
+                    thread::sleep(delay);
+                    let _ = write!(stream, "HTTP/1.1 {} Stub\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status);
+                });
+            }
+        });
+        format!("http://127.0.0.1:{}", port)
+    }
+
+    /// 没有服务监听的本地地址
+    fn refused() -> String {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        format!("http://127.0.0.1:{}", port)
+    }
+
+    fn options(probes: u32, timeout_ms: u64) -> BenchmarkOptions {
+        BenchmarkOptions::new(Some(probes), Some(timeout_ms))
+    }
+
+    fn result<'a>(report: &'a BenchmarkReport, url: &str) -> &'a EndpointResult {
+        report.results.iter().find(|r| r.url == url).unwrap()
+    }
+
+    #[test]
+    fn ranks_by_first_byte_latency() {
+        let slow = stub(Duration::from_millis(300), 200);
+        let fast = stub(Duration::ZERO, 401);
+        let report = run(UrlTarget::Claude, &[slow.clone(), fast.clone()], options(2, 2000)).unwrap();
+
+        let urls: Vec<&str> = report.results.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(urls, vec![fast.as_str(), slow.as_str()]);
+        assert_eq!(report.winner, Some(fast.clone()));
+        for r in &report.results {
+            assert_eq!(r.errors, 0);
+            assert_eq!(r.error_rate, 0.0);
+            assert!(r.connect_ms.unwrap() <= r.first_byte_ms.unwrap());
+            assert_eq!(r.tls_ms, None);
+        }
+        assert!(result(&report, &slow).first_byte_ms.unwrap() >= 300.0);
+    }
+
+    #[test]
+    fn server_errors_count_as_failures() {
+        let broken = stub(Duration::ZERO, 503);
+        let slow = stub(Duration::from_millis(200), 200);
+        let report = run(UrlTarget::Claude, &[broken.clone(), slow.clone()], options(3, 2000)).unwrap();
+
+        let broken = result(&report, &broken);
+        assert_eq!(broken.errors, 3);
+        assert_eq!(broken.error_rate, 1.0);
+        assert_eq!(broken.first_byte_ms, None);
+        assert_eq!(broken.last_error, Some(t!("bench.server_error", status = 503)));
+        // 错误率优先于延迟
+        assert_eq!(report.results[0].url, slow);
+        assert_eq!(report.winner, Some(slow));
+    }
+
+    #[test]
+    fn timeouts_and_refused_connections_count_as_failures() {
+        let hanging = stub(Duration::from_secs(5), 200);
+        let closed = refused();
+        let report = run(UrlTarget::Claude, &[hanging.clone(), closed.clone()], options(2, 200)).unwrap();
+
+        let hanging = result(&report, &hanging);
+        assert_eq!(hanging.error_rate, 1.0);
+        assert_eq!(hanging.last_error, Some(t!("bench.timeout")));
+
+        let closed = result(&report, &closed);
+        assert_eq!(closed.error_rate, 1.0);
+        assert!(closed.last_error.as_ref().unwrap().starts_with(&t!("bench.connect_failed")));
+        assert_eq!(report.winner, None);
+    }
+
+    #[test]
+    fn invalid_and_duplicate_urls() {
+        let fast = stub(Duration::ZERO, 200);
+        let urls = [fast.clone(), format!("{}/", fast), "ftp://example.com".to_string(), " ".to_string()];
+        let report = run(UrlTarget::Claude, &urls, options(1, 2000)).unwrap();
+
+        assert_eq!(report.results.len(), 2);
+        assert_eq!(report.winner, Some(fast));
+        let invalid = result(&report, "ftp://example.com");
+        assert_eq!(invalid.error_rate, 1.0);
+        assert!(invalid.last_error.is_some());
+    }
+
+    #[test]
+    fn parses_endpoints() {
+        let e = parse_endpoint("https://user@88code.org/openai/v1").unwrap();
+        assert!(e.tls);
+        assert_eq!((e.host.as_str(), e.port), ("88code.org", 443));
+        assert_eq!((e.authority.as_str(), e.path.as_str()), ("88code.org", "/openai/v1"));
+
+        let e = parse_endpoint("http://[::1]:8080").unwrap();
+        assert!(!e.tls);
+        assert_eq!((e.host.as_str(), e.port), ("::1", 8080));
+        assert_eq!((e.authority.as_str(), e.path.as_str()), ("[::1]:8080", "/"));
+
+        assert!(parse_endpoint("88code.org").is_err());
+        assert!(parse_endpoint("http://host:port").is_err());
+    }
+
+    #[test]
+    fn clamps_options_and_takes_median() {
+        let o = BenchmarkOptions::new(Some(0), Some(1));
+        assert_eq!((o.probes, o.timeout), (1, Duration::from_millis(MIN_TIMEOUT_MS)));
+        let o = BenchmarkOptions::new(None, Some(u64::MAX));
+        assert_eq!((o.probes, o.timeout), (DEFAULT_PROBES, Duration::from_millis(MAX_TIMEOUT_MS)));
+
+        assert_eq!(median(vec![]), None);
+        assert_eq!(median(vec![3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median(vec![4.0, 1.0, 2.0, 3.0]), Some(2.5));
+    }
+}
@@ -0,0 +1,698 @@
+use crate::api_key;
+use crate::base_url::{self, UrlTarget};
+use crate::config::{get_claude_settings_path, get_codex_auth_path, get_codex_config_path};
+use crate::error::{AppError, AppResult, ErrorKind, SuggestedAction};
+use crate::i18n::t;
+use crate::vscode::TargetOutcome;
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::Argon2;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// 配置包格式版本，导入时拒绝更高版本
+pub const BUNDLE_VERSION: u32 = 1;
+
+/// 配置包中的密钥
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SecretValue {
+    /// 明文
+    Plain { value: String },
+    /// 使用口令加密（Argon2id 派生密钥 + AES-256-GCM，字段均为 base64）
+    Encrypted {
+        salt: String,
+        nonce: String,
+        ciphertext: String,
+    },
+}
+
+/// 导出时如何处理密钥
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum KeyMode {
+    /// 不导出密钥，导入时保留本机现有密钥
+    Omit,
+    Plain,
+    Encrypt,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ClaudeSection {
+    pub base_url: Option<String>,
+    pub api_key: Option<SecretValue>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CodexSection {
+    pub base_url: Option<String>,
+    pub api_key: Option<SecretValue>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct VscodeSection {
+    /// ChatGPT 扩展的 chatgpt.apiBase
+    pub api_base: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnvVar {
+    pub name: String,
+    pub value: Option<SecretValue>,
+}
+
+/// 配置包：应用管理的各项配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigBundle {
+    pub version: u32,
+    pub app_version: String,
+    /// 导出时间（Unix 秒）
+    pub created_at: u64,
+    pub claude: Option<ClaudeSection>,
+    pub codex: Option<CodexSection>,
+    pub vscode: Option<VscodeSection>,
+    #[serde(default)]
+    pub env: Vec<EnvVar>,
+}
+
+/// 导入预览中的单项变化（密钥已打码）
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedChange {
+    pub target: String,
+    pub field: String,
+    pub current: Option<String>,
+    pub incoming: Option<String>,
+    pub changed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BundlePreview {
+    pub version: u32,
+    pub app_version: String,
+    pub created_at: u64,
+    pub changes: Vec<PlannedChange>,
+}
+
+/// 本机当前由应用管理的配置值（明文）
+#[derive(Debug, Default)]
+struct CurrentValues {
+    claude_base_url: Option<String>,
+    claude_api_key: Option<String>,
+    codex_base_url: Option<String>,
+    codex_api_key: Option<String>,
+    vscode_api_base: Option<String>,
+    key88: Option<String>,
+}
+
+/// 配置包解密后的明文值
+#[derive(Debug, Default)]
+struct IncomingValues {
+    claude_base_url: Option<String>,
+    claude_api_key: Option<String>,
+    codex_base_url: Option<String>,
+    codex_api_key: Option<String>,
+    vscode_api_base: Option<String>,
+    key88: Option<String>,
+}
+
+fn read_current_values() -> CurrentValues {
+    let claude = std::fs::read_to_string(get_claude_settings_path())
+        .ok()
+        .and_then(|c| crate::jsonc::parse_jsonc(&c).ok());
+    let claude_env = |key: &str| {
+        claude
+            .as_ref()
+            .and_then(|v| v.get("env"))
+            .and_then(|env| env.get(key))
+            .and_then(|s| s.as_str())
+            .map(|s| s.to_string())
+    };
+
+    let codex_api_key = std::fs::read_to_string(get_codex_auth_path())
+        .ok()
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+        .and_then(|v| v.get("OPENAI_API_KEY").and_then(|s| s.as_str()).map(|s| s.to_string()));
+    let codex_base_url = std::fs::read_to_string(get_codex_config_path())
+        .ok()
+        .and_then(|c| toml::from_str::<toml::Value>(&c).ok())
+        .and_then(|v| {
+            v.get("model_providers")?
+                .get("88code")?
+                .get("base_url")?
+                .as_str()
+                .map(|s| s.to_string())
+        });
+
+    CurrentValues {
+        claude_base_url: claude_env("ANTHROPIC_BASE_URL"),
+        claude_api_key: claude_env("ANTHROPIC_AUTH_TOKEN"),
+        codex_base_url,
+        codex_api_key,
+        vscode_api_base: crate::vscode::current_codex_api_base(),
+        key88: crate::env_manager::read_user_env("key88"),
+    }
+}
+
+/// 由口令和盐派生 AES-256 密钥
+fn derive_key(passphrase: &str, salt: &[u8]) -> AppResult<[u8; 32]> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| AppError::new(ErrorKind::Other, t!("bundle.derive_key_failed")).with_source(e))?;
+    Ok(key)
+}
+
+fn encrypt_secret(value: &str, passphrase: &str) -> AppResult<SecretValue> {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(passphrase, &salt)?;
+    let cipher = Aes256Gcm::new_from_slice(&key).map_err(|e| AppError::new(ErrorKind::Other, t!("bundle.init_cipher_failed")).with_source(e))?;
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, value.as_bytes())
+        .map_err(|_| AppError::new(ErrorKind::Other, t!("bundle.encrypt_failed")))?;
+
+    Ok(SecretValue::Encrypted {
+        salt: BASE64.encode(salt),
+        nonce: BASE64.encode(nonce),
+        ciphertext: BASE64.encode(ciphertext),
+    })
+}
+
+fn decrypt_secret(secret: &SecretValue, passphrase: Option<&str>) -> AppResult<String> {
+    match secret {
+        SecretValue::Plain { value } => Ok(value.clone()),
+        SecretValue::Encrypted {
+            salt,
+            nonce,
+            ciphertext,
+        } => {
+            let passphrase = passphrase
+                .filter(|p| !p.is_empty())
+                .ok_or_else(|| AppError::invalid_input(t!("bundle.passphrase_required")))?;
+            let corrupted = || AppError::new(ErrorKind::Parse, t!("bundle.corrupted"));
+            let decode = |s: &str| BASE64.decode(s).map_err(|_| corrupted());
+            let (salt, nonce, ciphertext) = (decode(salt)?, decode(nonce)?, decode(ciphertext)?);
+            if nonce.len() != 12 {
+                return Err(corrupted());
+            }
+
+            let key = derive_key(passphrase, &salt)?;
+            let cipher =
+                Aes256Gcm::new_from_slice(&key).map_err(|e| AppError::new(ErrorKind::Other, t!("bundle.init_decipher_failed")).with_source(e))?;
+            let plain = cipher
+                .decrypt(Nonce::from_slice(&nonce), ciphertext.as_ref())
+                .map_err(|_| AppError::invalid_input(t!("bundle.wrong_passphrase")))?;
+            String::from_utf8(plain).map_err(|_| corrupted())
+        }
+    }
+}
+
+/// 按导出方式处理单个密钥
+fn pack_secret(
+    value: Option<String>,
+    mode: KeyMode,
+    passphrase: Option<&str>,
+) -> AppResult<Option<SecretValue>> {
+    let Some(value) = value else {
+        return Ok(None);
+    };
+    match mode {
+        KeyMode::Omit => Ok(None),
+        KeyMode::Plain => Ok(Some(SecretValue::Plain { value })),
+        KeyMode::Encrypt => {
+            let passphrase = passphrase
+                .filter(|p| !p.is_empty())
+                .ok_or_else(|| AppError::invalid_input(t!("bundle.export_passphrase_required")))?;
+            encrypt_secret(&value, passphrase).map(Some)
+        }
+    }
+}
+
+/// 根据本机当前配置生成配置包
+pub fn build_bundle(mode: KeyMode, passphrase: Option<&str>) -> AppResult<ConfigBundle> {
+    let current = read_current_values();
+
+    Ok(ConfigBundle {
+        version: BUNDLE_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        claude: Some(ClaudeSection {
+            base_url: current.claude_base_url,
+            api_key: pack_secret(current.claude_api_key, mode, passphrase)?,
+        }),
+        codex: Some(CodexSection {
+            base_url: current.codex_base_url,
+            api_key: pack_secret(current.codex_api_key, mode, passphrase)?,
+        }),
+        vscode: Some(VscodeSection {
+            api_base: current.vscode_api_base,
+        }),
+        env: vec![EnvVar {
+            name: "key88".to_string(),
+            value: pack_secret(current.key88, mode, passphrase)?,
+        }],
+    })
+}
+
+/// 导出配置包到指定文件（明文密钥时文件权限设为 0600）
+pub fn export_bundle(target: &Path, mode: KeyMode, passphrase: Option<&str>) -> AppResult<()> {
+    let bundle = build_bundle(mode, passphrase)?;
+    let json =
+        serde_json::to_string_pretty(&bundle).map_err(|e| AppError::new(ErrorKind::Other, t!("bundle.serialize_failed")).with_source(e))?;
+
+    // 配置包不属于受管配置，直接写入，不计入审计日志
+    std::fs::write(target, json).map_err(|e| AppError::io(t!("bundle.write_failed"), target, e))?;
+    if mode == KeyMode::Plain {
+        crate::permissions::restrict(target, false)?;
+    }
+
+    log::info!("已导出配置包: {:?}", target);
+    Ok(())
+}
+
+/// 读取并校验配置包
+pub fn read_bundle(path: &Path) -> AppResult<ConfigBundle> {
+    let content = std::fs::read_to_string(path).map_err(|e| AppError::io(t!("bundle.read_failed"), path, e))?;
+    let bundle: ConfigBundle =
+        serde_json::from_str(&content).map_err(|e| AppError::json(t!("bundle.invalid"), &e).with_path(path))?;
+
+    if bundle.version == 0 || bundle.version > BUNDLE_VERSION {
+        return Err(AppError::new(
+            ErrorKind::Parse,
+            t!("bundle.unsupported_version", version = bundle.version, supported = BUNDLE_VERSION),
+        ));
+    }
+
+    let urls = [
+        bundle.claude.as_ref().and_then(|c| c.base_url.as_ref()),
+        bundle.codex.as_ref().and_then(|c| c.base_url.as_ref()),
+        bundle.vscode.as_ref().and_then(|v| v.api_base.as_ref()),
+    ];
+    for url in urls.into_iter().flatten() {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(AppError::invalid_input(t!("bundle.invalid_url", url = url)));
+        }
+    }
+
+    Ok(bundle)
+}
+
+/// 解密配置包中的所有值
+fn unpack(bundle: &ConfigBundle, passphrase: Option<&str>) -> AppResult<IncomingValues> {
+    let open = |secret: Option<&SecretValue>| -> AppResult<Option<String>> {
+        secret.map(|s| decrypt_secret(s, passphrase)).transpose()
+    };
+
+    Ok(IncomingValues {
+        claude_base_url: bundle.claude.as_ref().and_then(|c| c.base_url.clone()),
+        claude_api_key: open(bundle.claude.as_ref().and_then(|c| c.api_key.as_ref()))?,
+        codex_base_url: bundle.codex.as_ref().and_then(|c| c.base_url.clone()),
+        codex_api_key: open(bundle.codex.as_ref().and_then(|c| c.api_key.as_ref()))?,
+        vscode_api_base: bundle.vscode.as_ref().and_then(|v| v.api_base.clone()),
+        key88: open(
+            bundle
+                .env
+                .iter()
+                .find(|v| v.name == "key88")
+                .and_then(|v| v.value.as_ref()),
+        )?,
+    })
+}
+
+/// 密钥打码，只保留前后各 4 位
+pub(crate) fn mask(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.len() <= 8 {
+        return "****".to_string();
+    }
+    let head: String = chars[..4].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}****{}", head, tail)
+}
+
+/// 预览导入配置包会带来的变化（不写入任何文件）
+pub fn preview_bundle(path: &Path, passphrase: Option<&str>) -> AppResult<BundlePreview> {
+    let bundle = read_bundle(path)?;
+    let incoming = unpack(&bundle, passphrase)?;
+    let current = read_current_values();
+
+    let mut changes = Vec::new();
+    let mut push = |target: &str, field: &str, current: Option<String>, incoming: Option<String>, secret: bool| {
+        // 配置包中没有的值导入时保持不变
+        let Some(incoming) = incoming else {
+            return;
+        };
+        let show = |v: &str| if secret { mask(v) } else { v.to_string() };
+        changes.push(PlannedChange {
+            target: target.to_string(),
+            field: field.to_string(),
+            changed: current.as_deref() != Some(incoming.as_str()),
+            current: current.as_deref().map(show),
+            incoming: Some(show(&incoming)),
+        });
+    };
+
+    push("Claude Code", "ANTHROPIC_BASE_URL", current.claude_base_url, incoming.claude_base_url, false);
+    push("Claude Code", "ANTHROPIC_AUTH_TOKEN", current.claude_api_key, incoming.claude_api_key, true);
+    push("Codex", "base_url", current.codex_base_url, incoming.codex_base_url, false);
+    push("Codex", "OPENAI_API_KEY", current.codex_api_key, incoming.codex_api_key, true);
+    push("VSCode", "chatgpt.apiBase", current.vscode_api_base, incoming.vscode_api_base, false);
+    push(&t!("target.env"), "key88", current.key88, incoming.key88, true);
+
+    Ok(BundlePreview {
+        version: bundle.version,
+        app_version: bundle.app_version,
+        created_at: bundle.created_at,
+        changes,
+    })
+}
+
+/// 校验后的导入计划（地址已规范化，密钥已清理）
+#[derive(Debug, Default)]
+struct ImportPlan {
+    /// (base_url, api_key)
+    claude: Option<(String, String)>,
+    codex: Option<(String, String)>,
+    vscode_api_base: Option<String>,
+    key88: Option<String>,
+    /// 缺少密钥而跳过的部分
+    skipped: Vec<String>,
+}
+
+/// 校验配置包中的所有值，任何一项无效都在写入前返回错误
+/// 配置包中省略的密钥使用本机现有密钥；两者都没有时跳过该部分
+fn plan_import(incoming: IncomingValues, current: CurrentValues) -> AppResult<ImportPlan> {
+    let mut plan = ImportPlan::default();
+
+    if let Some(base_url) = incoming.claude_base_url {
+        let base_url = base_url::normalize(UrlTarget::Claude, &base_url)?.url;
+        match incoming.claude_api_key.or(current.claude_api_key) {
+            Some(api_key) => {
+                let key = api_key::check(UrlTarget::Claude, &api_key, Some(&base_url))?.key;
+                plan.claude = Some((base_url, key));
+            }
+            None => plan.skipped.push(t!("bundle.missing_key", target = "Claude Code")),
+        }
+    }
+
+    let codex_api_key = incoming.codex_api_key.or(current.codex_api_key);
+    if let Some(base_url) = incoming.codex_base_url {
+        let base_url = base_url::normalize(UrlTarget::Codex, &base_url)?.url;
+        match &codex_api_key {
+            Some(api_key) => {
+                let key = api_key::check(UrlTarget::Codex, api_key, Some(&base_url))?.key;
+                plan.codex = Some((base_url, key));
+            }
+            None => plan.skipped.push(t!("bundle.missing_key", target = "Codex")),
+        }
+    }
+
+    if let Some(api_base) = incoming.vscode_api_base {
+        plan.vscode_api_base = Some(base_url::normalize(UrlTarget::Codex, &api_base)?.url);
+    }
+
+    if let Some(key88) = incoming.key88 {
+        plan.key88 = Some(api_key::check(UrlTarget::Codex, &key88, None)?.key);
+    }
+
+    Ok(plan)
+}
+
+/// 导入前的文件快照，导入中途失败时恢复
+struct Snapshot(Vec<(PathBuf, Option<Vec<u8>>)>);
+
+impl Snapshot {
+    fn take(paths: Vec<PathBuf>) -> Self {
+        Snapshot(
+            paths
+                .into_iter()
+                .map(|path| {
+                    let content = std::fs::read(&path).ok();
+                    (path, content)
+                })
+                .collect(),
+        )
+    }
+
+    /// 恢复到快照时的内容，导入前不存在的文件直接删除
+    fn restore(&self) {
+        for (path, content) in &self.0 {
+            let current = std::fs::read(path).ok();
+            if &current == content {
+                continue;
+            }
+            let result = match content {
+                Some(content) => crate::config::atomic_write(path, content),
+                None => crate::config::remove_file(path),
+            };
+            if let Err(e) = result {
+                log::warn!("恢复 {:?} 失败: {}", path, e);
+            }
+        }
+    }
+}
+
+/// 导入计划会写入的文件
+fn touched_paths(plan: &ImportPlan) -> AppResult<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    if plan.claude.is_some() {
+        paths.push(get_claude_settings_path());
+    }
+    if plan.codex.is_some() {
+        paths.push(get_codex_auth_path());
+        paths.push(get_codex_config_path());
+    }
+    if plan.vscode_api_base.is_some() {
+        paths.extend(crate::vscode::default_settings_targets()?);
+    }
+    if plan.key88.is_some() {
+        paths.extend(crate::env_manager::shell_config_path());
+    }
+    Ok(paths)
+}
+
+/// 按计划依次写入，遇到第一个错误即返回
+fn write_plan(plan: ImportPlan) -> AppResult<Vec<String>> {
+    let mut results = plan.skipped;
+
+    if let Some((base_url, api_key)) = plan.claude {
+        crate::claude_config::configure_claude_code(base_url, api_key)?;
+        results.push(t!("bundle.imported", target = "Claude Code"));
+    }
+
+    let codex_api_key = plan.codex.as_ref().map(|(_, key)| key.clone());
+    if let Some((base_url, api_key)) = plan.codex {
+        crate::codex_config::configure_codex(base_url, api_key)?;
+        results.push(t!("bundle.imported", target = "Codex"));
+    }
+
+    if let Some(api_base) = plan.vscode_api_base {
+        let outcomes = crate::vscode::configure_vscode_codex(api_base, codex_api_key.unwrap_or_default(), Vec::new())?;
+        for outcome in outcomes {
+            let path = Path::new(&outcome.path);
+            match &outcome.outcome {
+                TargetOutcome::ParseError { message, line, column } => {
+                    return Err(AppError::new(ErrorKind::Parse, message.clone())
+                        .with_path(path)
+                        .at(*line, *column)
+                        .with_action(SuggestedAction::EditFile));
+                }
+                TargetOutcome::Failed { message } => {
+                    return Err(AppError::new(ErrorKind::Io, message.clone()).with_path(path));
+                }
+                _ => results.push(format!("VSCode {}: {:?}", outcome.path, outcome.outcome)),
+            }
+        }
+    }
+
+    if let Some(key88) = plan.key88 {
+        crate::env_manager::set_key88_env(key88)?;
+        results.push(t!("bundle.imported", target = format!("{} key88", t!("target.env"))));
+    }
+
+    Ok(results)
+}
+
+/// 导入配置包：先校验全部内容，再通过现有的配置函数写入，返回每一部分的结果
+/// 写入中途失败时把已修改的文件恢复到导入前的状态
+pub fn apply_bundle(path: &Path, passphrase: Option<&str>) -> AppResult<Vec<String>> {
+    let bundle = read_bundle(path)?;
+    let incoming = unpack(&bundle, passphrase)?;
+    let plan = plan_import(incoming, read_current_values())?;
+
+    let snapshot = Snapshot::take(touched_paths(&plan)?);
+    match write_plan(plan) {
+        Ok(results) => {
+            log::info!("已导入配置包 {:?}: {:?}", path, results);
+            Ok(results)
+        }
+        Err(e) => {
+            snapshot.restore();
+            log::warn!("导入配置包 {:?} 失败，已恢复导入前的配置: {}", path, e);
+            Err(AppError {
+                message: t!("bundle.rolled_back", error = e.message),
+                ..e
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "88_0123456789abcdef0123456789abcdef";
+
+    #[test]
+    fn encrypted_secret_round_trips() {
+        let secret = encrypt_secret("sk-secret", "pass").unwrap();
+        assert!(matches!(secret, SecretValue::Encrypted { .. }));
+        assert_eq!(decrypt_secret(&secret, Some("pass")).unwrap(), "sk-secret");
+    }
+
+    #[test]
+    fn encryption_uses_fresh_salt_and_nonce() {
+        let (SecretValue::Encrypted { salt: s1, nonce: n1, .. }, SecretValue::Encrypted { salt: s2, nonce: n2, .. }) =
+            (encrypt_secret("v", "p").unwrap(), encrypt_secret("v", "p").unwrap())
+        else {
+            panic!("expected encrypted values");
+        };
+        assert_ne!(s1, s2);
+        assert_ne!(n1, n2);
+    }
+
+    #[test]
+    fn wrong_or_missing_passphrase_is_rejected() {
+        let secret = encrypt_secret("sk-secret", "pass").unwrap();
+        assert_eq!(decrypt_secret(&secret, Some("other")).unwrap_err().message, t!("bundle.wrong_passphrase"));
+        assert_eq!(decrypt_secret(&secret, None).unwrap_err().message, t!("bundle.passphrase_required"));
+        assert_eq!(decrypt_secret(&secret, Some("")).unwrap_err().message, t!("bundle.passphrase_required"));
+    }
+
+    #[test]
+    fn corrupted_secret_is_rejected() {
+        let SecretValue::Encrypted { salt, ciphertext, .. } = encrypt_secret("sk-secret", "pass").unwrap() else {
+            panic!("expected encrypted value");
+        };
+        let short_nonce = SecretValue::Encrypted {
+            salt: salt.clone(),
+            nonce: BASE64.encode([0u8; 8]),
+            ciphertext: ciphertext.clone(),
+        };
+        assert_eq!(decrypt_secret(&short_nonce, Some("pass")).unwrap_err().message, t!("bundle.corrupted"));
+
+        let not_base64 = SecretValue::Encrypted {
+            salt,
+            nonce: "%%%".to_string(),
+            ciphertext,
+        };
+        assert_eq!(decrypt_secret(&not_base64, Some("pass")).unwrap_err().message, t!("bundle.corrupted"));
+    }
+
+    #[test]
+    fn plain_secret_needs_no_passphrase() {
+        let secret = SecretValue::Plain { value: "v".to_string() };
+        assert_eq!(decrypt_secret(&secret, None).unwrap(), "v");
+    }
+
+    #[test]
+    fn pack_secret_follows_key_mode() {
+        assert!(pack_secret(Some("v".into()), KeyMode::Omit, None).unwrap().is_none());
+        assert!(pack_secret(None, KeyMode::Plain, None).unwrap().is_none());
+        assert!(matches!(
+            pack_secret(Some("v".into()), KeyMode::Plain, None).unwrap(),
+            Some(SecretValue::Plain { value }) if value == "v"
+        ));
+        assert_eq!(
+            pack_secret(Some("v".into()), KeyMode::Encrypt, None).unwrap_err().message,
+            t!("bundle.export_passphrase_required")
+        );
+        let packed = pack_secret(Some("v".into()), KeyMode::Encrypt, Some("p")).unwrap().unwrap();
+        assert_eq!(decrypt_secret(&packed, Some("p")).unwrap(), "v");
+    }
+
+    #[test]
+    fn mask_keeps_only_head_and_tail() {
+        assert_eq!(mask("short"), "****");
+        assert_eq!(mask("88_abcdefghijk"), "88_a****hijk");
+    }
+
+    fn write_bundle(dir: &tempfile::TempDir, json: &str) -> PathBuf {
+        let path = dir.path().join("bundle.json");
+        std::fs::write(&path, json).unwrap();
+        path
+    }
+
+    #[test]
+    fn read_bundle_checks_version_and_urls() {
+        let dir = tempfile::tempdir().unwrap();
+        let ok = r#"{"version":1,"app_version":"1.0.0","created_at":0,"claude":{"base_url":"https://88code.org/api","api_key":null},"codex":null,"vscode":null}"#;
+        assert!(read_bundle(&write_bundle(&dir, ok)).is_ok());
+
+        let future = ok.replace("\"version\":1", "\"version\":99");
+        assert!(read_bundle(&write_bundle(&dir, &future)).is_err());
+
+        let bad_url = ok.replace("https://88code.org/api", "ftp://88code.org/api");
+        assert_eq!(
+            read_bundle(&write_bundle(&dir, &bad_url)).unwrap_err().message,
+            t!("bundle.invalid_url", url = "ftp://88code.org/api")
+        );
+    }
+
+    #[test]
+    fn plan_uses_local_key_when_bundle_omits_it() {
+        let incoming = IncomingValues {
+            claude_base_url: Some("https://www.88code.org/api".to_string()),
+            codex_base_url: Some("https://88code.org/openai/v1".to_string()),
+            ..Default::default()
+        };
+        let current = CurrentValues {
+            claude_api_key: Some(format!("Bearer {}", KEY)),
+            ..Default::default()
+        };
+
+        let plan = plan_import(incoming, current).unwrap();
+        assert_eq!(plan.claude, Some(("https://www.88code.org/api".to_string(), KEY.to_string())));
+        assert!(plan.codex.is_none());
+        assert_eq!(plan.skipped, vec![t!("bundle.missing_key", target = "Codex")]);
+    }
+
+    #[test]
+    fn plan_rejects_invalid_values_before_writing() {
+        let incoming = IncomingValues {
+            codex_base_url: Some("https://88code.org/openai/v1".to_string()),
+            codex_api_key: Some("   ".to_string()),
+            ..Default::default()
+        };
+        assert!(plan_import(incoming, CurrentValues::default()).is_err());
+
+        let incoming = IncomingValues {
+            vscode_api_base: Some(" ".to_string()),
+            ..Default::default()
+        };
+        assert!(plan_import(incoming, CurrentValues::default()).is_err());
+    }
+
+    #[test]
+    fn snapshot_restores_changed_and_created_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let existing = dir.path().join("settings.json");
+        let created = dir.path().join("auth.json");
+        std::fs::write(&existing, "{}").unwrap();
+
+        let snapshot = Snapshot::take(vec![existing.clone(), created.clone()]);
+        std::fs::write(&existing, r#"{"a":1}"#).unwrap();
+        std::fs::write(&created, "{}").unwrap();
+        snapshot.restore();
+
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "{}");
+        assert!(!created.exists());
+    }
+}
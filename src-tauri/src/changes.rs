@@ -0,0 +1,550 @@
+use crate::config::{get_app_data_dir, read_json_file, write_json_file};
+use crate::error::{AppError, AppResult, ErrorKind};
+use crate::i18n::t;
+use crate::jsonc;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 变更记录文件格式版本
+const JOURNAL_VERSION: u32 = 1;
+
+/// 被修改文件的格式，决定如何读取原值和撤销
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ManagedFormat {
+    /// 严格 JSON（settings.json、auth.json、config.json）
+    Json,
+    /// 带注释的 JSON（VS Code settings.json、.code-workspace）
+    Jsonc,
+    /// Codex config.toml
+    Toml,
+    /// shell 配置文件中的 export 行
+    ShellRc,
+    /// JetBrains options/terminal.xml 中的环境变量
+    JetBrainsXml,
+    /// Windows 用户环境变量（path 字段无实际意义）
+    WindowsEnv,
+}
+
+/// 单个键的原值（None 表示应用写入前不存在）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyChange {
+    /// 键路径，空数组表示整个文件
+    pub key: Vec<String>,
+    pub original: Option<Value>,
+}
+
+/// 单个文件的变更记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileRecord {
+    pub format: ManagedFormat,
+    pub path: String,
+    /// 应用首次修改前文件是否存在
+    pub existed_before: bool,
+    /// 首次修改时间（Unix 秒）
+    pub first_changed_at: u64,
+    pub changes: Vec<KeyChange>,
+}
+
+/// 应用所做修改的记录，用于“撤销全部 88code 修改”
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeJournal {
+    pub version: u32,
+    pub files: Vec<FileRecord>,
+}
+
+impl Default for ChangeJournal {
+    fn default() -> Self {
+        Self {
+            version: JOURNAL_VERSION,
+            files: Vec::new(),
+        }
+    }
+}
+
+/// 单个文件的撤销结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RevertOutcome {
+    /// 已移除应用写入的键并恢复原值
+    Reverted,
+    /// 文件由应用创建，已删除
+    Deleted,
+    /// 文件已不存在，无需处理
+    NotFound,
+    /// 撤销失败，记录会保留以便重试
+    Failed { message: String },
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RevertResult {
+    pub format: ManagedFormat,
+    pub path: String,
+    #[serde(flatten)]
+    pub outcome: RevertOutcome,
+}
+
+/// 变更记录文件路径
+pub fn journal_path() -> PathBuf {
+    get_app_data_dir().join("changes.json")
+}
+
+/// 读取变更记录（不存在或损坏时返回空记录）
+pub fn load_journal() -> ChangeJournal {
+    let path = journal_path();
+    if !path.exists() {
+        return ChangeJournal::default();
+    }
+
+    match read_json_file(&path) {
+        Ok(journal) => journal,
+        Err(e) => {
+            log::warn!("读取变更记录失败: {}，将重新开始记录", e);
+            ChangeJournal::default()
+        }
+    }
+}
+
+fn save_journal(journal: &ChangeJournal) -> AppResult<()> {
+    write_json_file(&journal_path(), journal)
+}
+
+fn now_secs() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+/// 读取文件中某个键的当前值
+fn read_current_value(format: ManagedFormat, path: &Path, key: &[&str]) -> Option<Value> {
+    if format == ManagedFormat::WindowsEnv {
+        return key
+            .first()
+            .and_then(|name| std::env::var(name).ok())
+            .map(Value::String);
+    }
+
+    let content = fs::read_to_string(path).ok()?;
+    match format {
+        ManagedFormat::Json | ManagedFormat::Jsonc => {
+            let mut value = jsonc::parse_jsonc(&content).ok()?;
+            for k in key {
+                value = value.get(k)?.clone();
+            }
+            Some(value)
+        }
+        ManagedFormat::Toml => {
+            let mut value = toml::from_str::<toml::Value>(&content).ok()?;
+            for k in key {
+                value = value.get(k)?.clone();
+            }
+            serde_json::to_value(value).ok()
+        }
+        ManagedFormat::ShellRc => {
+            let marker = format!("export {}=", key.first()?);
+            content
+                .lines()
+                .find(|line| line.trim().starts_with(&marker))
+                .map(|line| Value::String(line.to_string()))
+        }
+        ManagedFormat::JetBrainsXml => crate::jetbrains::read_env_entry(&content, key.first()?)
+            .map(Value::String),
+        ManagedFormat::WindowsEnv => None,
+    }
+}
+
+/// 在应用修改文件之前调用：记录文件和各键的原值（同一个键只记录第一次的原值）
+/// 记录失败不影响配置本身，只输出警告
+pub fn remember(format: ManagedFormat, path: &Path, keys: &[&[&str]]) {
+    let _lock = match crate::config::lock_for_update(&journal_path()) {
+        Ok(lock) => lock,
+        Err(e) => {
+            log::warn!("变更记录被占用，本次修改未记录: {}", e);
+            return;
+        }
+    };
+    let mut journal = load_journal();
+    let path_str = path.to_string_lossy().to_string();
+
+    let idx = match journal.files.iter().position(|f| f.path == path_str) {
+        Some(idx) => idx,
+        None => {
+            journal.files.push(FileRecord {
+                format,
+                path: path_str,
+                existed_before: format == ManagedFormat::WindowsEnv || path.exists(),
+                first_changed_at: now_secs(),
+                changes: Vec::new(),
+            });
+            journal.files.len() - 1
+        }
+    };
+
+    let record = &mut journal.files[idx];
+    let mut changed = false;
+    for key in keys {
+        let key_vec: Vec<String> = key.iter().map(|k| k.to_string()).collect();
+        // 已记录整个文件时，各个键的原值已包含在其中
+        if record.changes.iter().any(|c| c.key.is_empty() || c.key == key_vec) {
+            continue;
+        }
+        record.changes.push(KeyChange {
+            key: key_vec,
+            original: read_current_value(format, path, key),
+        });
+        changed = true;
+    }
+
+    if changed {
+        if let Err(e) = save_journal(&journal) {
+            log::warn!("保存变更记录失败: {}", e);
+        }
+    }
+}
+
+/// 判断值是否只剩空对象/空数组
+fn is_effectively_empty(value: &Value) -> bool {
+    match value {
+        Value::Object(map) => map.values().all(is_effectively_empty),
+        Value::Array(items) => items.is_empty(),
+        _ => false,
+    }
+}
+
+/// 撤销 JSON/JSONC 文件中的修改（在原文本基础上修改，保留其他内容和注释）
+fn revert_json(record: &FileRecord, path: &Path) -> AppResult<RevertOutcome> {
+    let mut content = fs::read_to_string(path).map_err(|e| AppError::io(t!("fs.read_failed"), path, e))?;
+
+    // 按记录的逆序撤销，先恢复后写入的键
+    for change in record.changes.iter().rev() {
+        let key: Vec<&str> = change.key.iter().map(|k| k.as_str()).collect();
+        match (&change.original, key.is_empty()) {
+            (None, true) if !record.existed_before => {
+                crate::config::remove_file(path)?;
+                return Ok(RevertOutcome::Deleted);
+            }
+            // 文件原本存在但未能记录原内容（例如当时无法解析）：从 .bak 备份恢复
+            (None, true) => {
+                let backup = crate::config::backup_path_for(path);
+                let data = fs::read(&backup).map_err(|e| AppError::io(t!("changes.no_backup"), &backup, e))?;
+                crate::config::atomic_write(path, &data)?;
+                return Ok(RevertOutcome::Reverted);
+            }
+            (Some(original), _) => {
+                content = jsonc::set_member(&content, &key, original)
+                    .ok_or_else(|| AppError::new(ErrorKind::Other, t!("changes.cannot_restore", key = change.key.join("."))).with_path(path))?;
+            }
+            (None, false) => {
+                if let Some(updated) = jsonc::remove_member(&content, &key) {
+                    content = updated;
+                }
+            }
+        }
+    }
+
+    // 文件由应用创建，且撤销后只剩空结构：直接删除
+    if !record.existed_before
+        && jsonc::parse_jsonc(&content).is_ok_and(|v| is_effectively_empty(&v))
+    {
+        crate::config::remove_file(path)?;
+        return Ok(RevertOutcome::Deleted);
+    }
+
+    crate::config::atomic_write(path, content.as_bytes())?;
+    Ok(RevertOutcome::Reverted)
+}
+
+/// 撤销 config.toml 中的修改（在原文本基础上修改，保留注释、空行和键顺序）
+fn revert_toml(record: &FileRecord, path: &Path) -> AppResult<RevertOutcome> {
+    let mut content = fs::read_to_string(path).map_err(|e| AppError::io(t!("fs.read_failed"), path, e))?;
+    if let Err(e) = toml::from_str::<toml::Value>(&content) {
+        return Err(AppError::toml(t!("fs.parse_named_failed", file = "config.toml"), &content, &e).with_path(path));
+    }
+
+    for change in record.changes.iter().rev() {
+        let key: Vec<&str> = change.key.iter().map(|k| k.as_str()).collect();
+        if key.is_empty() {
+            continue;
+        }
+        // 删除键时一并移除留下的空父表（如撤销后为空的 model_providers）
+        let updated = match &change.original {
+            Some(original) => crate::codex_config::set_toml_member(&content, &key, original),
+            None => crate::codex_config::remove_toml_member(&content, &key),
+        };
+        content = updated.ok_or_else(|| {
+            AppError::new(ErrorKind::Other, t!("changes.cannot_restore", key = change.key.join("."))).with_path(path)
+        })?;
+    }
+
+    if !record.existed_before && content.trim().is_empty() {
+        crate::config::remove_file(path)?;
+        return Ok(RevertOutcome::Deleted);
+    }
+
+    crate::config::atomic_write(path, content.as_bytes())?;
+    Ok(RevertOutcome::Reverted)
+}
+
+/// 撤销 shell 配置文件中的 export 行
+#[cfg(not(windows))]
+fn revert_shell_rc(record: &FileRecord, path: &Path) -> AppResult<RevertOutcome> {
+    let path_buf = path.to_path_buf();
+    for change in &record.changes {
+        let Some(key) = change.key.first() else {
+            continue;
+        };
+        crate::env_manager::remove_env_from_shell_config(&path_buf, key)?;
+        if let Some(Value::String(line)) = &change.original {
+            crate::env_manager::restore_shell_line(&path_buf, line)?;
+        }
+    }
+
+    let content = fs::read_to_string(path).unwrap_or_default();
+    if !record.existed_before && content.trim().is_empty() {
+        crate::config::remove_file(path)?;
+        return Ok(RevertOutcome::Deleted);
+    }
+
+    Ok(RevertOutcome::Reverted)
+}
+
+/// 撤销 Windows 用户环境变量
+#[cfg(windows)]
+fn revert_windows_env(record: &FileRecord) -> AppResult<RevertOutcome> {
+    for change in &record.changes {
+        let Some(key) = change.key.first() else {
+            continue;
+        };
+        match &change.original {
+            // 恢复原值时不能再记录变更，否则刚清理的记录会被重新写入
+            Some(Value::String(value)) => crate::env_manager::write_windows_env(key, value)?,
+            _ => crate::env_manager::remove_windows_env(key)?,
+        }
+    }
+    Ok(RevertOutcome::Reverted)
+}
+
+/// 撤销 JetBrains terminal.xml 中的环境变量
+fn revert_jetbrains(record: &FileRecord, path: &Path) -> AppResult<RevertOutcome> {
+    let mut content = fs::read_to_string(path).map_err(|e| AppError::io(t!("fs.read_failed"), path, e))?;
+
+    for change in &record.changes {
+        let Some(key) = change.key.first() else {
+            continue;
+        };
+        content = match &change.original {
+            Some(Value::String(value)) => {
+                crate::jetbrains::upsert_terminal_envs(Some(&content), &[(key, value)])?
+            }
+            _ => crate::jetbrains::remove_env_entry(&content, key),
+        };
+    }
+
+    crate::config::atomic_write(path, content.as_bytes())?;
+    Ok(RevertOutcome::Reverted)
+}
+
+/// 撤销单个文件
+fn revert_record(record: &FileRecord) -> AppResult<RevertOutcome> {
+    let path = Path::new(&record.path);
+
+    let _lock = match record.format {
+        ManagedFormat::WindowsEnv => None,
+        _ => Some(crate::config::lock_for_update(path)?),
+    };
+    if record.format != ManagedFormat::WindowsEnv && !path.exists() {
+        return Ok(RevertOutcome::NotFound);
+    }
+
+    let outcome = match record.format {
+        ManagedFormat::Json | ManagedFormat::Jsonc => revert_json(record, path)?,
+        ManagedFormat::Toml => revert_toml(record, path)?,
+        ManagedFormat::JetBrainsXml => revert_jetbrains(record, path)?,
+        #[cfg(not(windows))]
+        ManagedFormat::ShellRc => revert_shell_rc(record, path)?,
+        #[cfg(windows)]
+        ManagedFormat::WindowsEnv => revert_windows_env(record)?,
+        #[allow(unreachable_patterns)]
+        _ => return Err(AppError::new(ErrorKind::Other, t!("changes.unsupported")).with_path(path)),
+    };
+
+    Ok(outcome)
+}
+
+/// 撤销应用记录的全部修改，返回每个文件的结果；失败的记录保留以便重试
+pub fn revert_all() -> AppResult<Vec<RevertResult>> {
+    revert_matching(|_| true)
+}
+
+/// 撤销满足条件的记录（测试中只撤销自己创建的文件）
+fn revert_matching(include: impl Fn(&FileRecord) -> bool) -> AppResult<Vec<RevertResult>> {
+    let journal = load_journal();
+    let mut results = Vec::new();
+    let mut reverted = Vec::new();
+
+    // 按首次修改的逆序撤销（撤销时不持有变更记录的锁，避免与写入配置时的加锁顺序相反）
+    for record in journal.files.into_iter().rev().filter(|record| include(record)) {
+        let outcome = match revert_record(&record) {
+            Ok(outcome) => {
+                reverted.push((record.path.clone(), record.first_changed_at));
+                outcome
+            }
+            Err(e) => RevertOutcome::Failed { message: e.to_string() },
+        };
+        log::info!("撤销修改: {} -> {:?}", record.path, outcome);
+        results.push(RevertResult {
+            format: record.format,
+            path: record.path,
+            outcome,
+        });
+    }
+
+    // 重新读取后只删除已撤销的记录，保留失败的记录和撤销期间新增的记录
+    let _lock = crate::config::lock_for_update(&journal_path())?;
+    let mut journal = load_journal();
+    journal
+        .files
+        .retain(|f| !reverted.contains(&(f.path.clone(), f.first_changed_at)));
+    save_journal(&journal)?;
+
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn record(format: ManagedFormat, path: &Path, existed_before: bool, changes: Vec<(&[&str], Option<Value>)>) -> FileRecord {
+        FileRecord {
+            format,
+            path: path.to_string_lossy().to_string(),
+            existed_before,
+            first_changed_at: 0,
+            changes: changes
+                .into_iter()
+                .map(|(key, original)| KeyChange {
+                    key: key.iter().map(|k| k.to_string()).collect(),
+                    original,
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn reverts_json_keys_and_keeps_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        fs::write(&path, "{\n  // 用户注释\n  \"model\": \"opus\",\n  \"env\": {\n    \"KEEP\": \"1\",\n    \"ANTHROPIC_BASE_URL\": \"https://88code.org/api\"\n  }\n}\n").unwrap();
+
+        let record = record(
+            ManagedFormat::Jsonc,
+            &path,
+            true,
+            vec![(&["env", "ANTHROPIC_BASE_URL"], None), (&["model"], Some(json!("sonnet")))],
+        );
+        assert!(matches!(revert_json(&record, &path).unwrap(), RevertOutcome::Reverted));
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("// 用户注释"));
+        assert_eq!(
+            jsonc::parse_jsonc(&content).unwrap(),
+            json!({ "model": "sonnet", "env": { "KEEP": "1" } })
+        );
+    }
+
+    #[test]
+    fn deletes_json_file_created_by_app() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        fs::write(&path, "{\n  \"env\": {\n    \"ANTHROPIC_BASE_URL\": \"https://88code.org/api\"\n  }\n}\n").unwrap();
+
+        let record = record(ManagedFormat::Json, &path, false, vec![(&["env", "ANTHROPIC_BASE_URL"], None)]);
+        assert!(matches!(revert_json(&record, &path).unwrap(), RevertOutcome::Deleted));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn reverts_toml_and_drops_empty_provider_table() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(&path, "model = \"gpt-5\"\nmodel_provider = \"88code\"\n\n[model_providers.88code]\nname = \"88code\"\n").unwrap();
+
+        let record = record(
+            ManagedFormat::Toml,
+            &path,
+            true,
+            vec![
+                (&["model_provider"], Some(json!("openai"))),
+                (&["model_providers", "88code"], None),
+            ],
+        );
+        assert!(matches!(revert_toml(&record, &path).unwrap(), RevertOutcome::Reverted));
+
+        let root: toml::Value = toml::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(root.get("model_provider").and_then(|v| v.as_str()), Some("openai"));
+        assert_eq!(root.get("model").and_then(|v| v.as_str()), Some("gpt-5"));
+        assert!(root.get("model_providers").is_none());
+    }
+
+    #[test]
+    fn effectively_empty_values() {
+        assert!(is_effectively_empty(&json!({})));
+        assert!(is_effectively_empty(&json!({ "env": {}, "list": [] })));
+        assert!(!is_effectively_empty(&json!({ "env": { "A": "" } })));
+        assert!(!is_effectively_empty(&json!({ "a": null })));
+    }
+
+    #[test]
+    fn revert_all_keeps_toml_comments_and_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        let original = "# 个人配置\nmodel = \"gpt-5\" # 常用模型\n\n# 快速档\n[profiles.fast]\nmodel = \"o3\"\n";
+        fs::write(&path, original).unwrap();
+
+        let keys: &[&[&str]] = &[&["model"], &["model_provider"], &["model_providers", "88code"]];
+        remember(ManagedFormat::Toml, &path, keys);
+        let mut content = original.to_string();
+        for (key, value) in [
+            (keys[0], json!("gpt-5-codex")),
+            (keys[1], json!("88code")),
+            (keys[2], json!({ "name": "88code", "base_url": "https://88code.org/openai/v1" })),
+        ] {
+            content = crate::codex_config::set_toml_member(&content, key, &value).unwrap();
+        }
+        crate::config::atomic_write(&path, content.as_bytes()).unwrap();
+        assert!(content.contains("# 常用模型"));
+        assert!(content.contains("[model_providers.88code]"));
+
+        let results = revert_matching(|r| r.path == path.to_string_lossy()).unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(matches!(results[0].outcome, RevertOutcome::Reverted));
+        assert_eq!(fs::read_to_string(&path).unwrap(), original);
+        assert!(!load_journal().files.iter().any(|f| f.path == path.to_string_lossy()));
+    }
+
+    #[test]
+    fn whole_file_revert_respects_existed_before() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+
+        fs::write(&path, "{ \"primaryApiKey\": \"sk-test\" }").unwrap();
+        let created = record(ManagedFormat::Json, &path, false, vec![(&[], None)]);
+        assert!(matches!(revert_json(&created, &path).unwrap(), RevertOutcome::Deleted));
+        assert!(!path.exists());
+
+        // 原文件存在但没有记录到原内容：没有备份时报错且不删除文件
+        let path = dir.path().join("auth.json");
+        fs::write(&path, "{ \"primaryApiKey\": \"sk-test\" }").unwrap();
+        let existed = record(ManagedFormat::Json, &path, true, vec![(&[], None)]);
+        assert!(revert_json(&existed, &path).is_err());
+        assert!(path.exists());
+
+        fs::write(crate::config::backup_path_for(&path), "{ broken").unwrap();
+        assert!(matches!(revert_json(&existed, &path).unwrap(), RevertOutcome::Reverted));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{ broken");
+    }
+}
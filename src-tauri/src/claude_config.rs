@@ -1,10 +1,12 @@
 use crate::config::{get_claude_settings_path, read_json_file};
+use crate::error::{AppError, AppResult};
+use crate::i18n::t;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
 
 /// Claude Code settings.json 的结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ClaudeSettings {
     pub env: HashMap<String, String>,
     #[serde(default)]
@@ -22,22 +24,23 @@ pub struct Permissions {
     pub deny: Vec<String>,
 }
 
-impl Default for ClaudeSettings {
-    fn default() -> Self {
-        Self {
-            env: HashMap::new(),
-            permissions: Permissions::default(),
-            extra: HashMap::new(),
-        }
-    }
-}
+/// 应用写入 settings.json 的键（用于撤销修改）
+const MANAGED_KEYS: &[&[&str]] = &[
+    &["env", "ANTHROPIC_AUTH_TOKEN"],
+    &["env", "ANTHROPIC_BASE_URL"],
+    &["env", "CLAUDE_CODE_DISABLE_NONESSENTIAL_TRAFFIC"],
+    &["permissions"],
+];
 
 /// 配置 Claude Code
-pub fn configure_claude_code(base_url: String, api_key: String) -> Result<(), String> {
+pub fn configure_claude_code(base_url: String, api_key: String) -> AppResult<()> {
     let settings_path = get_claude_settings_path();
+    // 持有写锁直到写入完成，避免读取后、写入前被其他进程修改
+    let _lock = crate::config::lock_for_update(&settings_path)?;
 
-    // 首次配置前创建备份
+    // 首次配置前创建备份，并记录原值以便撤销
     crate::config::create_backup_if_not_exists(&settings_path)?;
+    crate::changes::remember(crate::changes::ManagedFormat::Json, &settings_path, MANAGED_KEYS);
 
     // 读取现有配置JSON并提取未知字段（使用Vec保持顺序）
     let mut extra_env = Vec::new();
@@ -111,39 +114,58 @@ pub fn configure_claude_code(base_url: String, api_key: String) -> Result<(), St
     json_str.push_str("\n}\n");
 
     // 写入配置文件
-    if let Some(parent) = settings_path.parent() {
-        std::fs::create_dir_all(parent)
-            .map_err(|e| format!("创建配置目录失败: {}", e))?;
-    }
-
-    std::fs::write(&settings_path, json_str)
-        .map_err(|e| format!("写入配置文件失败: {}", e))?;
+    crate::config::write_text_file(&settings_path, &json_str)?;
 
     log::info!("Claude Code 配置成功: {:?}", settings_path);
     Ok(())
 }
 
 /// 读取当前 Claude Code 配置
-pub fn get_claude_config() -> Result<ClaudeSettings, String> {
+pub fn get_claude_config() -> AppResult<ClaudeSettings> {
     let settings_path = get_claude_settings_path();
 
     if !settings_path.exists() {
         return Ok(ClaudeSettings::default());
     }
 
+    crate::watcher::record_read(&settings_path);
     read_json_file(&settings_path)
 }
 
 /// 高级配置 Claude Code（直接写入用户提供的完整配置内容）
-pub fn configure_claude_advanced(config_content: String) -> Result<(), String> {
+pub fn configure_claude_advanced(config_content: String) -> AppResult<()> {
     let settings_path = get_claude_settings_path();
+    // 持有写锁直到写入完成，避免读取后、写入前被其他进程修改
+    let _lock = crate::config::lock_for_update(&settings_path)?;
+
+    // 按 settings.json 结构校验，存在错误时不写入（未知字段只是警告）
+    crate::schema::ensure_valid(
+        "settings.json",
+        &crate::schema::validate_claude_settings(&config_content),
+    )?;
 
     // 首次配置前创建备份
     crate::config::create_backup_if_not_exists(&settings_path)?;
 
+    // 高级配置基于前端读取的内容，外部修改后拒绝覆盖
+    crate::watcher::ensure_unchanged_since_read(&settings_path)?;
+
     // 验证JSON格式
     let new_config: Value = serde_json::from_str(&config_content)
-        .map_err(|e| format!("配置内容格式错误: {}", e))?;
+        .map_err(|e| AppError::json(t!("claude.invalid_content"), &e))?;
+
+    // 记录将被写入的键的原值以便撤销
+    let mut keys: Vec<Vec<&str>> = MANAGED_KEYS.iter().map(|k| k.to_vec()).collect();
+    if let Some(obj) = new_config.as_object() {
+        for (key, value) in obj {
+            match (key.as_str(), value.as_object()) {
+                ("env", Some(env)) => keys.extend(env.keys().map(|k| vec!["env", k.as_str()])),
+                _ => keys.push(vec![key.as_str()]),
+            }
+        }
+    }
+    let keys: Vec<&[&str]> = keys.iter().map(|k| k.as_slice()).collect();
+    crate::changes::remember(crate::changes::ManagedFormat::Json, &settings_path, &keys);
 
     // 读取现有配置，提取字段（使用Vec保持顺序）
     let mut extra_env = Vec::new();
@@ -241,14 +263,66 @@ pub fn configure_claude_advanced(config_content: String) -> Result<(), String> {
     json_str.push_str("\n}\n");
 
     // 写入配置文件
-    if let Some(parent) = settings_path.parent() {
-        std::fs::create_dir_all(parent)
-            .map_err(|e| format!("创建配置目录失败: {}", e))?;
-    }
-
-    std::fs::write(&settings_path, json_str)
-        .map_err(|e| format!("写入配置文件失败: {}", e))?;
+    crate::config::write_text_file(&settings_path, &json_str)?;
 
     log::info!("Claude Code 高级配置成功: {:?}", settings_path);
     Ok(())
 }
+
+/// 移除 settings.json 中由 88code 写入的 ANTHROPIC_* 环境变量，保留其他设置
+/// 返回被移除的键
+pub fn remove_claude_config() -> AppResult<Vec<String>> {
+    let settings_path = get_claude_settings_path();
+    // 持有写锁直到写入完成，避免读取后、写入前被其他进程修改
+    let _lock = crate::config::lock_for_update(&settings_path)?;
+    if !settings_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let original = std::fs::read_to_string(&settings_path)
+        .map_err(|e| AppError::io(t!("config.read_failed"), &settings_path, e))?;
+    let parsed = crate::jsonc::parse_jsonc(&original)
+        .map_err(|e| AppError::json(t!("config.unsafe_to_remove", file = "settings.json"), &e).with_path(&settings_path))?;
+
+    let env_keys: Vec<String> = parsed
+        .get("env")
+        .and_then(|v| v.as_object())
+        .map(|env| {
+            env.keys()
+                .filter(|k| k.starts_with("ANTHROPIC_"))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    if env_keys.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    // 在原文本基础上删除，保持其余内容和顺序不变
+    let mut content = original;
+    let mut removed = Vec::new();
+    for key in &env_keys {
+        if let Some(updated) = crate::jsonc::remove_member(&content, &["env", key]) {
+            content = updated;
+            removed.push(format!("env.{}", key));
+        }
+    }
+
+    // env 被清空时一并移除
+    let env_empty = crate::jsonc::parse_jsonc(&content)
+        .ok()
+        .and_then(|v| v.get("env").and_then(|e| e.as_object()).map(|e| e.is_empty()))
+        .unwrap_or(false);
+    if env_empty {
+        if let Some(updated) = crate::jsonc::remove_member(&content, &["env"]) {
+            content = updated;
+        }
+    }
+
+    // 首次修改前创建备份
+    crate::config::create_backup_if_not_exists(&settings_path)?;
+    crate::config::atomic_write(&settings_path, content.as_bytes())?;
+
+    log::info!("已移除 Claude Code 中的 88code 配置: {:?}", removed);
+    Ok(removed)
+}
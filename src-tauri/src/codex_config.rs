@@ -1,7 +1,10 @@
 use crate::config::{get_codex_auth_path, get_codex_config_path};
+use crate::error::{AppError, AppResult, ErrorKind};
+use crate::i18n::t;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use std::path::Path;
 
 /// 辅助函数：格式化 TOML 值为字符串
 fn format_toml_value(key: &str, value: &toml::Value) -> String {
@@ -36,14 +39,44 @@ pub struct CodexAuth {
     pub extra: HashMap<String, Value>,
 }
 
+/// 应用写入 auth.json 的键（用于撤销修改）
+const MANAGED_AUTH_KEYS: &[&[&str]] = &[&["OPENAI_API_KEY"]];
+
+/// 应用写入 config.toml 的键（用于撤销修改）
+const MANAGED_CONFIG_KEYS: &[&[&str]] = &[
+    &["model_provider"],
+    &["model"],
+    &["model_reasoning_effort"],
+    &["disable_response_storage"],
+    &["model_providers", "88code"],
+];
+
+/// 记录 auth.json 与 config.toml 中将被写入的键的原值
+fn remember_codex_changes(auth_path: &Path, config_path: &Path, extra_config_keys: &[&[&str]]) {
+    use crate::changes::{remember, ManagedFormat};
+
+    remember(ManagedFormat::Json, auth_path, MANAGED_AUTH_KEYS);
+    let config_keys: Vec<&[&str]> = MANAGED_CONFIG_KEYS
+        .iter()
+        .chain(extra_config_keys.iter())
+        .copied()
+        .collect();
+    remember(ManagedFormat::Toml, config_path, &config_keys);
+}
+
 /// 配置 Codex
-pub fn configure_codex(base_url: String, api_key: String) -> Result<(), String> {
+pub fn configure_codex(base_url: String, api_key: String) -> AppResult<()> {
     let auth_path = get_codex_auth_path();
     let config_path = get_codex_config_path();
 
-    // 首次配置前创建备份
+    // 持有两个文件的写锁直到写入完成（固定先 auth.json 后 config.toml，避免互相等待）
+    let _auth_lock = crate::config::lock_for_update(&auth_path)?;
+    let _config_lock = crate::config::lock_for_update(&config_path)?;
+
+    // 首次配置前创建备份，并记录原值以便撤销
     crate::config::create_backup_if_not_exists(&auth_path)?;
     crate::config::create_backup_if_not_exists(&config_path)?;
+    remember_codex_changes(&auth_path, &config_path, &[]);
 
     // 读取现有 auth.json，提取所有字段（使用Vec保持顺序）
     let mut extra_fields = Vec::new();
@@ -83,13 +116,7 @@ pub fn configure_codex(base_url: String, api_key: String) -> Result<(), String>
     json_str.push_str("\n}\n");
 
     // 写入 auth.json
-    if let Some(parent) = auth_path.parent() {
-        std::fs::create_dir_all(parent)
-            .map_err(|e| format!("创建 Codex 配置目录失败: {}", e))?;
-    }
-
-    std::fs::write(&auth_path, json_str)
-        .map_err(|e| format!("写入 auth.json 失败: {}", e))?;
+    crate::config::write_text_file(&auth_path, &json_str)?;
 
     // 读取现有 config.toml（如果存在），提取未知字段
     let existing_toml_content = if config_path.exists() {
@@ -141,12 +168,12 @@ pub fn configure_codex(base_url: String, api_key: String) -> Result<(), String>
     }
 
     // 按照固定顺序生成配置内容
-    let mut toml_str = format!(
+    let mut toml_str = String::from(
         r#"model_provider = "88code"
 model = "gpt-5-codex"
 model_reasoning_effort = "high"
 disable_response_storage = true
-"#
+"#,
     );
 
     // 添加额外的根级别字段
@@ -183,8 +210,7 @@ requires_openai_auth = true
     }
 
     // 写入 config.toml
-    std::fs::write(&config_path, toml_str)
-        .map_err(|e| format!("写入 config.toml 失败: {}", e))?;
+    crate::config::write_text_file(&config_path, &toml_str)?;
 
     log::info!("Codex 配置成功");
     log::info!("  base_url: {}", base_url);
@@ -195,18 +221,19 @@ requires_openai_auth = true
 }
 
 /// 读取当前 Codex 配置
-pub fn get_codex_auth() -> Result<Option<CodexAuth>, String> {
+pub fn get_codex_auth() -> AppResult<Option<CodexAuth>> {
     let auth_path = get_codex_auth_path();
 
     if !auth_path.exists() {
         return Ok(None);
     }
 
+    crate::watcher::record_read(&auth_path);
     let content = std::fs::read_to_string(&auth_path)
-        .map_err(|e| format!("读取 auth.json 失败: {}", e))?;
+        .map_err(|e| AppError::io(t!("fs.read_named_failed", file = "auth.json"), &auth_path, e))?;
 
     let auth: CodexAuth = serde_json::from_str(&content)
-        .map_err(|e| format!("解析 auth.json 失败: {}", e))?;
+        .map_err(|e| AppError::json(t!("fs.parse_named_failed", file = "auth.json"), &e).with_path(&auth_path))?;
 
     Ok(Some(auth))
 }
@@ -216,17 +243,46 @@ pub fn configure_codex_advanced(
     auth_json: String,
     config_toml: String,
     api_key: String,
-) -> Result<(), String> {
+) -> AppResult<()> {
     let auth_path = get_codex_auth_path();
     let config_path = get_codex_config_path();
 
+    // 持有两个文件的写锁直到写入完成（固定先 auth.json 后 config.toml，避免互相等待）
+    let _auth_lock = crate::config::lock_for_update(&auth_path)?;
+    let _config_lock = crate::config::lock_for_update(&config_path)?;
+
+    // 按 auth.json 与 config.toml 结构校验，存在错误时不写入（未知字段只是警告）
+    crate::schema::ensure_valid("auth.json", &crate::schema::validate_codex_auth(&auth_json))?;
+    crate::schema::ensure_valid(
+        "config.toml",
+        &crate::schema::validate_codex_config(&config_toml),
+    )?;
+
     // 首次配置前创建备份
     crate::config::create_backup_if_not_exists(&auth_path)?;
     crate::config::create_backup_if_not_exists(&config_path)?;
 
+    // 高级配置基于前端读取的内容，外部修改后拒绝覆盖
+    crate::watcher::ensure_unchanged_since_read(&auth_path)?;
+    crate::watcher::ensure_unchanged_since_read(&config_path)?;
+
     // 验证并解析 auth.json
     let new_auth_value: Value = serde_json::from_str(&auth_json)
-        .map_err(|e| format!("auth.json 格式错误: {}", e))?;
+        .map_err(|e| AppError::json(t!("config.malformed", file = "auth.json"), &e))?;
+
+    // 记录将被写入的键的原值以便撤销（config.toml 解析失败时由后面的校验报错）
+    let new_toml_keys: Vec<String> = toml::from_str::<toml::Value>(&config_toml)
+        .ok()
+        .and_then(|v| v.as_table().map(|t| t.keys().cloned().collect()))
+        .unwrap_or_default();
+    let new_toml_keys: Vec<[&str; 1]> = new_toml_keys.iter().map(|k| [k.as_str()]).collect();
+    let new_toml_keys: Vec<&[&str]> = new_toml_keys.iter().map(|k| k.as_slice()).collect();
+    remember_codex_changes(&auth_path, &config_path, &new_toml_keys);
+    if let Some(obj) = new_auth_value.as_object() {
+        let auth_keys: Vec<[&str; 1]> = obj.keys().map(|k| [k.as_str()]).collect();
+        let auth_keys: Vec<&[&str]> = auth_keys.iter().map(|k| k.as_slice()).collect();
+        crate::changes::remember(crate::changes::ManagedFormat::Json, &auth_path, &auth_keys);
+    }
 
     // 读取现有 auth.json，提取所有字段（使用Vec保持顺序）
     let mut extra_fields = Vec::new();
@@ -284,12 +340,11 @@ pub fn configure_codex_advanced(
     auth_json_str.push_str("\n}\n");
 
     // 写入 auth.json
-    std::fs::write(&auth_path, auth_json_str)
-        .map_err(|e| format!("写入 auth.json 失败: {}", e))?;
+    crate::config::write_text_file(&auth_path, &auth_json_str)?;
 
     // 解析新的config.toml
     let new_toml: toml::Value = toml::from_str(&config_toml)
-        .map_err(|e| format!("config.toml 格式错误: {}", e))?;
+        .map_err(|e| AppError::toml(t!("config.malformed", file = "config.toml"), &config_toml, &e))?;
 
     // 读取现有config.toml并提取未知字段
     let mut extra_fields = Vec::new();
@@ -379,10 +434,9 @@ pub fn configure_codex_advanced(
                         if !matches!(key.as_str(),
                             "model_provider" | "model" | "model_reasoning_effort" |
                             "disable_response_storage" | "model_providers"
-                        ) {
-                            if !extra_fields.iter().any(|(k, _)| k == key) {
-                                extra_fields.push((key.clone(), value.clone()));
-                            }
+                        ) && !extra_fields.iter().any(|(k, _)| k == key)
+                        {
+                            extra_fields.push((key.clone(), value.clone()));
                         }
                     }
 
@@ -393,10 +447,9 @@ pub fn configure_codex_advanced(
                             for (key, value) in code88 {
                                 if !matches!(key.as_str(),
                                     "name" | "base_url" | "wire_api" | "env_key" | "requires_openai_auth"
-                                ) {
-                                    if !extra_88code_fields.iter().any(|(k, _)| k == key) {
-                                        extra_88code_fields.push((key.clone(), value.clone()));
-                                    }
+                                ) && !extra_88code_fields.iter().any(|(k, _)| k == key)
+                                {
+                                    extra_88code_fields.push((key.clone(), value.clone()));
                                 }
                             }
                         }
@@ -457,8 +510,7 @@ requires_openai_auth = {}
     }
 
     // 写入config.toml
-    std::fs::write(&config_path, toml_str)
-        .map_err(|e| format!("写入 config.toml 失败: {}", e))?;
+    crate::config::write_text_file(&config_path, &toml_str)?;
 
     log::info!("Codex 高级配置成功");
     log::info!("  auth.json: {:?}", auth_path);
@@ -466,3 +518,287 @@ requires_openai_auth = {}
 
     Ok(())
 }
+
+/// 解析 TOML 表头（如 "[model_providers.88code]"），返回去掉引号的键路径
+fn parse_table_header(line: &str) -> Option<Vec<String>> {
+    let trimmed = line.split('#').next().unwrap_or("").trim();
+    // 多行数组中的元素（如 "[1, 2],"）不是表头
+    if !trimmed.ends_with(']') || trimmed.contains(',') {
+        return None;
+    }
+    let inner = trimmed
+        .strip_prefix("[[")
+        .and_then(|s| s.split(']').next())
+        .or_else(|| trimmed.strip_prefix('[').and_then(|s| s.split(']').next()))?;
+    Some(
+        inner
+            .split('.')
+            .map(|part| part.trim().trim_matches('"').trim_matches('\'').to_string())
+            .collect(),
+    )
+}
+
+/// 解析 "key = value" 行，返回去掉引号的键和值文本
+fn parse_key_line(line: &str) -> Option<(String, &str)> {
+    let (key, value) = line.split_once('=')?;
+    let key = key.trim().trim_matches('"').trim_matches('\'');
+    Some((key.to_string(), value.trim()))
+}
+
+/// 在原文本基础上移除 [model_providers.88code] 表和指向它的 model_provider，保留其余内容
+/// 返回新内容和被移除的键
+fn strip_88code_provider(content: &str) -> (String, Vec<String>) {
+    let mut lines = Vec::new();
+    let mut removed = Vec::new();
+    let mut current_table: Vec<String> = Vec::new();
+    let mut skipping = false;
+
+    for line in content.lines() {
+        if let Some(header) = parse_table_header(line) {
+            // [model_providers.88code] 及其子表整体跳过
+            skipping = header.len() >= 2 && header[0] == "model_providers" && header[1] == "88code";
+            if skipping && header.len() == 2 {
+                removed.push("model_providers.88code".to_string());
+            }
+            current_table = header;
+            if skipping {
+                continue;
+            }
+        } else if skipping {
+            continue;
+        } else if let Some((key, value)) = parse_key_line(line) {
+            let value = value.split('#').next().unwrap_or("").trim();
+            // 根级别指向 88code 的 model_provider
+            if current_table.is_empty() && key == "model_provider" && value.trim_matches('"') == "88code" {
+                removed.push("model_provider".to_string());
+                continue;
+            }
+            // [model_providers] 表中的内联写法：88code = { ... }
+            if current_table == ["model_providers"] && key == "88code" {
+                removed.push("model_providers.88code".to_string());
+                continue;
+            }
+        }
+        lines.push(line);
+    }
+
+    let mut result = lines.join("\n").trim_end().to_string();
+    if !result.is_empty() {
+        result.push('\n');
+    }
+    (result, removed)
+}
+
+/// 把 JSON 值转换为 TOML 值（null 无法表示，返回 None）
+fn json_to_toml_value(value: &Value) -> Option<toml_edit::Value> {
+    Some(match value {
+        Value::Null => return None,
+        Value::Bool(b) => (*b).into(),
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => i.into(),
+            None => n.as_f64()?.into(),
+        },
+        Value::String(s) => s.as_str().into(),
+        Value::Array(items) => {
+            let mut array = toml_edit::Array::new();
+            for item in items {
+                array.push(json_to_toml_value(item)?);
+            }
+            array.into()
+        }
+        Value::Object(map) => {
+            let mut table = toml_edit::InlineTable::new();
+            for (key, item) in map {
+                table.insert(key, json_to_toml_value(item)?);
+            }
+            table.into()
+        }
+    })
+}
+
+/// 把 JSON 值转换为 TOML 条目：对象写成 [表]，其余写成值
+fn json_to_toml_item(value: &Value) -> Option<toml_edit::Item> {
+    match value {
+        Value::Object(map) => {
+            let mut table = toml_edit::Table::new();
+            for (key, item) in map {
+                table.insert(key, json_to_toml_item(item)?);
+            }
+            Some(toml_edit::Item::Table(table))
+        }
+        _ => json_to_toml_value(value).map(toml_edit::Item::Value),
+    }
+}
+
+/// 设置键路径对应的值（在原文本基础上修改，保留注释、空行和键顺序）
+/// 缺少的父表会被创建；内容无法解析或父路径不是表时返回 None
+pub(crate) fn set_toml_member(content: &str, path: &[&str], value: &Value) -> Option<String> {
+    let (last, parents) = path.split_last()?;
+    let mut doc = content.parse::<toml_edit::Document>().ok()?;
+    let mut table: &mut dyn toml_edit::TableLike = doc.as_table_mut();
+    for parent in parents {
+        if table.get(parent).is_none() {
+            let mut created = toml_edit::Table::new();
+            created.set_implicit(true);
+            table.insert(parent, toml_edit::Item::Table(created));
+        }
+        table = table.get_mut(parent)?.as_table_like_mut()?;
+    }
+
+    match table.get_mut(last) {
+        // 已存在的值只替换值本身，保留行尾注释
+        Some(toml_edit::Item::Value(existing)) if !value.is_object() => {
+            let decor = existing.decor().clone();
+            *existing = json_to_toml_value(value)?;
+            *existing.decor_mut() = decor;
+        }
+        _ => {
+            table.insert(last, json_to_toml_item(value)?);
+        }
+    }
+    Some(doc.to_string())
+}
+
+/// 删除键路径对应的成员（在原文本基础上修改），删除后留下的空父表一并移除
+/// 成员不存在时返回原内容，内容无法解析时返回 None
+pub(crate) fn remove_toml_member(content: &str, path: &[&str]) -> Option<String> {
+    let mut doc = content.parse::<toml_edit::Document>().ok()?;
+    remove_toml_item(doc.as_table_mut(), path);
+    Some(doc.to_string())
+}
+
+fn remove_toml_item(table: &mut dyn toml_edit::TableLike, path: &[&str]) {
+    let Some((first, rest)) = path.split_first() else {
+        return;
+    };
+    if rest.is_empty() {
+        table.remove(first);
+        return;
+    }
+    let Some(child) = table.get_mut(first).and_then(|item| item.as_table_like_mut()) else {
+        return;
+    };
+    let had_members = !child.is_empty();
+    remove_toml_item(child, rest);
+    if had_members && child.is_empty() {
+        table.remove(first);
+    }
+}
+
+/// 移除 88code 写入的 Codex 配置（auth.json 中的 OPENAI_API_KEY、config.toml 中的 88code provider），保留其他设置
+/// 返回被移除的键（带文件名前缀）
+pub fn remove_codex_config() -> AppResult<Vec<String>> {
+    let auth_path = get_codex_auth_path();
+    let config_path = get_codex_config_path();
+
+    // 持有两个文件的写锁直到写入完成（固定先 auth.json 后 config.toml，避免互相等待）
+    let _auth_lock = crate::config::lock_for_update(&auth_path)?;
+    let _config_lock = crate::config::lock_for_update(&config_path)?;
+    let mut removed = Vec::new();
+
+    if auth_path.exists() {
+        let original = std::fs::read_to_string(&auth_path)
+            .map_err(|e| AppError::io(t!("fs.read_named_failed", file = "auth.json"), &auth_path, e))?;
+        crate::jsonc::parse_jsonc(&original)
+            .map_err(|e| AppError::json(t!("config.unsafe_to_remove", file = "auth.json"), &e).with_path(&auth_path))?;
+
+        if let Some(content) = crate::jsonc::remove_member(&original, &["OPENAI_API_KEY"]) {
+            crate::config::create_backup_if_not_exists(&auth_path)?;
+            crate::config::atomic_write(&auth_path, content.as_bytes())?;
+            removed.push("auth.json: OPENAI_API_KEY".to_string());
+        }
+    }
+
+    if config_path.exists() {
+        let original = std::fs::read_to_string(&config_path)
+            .map_err(|e| AppError::io(t!("fs.read_named_failed", file = "config.toml"), &config_path, e))?;
+        toml::from_str::<toml::Value>(&original).map_err(|e| {
+            AppError::toml(t!("config.unsafe_to_remove", file = "config.toml"), &original, &e).with_path(&config_path)
+        })?;
+
+        let (content, keys) = strip_88code_provider(&original);
+        if !keys.is_empty() {
+            // 文本删除后必须仍是合法 TOML，否则不写入
+            toml::from_str::<toml::Value>(&content).map_err(|e| {
+                AppError::new(ErrorKind::Parse, t!("codex.remove_breaks_toml"))
+                    .with_path(&config_path)
+                    .with_source(e.message())
+            })?;
+
+            crate::config::create_backup_if_not_exists(&config_path)?;
+            crate::config::atomic_write(&config_path, content.as_bytes())?;
+            removed.extend(keys.into_iter().map(|k| format!("config.toml: {}", k)));
+        }
+    }
+
+    log::info!("已移除 Codex 中的 88code 配置: {:?}", removed);
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_provider_table_and_reference() {
+        let content = r#"# 用户注释
+model = "gpt-5"
+model_provider = "88code" # 88code
+
+[model_providers.88code]
+name = "88code"
+base_url = "https://88code.org/openai/v1"
+
+[model_providers.88code.http_headers]
+X-Test = "1"
+
+[model_providers.other]
+name = "other"
+"#;
+        let (result, removed) = strip_88code_provider(content);
+        assert_eq!(removed, vec!["model_provider", "model_providers.88code"]);
+        assert_eq!(
+            result,
+            "# 用户注释\nmodel = \"gpt-5\"\n\n[model_providers.other]\nname = \"other\"\n"
+        );
+    }
+
+    #[test]
+    fn keeps_other_model_providers() {
+        let content = "model_provider = \"openai\"\n\n[profiles.work]\nmodel_provider = \"88code\"\n";
+        let (result, removed) = strip_88code_provider(content);
+        assert!(removed.is_empty());
+        assert_eq!(result, content);
+    }
+
+    #[test]
+    fn strips_quoted_header_and_inline_table() {
+        let (result, removed) = strip_88code_provider("[model_providers.\"88code\"]\nname = \"88code\"\n");
+        assert_eq!(removed, vec!["model_providers.88code"]);
+        assert_eq!(result, "");
+
+        let content = "[model_providers]\n88code = { name = \"88code\" }\nother = { name = \"other\" }\n";
+        let (result, removed) = strip_88code_provider(content);
+        assert_eq!(removed, vec!["model_providers.88code"]);
+        assert_eq!(result, "[model_providers]\nother = { name = \"other\" }\n");
+    }
+
+    #[test]
+    fn array_elements_are_not_table_headers() {
+        let content = "[model_providers.other]\nargs = [\n  [1, 2],\n]\n";
+        let (result, removed) = strip_88code_provider(content);
+        assert!(removed.is_empty());
+        assert_eq!(result, content);
+    }
+
+    #[test]
+    fn parses_table_headers() {
+        assert_eq!(
+            parse_table_header("[model_providers.88code] # x"),
+            Some(vec!["model_providers".to_string(), "88code".to_string()])
+        );
+        assert_eq!(parse_table_header("[[profiles]]"), Some(vec!["profiles".to_string()]));
+        assert_eq!(parse_table_header("key = [1]"), None);
+        assert_eq!(parse_table_header("[1, 2]"), None);
+    }
+}
@@ -1,75 +1,227 @@
+use crate::account;
+use crate::api_key;
+use crate::base_url::{self, UrlTarget};
+use crate::benchmark;
+use crate::bundle;
+use crate::changes;
 use crate::claude_config;
 use crate::codex_config;
 use crate::config;
 use crate::env_manager;
+use crate::error::{AppError, ErrorKind, SuggestedAction};
+use crate::history;
+use crate::i18n::{self, t};
+use crate::jetbrains;
+use crate::manifest;
+use crate::permissions;
+use crate::presets;
+use crate::relay;
+use crate::schema;
+use crate::sessions;
+use crate::usage;
 use crate::vscode;
-
-/// 默认的 Base URL
-const DEFAULT_CLAUDE_BASE_URL: &str = "https://www.88code.org/api";
-const DEFAULT_CODEX_BASE_URL: &str = "https://88code.org/openai/v1";
+use crate::watcher;
+
+/// 在阻塞线程池中执行耗时操作（网络请求、扫描大量文件），避免占用异步运行时的工作线程
+async fn blocking<T, F>(task: F) -> Result<T, AppError>
+where
+    F: FnOnce() -> Result<T, AppError> + Send + 'static,
+    T: Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(task)
+        .await
+        .map_err(|e| AppError::new(ErrorKind::Other, t!("task.failed")).with_source(e))?
+}
 
 /// 配置 Claude Code
 #[tauri::command]
-pub async fn configure_claude_code(base_url: String, api_key: String) -> Result<String, String> {
-    // 验证输入
-    if api_key.trim().is_empty() {
-        return Err("API 密钥不能为空".to_string());
-    }
-
-    // 如果 base_url 为空，使用 Claude 默认值
-    let base_url = if base_url.trim().is_empty() {
-        DEFAULT_CLAUDE_BASE_URL.to_string()
-    } else {
-        base_url.trim().to_string()
-    };
+pub async fn configure_claude_code(base_url: String, api_key: String) -> Result<String, AppError> {
+    let _op = history::begin("configure_claude_code");
+    // 如果 base_url 为空，使用 Claude 默认值，否则规范化
+    let base_url = base_url::resolve(UrlTarget::Claude, &base_url)?;
+    // 验证并清理密钥
+    let key = api_key::check(UrlTarget::Claude, &api_key, Some(&base_url.url))?;
 
     // 配置 Claude Code
-    claude_config::configure_claude_code(base_url, api_key)?;
+    claude_config::configure_claude_code(base_url.url.clone(), key.key.clone())?;
 
-    Ok("Claude Code 配置成功！".to_string())
+    Ok(key.annotate(base_url.annotate(t!("claude.configured"))))
 }
 
 /// 配置 Codex 并设置环境变量
 #[tauri::command]
-pub async fn configure_codex(base_url: String, api_key: String) -> Result<String, String> {
-    // 验证输入
-    if api_key.trim().is_empty() {
-        return Err("API 密钥不能为空".to_string());
-    }
-
-    // 如果 base_url 为空，使用 Codex 默认值
-    let base_url = if base_url.trim().is_empty() {
-        DEFAULT_CODEX_BASE_URL.to_string()
-    } else {
-        base_url.trim().to_string()
-    };
+pub async fn configure_codex(base_url: String, api_key: String) -> Result<String, AppError> {
+    let _op = history::begin("configure_codex");
+    // 如果 base_url 为空，使用 Codex 默认值，否则规范化
+    let base_url = base_url::resolve(UrlTarget::Codex, &base_url)?;
+    // 验证并清理密钥
+    let key = api_key::check(UrlTarget::Codex, &api_key, Some(&base_url.url))?;
 
     // 配置 Codex
-    codex_config::configure_codex(base_url, api_key.clone())?;
+    codex_config::configure_codex(base_url.url.clone(), key.key.clone())?;
 
     // 设置环境变量 key88
-    env_manager::set_key88_env(api_key)?;
+    env_manager::set_key88_env(key.key.clone())?;
 
     #[cfg(windows)]
     {
-        Ok("Codex 配置成功！环境变量 key88 已设置，请重启 Codex 以使环境变量生效。".to_string())
+        Ok(key.annotate(base_url.annotate(t!("codex.configured_windows"))))
     }
 
     #[cfg(not(windows))]
     {
-        Ok("Codex 配置成功！环境变量 key88 已添加到 shell 配置文件，请重启终端或运行 'source ~/.zshrc' (或相应的配置文件) 以使环境变量生效。".to_string())
+        Ok(key.annotate(base_url.annotate(t!("codex.configured_unix"))))
+    }
+}
+
+/// 检查并规范化 Base URL（不写入任何文件），供界面在输入时提示
+#[tauri::command]
+pub async fn check_base_url(target: UrlTarget, url: String) -> Result<base_url::NormalizedUrl, AppError> {
+    base_url::normalize(target, &url)
+}
+
+/// 检查 API 密钥格式及是否填错位置（不写入任何文件），供界面在输入时提示
+#[tauri::command]
+pub async fn check_api_key(
+    target: UrlTarget,
+    api_key: String,
+    base_url: Option<String>,
+) -> Result<api_key::KeyCheck, AppError> {
+    api_key::check(target, &api_key, base_url.as_deref())
+}
+
+/// 获取服务地址预设（内置预设 + 应用配置目录中的 presets.json）
+#[tauri::command]
+pub async fn list_presets() -> Result<presets::PresetList, AppError> {
+    Ok(presets::load())
+}
+
+/// 测试多个服务地址的连接、TLS 握手和首字节延迟并排序（urls 为空时测试所有预设地址）；
+/// 提供 api_key 时把最快的地址写入对应客户端的配置
+#[tauri::command]
+pub async fn benchmark_endpoints(
+    target: UrlTarget,
+    urls: Vec<String>,
+    probes: Option<u32>,
+    timeout_ms: Option<u64>,
+    api_key: Option<String>,
+) -> Result<benchmark::BenchmarkReport, AppError> {
+    let options = benchmark::BenchmarkOptions::new(probes, timeout_ms);
+    let mut report = blocking(move || benchmark::run(target, &urls, options)).await?;
+
+    if let Some(api_key) = api_key {
+        let winner = report.winner.clone().ok_or_else(|| {
+            AppError::new(ErrorKind::Other, t!("bench.no_winner")).with_action(SuggestedAction::Retry)
+        })?;
+        let message = match target {
+            UrlTarget::Claude => configure_claude_code(winner, api_key).await?,
+            UrlTarget::Codex => configure_codex(winner, api_key).await?,
+        };
+        report.applied = Some(message);
     }
+
+    Ok(report)
+}
+
+/// 查询客户端当前配置的密钥的套餐、剩余额度和近期用量（60 秒内重复查询使用缓存）
+#[tauri::command]
+pub async fn get_account_status(target: UrlTarget, refresh: Option<bool>) -> Result<account::AccountStatus, AppError> {
+    blocking(move || account::status(target, refresh.unwrap_or(false))).await
+}
+
+/// 统计本机 Claude Code 与 Codex 会话日志中的用量（since、until 为 UTC 日期 YYYY-MM-DD）
+#[tauri::command]
+pub async fn get_usage_stats(since: Option<String>, until: Option<String>) -> Result<usage::UsageReport, AppError> {
+    blocking(move || usage::collect(since.as_deref(), until.as_deref())).await
+}
+
+/// 将本地用量统计明细导出为 CSV
+#[tauri::command]
+pub async fn export_usage_csv(
+    target: String,
+    since: Option<String>,
+    until: Option<String>,
+) -> Result<String, AppError> {
+    let path = std::path::PathBuf::from(&target);
+    let count = blocking(move || usage::export_csv(&path, since.as_deref(), until.as_deref())).await?;
+    Ok(t!("usage.exported", count = count, path = target))
+}
+
+/// 列出本机 Claude Code 与 Codex 的历史会话（可按客户端和项目过滤）
+#[tauri::command]
+pub async fn list_sessions(
+    client: Option<UrlTarget>,
+    project: Option<String>,
+) -> Result<Vec<sessions::SessionSummary>, AppError> {
+    blocking(move || Ok(sessions::list(client, project.as_deref()))).await
+}
+
+/// 在历史会话的消息中全文搜索
+#[tauri::command]
+pub async fn search_sessions(
+    query: String,
+    client: Option<UrlTarget>,
+    project: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<sessions::SearchHit>, AppError> {
+    blocking(move || sessions::search(&query, client, project.as_deref(), limit)).await
+}
+
+/// 读取单个会话的完整记录
+#[tauri::command]
+pub async fn get_session(path: String) -> Result<sessions::Transcript, AppError> {
+    blocking(move || sessions::get(std::path::Path::new(&path))).await
+}
+
+/// 将会话导出为 Markdown
+#[tauri::command]
+pub async fn export_session_markdown(path: String, target: String) -> Result<String, AppError> {
+    let target_path = std::path::PathBuf::from(&target);
+    blocking(move || sessions::export_markdown(std::path::Path::new(&path), &target_path)).await?;
+    Ok(t!("session.exported", path = target))
+}
+
+/// 启动本地中转，apply_config 为 true 时将客户端配置改为指向中转
+#[tauri::command]
+pub async fn start_relay(config: relay::RelayConfig, apply_config: Option<bool>) -> Result<relay::RelayStatus, AppError> {
+    let _op = history::begin("start_relay");
+    relay::start(config, apply_config.unwrap_or(false))
+}
+
+/// 运行中更新中转的上游地址、密钥和代理
+#[tauri::command]
+pub async fn update_relay(config: relay::RelayConfig) -> Result<relay::RelayStatus, AppError> {
+    relay::update(config)
+}
+
+/// 停止本地中转，restore_config 为 true 时将客户端配置恢复为启动前的地址和密钥
+#[tauri::command]
+pub async fn stop_relay(restore_config: Option<bool>) -> Result<relay::RelayStatus, AppError> {
+    let _op = history::begin("stop_relay");
+    relay::stop(restore_config.unwrap_or(true))
+}
+
+/// 获取本地中转状态和最近的请求记录
+#[tauri::command]
+pub async fn get_relay_status() -> Result<relay::RelayStatus, AppError> {
+    Ok(relay::status())
 }
 
 /// 获取配置路径信息
 #[tauri::command]
-pub async fn get_config_paths() -> Result<config::ConfigPaths, String> {
+pub async fn get_config_paths() -> Result<config::ConfigPaths, AppError> {
     Ok(config::get_config_paths_info())
 }
 
+/// 获取所有受管配置文件的当前状态（配合 config-file-changed 事件刷新界面）
+#[tauri::command]
+pub async fn get_config_file_status() -> Result<Vec<watcher::ConfigFileStatus>, AppError> {
+    Ok(watcher::all_file_status())
+}
+
 /// 读取当前 Claude Code 配置
 #[tauri::command]
-pub async fn get_current_claude_config() -> Result<Option<claude_config::ClaudeSettings>, String> {
+pub async fn get_current_claude_config() -> Result<Option<claude_config::ClaudeSettings>, AppError> {
     match claude_config::get_claude_config() {
         Ok(settings) => Ok(Some(settings)),
         Err(_) => Ok(None),
@@ -78,52 +230,141 @@ pub async fn get_current_claude_config() -> Result<Option<claude_config::ClaudeS
 
 /// 读取当前 Codex 配置
 #[tauri::command]
-pub async fn get_current_codex_auth() -> Result<Option<codex_config::CodexAuth>, String> {
+pub async fn get_current_codex_auth() -> Result<Option<codex_config::CodexAuth>, AppError> {
     codex_config::get_codex_auth()
 }
 
+/// 读取高级配置编辑器使用的当前文件内容，保存时据此检查文件是否被外部修改
+#[tauri::command]
+pub async fn read_config_for_edit(target: UrlTarget) -> Result<watcher::EditableConfig, AppError> {
+    watcher::read_config_for_edit(target)
+}
+
 /// 配置 VSCode Claude 扩展
 #[tauri::command]
-pub async fn configure_vscode_claude(base_url: String, api_key: String) -> Result<String, String> {
-    // 如果 api_key 为空，使用默认值 "key"
-    let api_key = if api_key.trim().is_empty() {
-        "key".to_string()
-    } else {
-        api_key.trim().to_string()
-    };
+pub async fn configure_vscode_claude(base_url: String, api_key: String) -> Result<String, AppError> {
+    let _op = history::begin("configure_vscode_claude");
+    // 扩展实际使用 settings.json 中的密钥，未填写时写入占位值 "key" 并明确告知
+    if api_key.trim().is_empty() || api_key.trim() == "key" {
+        let message = vscode::configure_vscode_claude("key".to_string(), base_url)?;
+        return Ok(format!("{}\n{}", message, t!("key.vscode_placeholder")));
+    }
 
     // VSCode Claude 扩展只需要 API Key，base_url 不做检查
-    vscode::configure_vscode_claude(api_key, base_url)
+    let key = api_key::check(UrlTarget::Claude, &api_key, None)?;
+    let message = vscode::configure_vscode_claude(key.key.clone(), base_url)?;
+    Ok(key.annotate(message))
 }
 
-/// 配置 VSCode Codex 扩展
+/// 配置 VSCode Codex 扩展（targets 为所选 settings.json 路径，未传时配置全部已安装的编辑器）
+/// 返回每个目标的配置结果
 #[tauri::command]
-pub async fn configure_vscode_codex(base_url: String, api_key: String) -> Result<String, String> {
+pub async fn configure_vscode_codex(
+    base_url: String,
+    api_key: String,
+    targets: Option<Vec<String>>,
+) -> Result<Vec<vscode::TargetResult>, AppError> {
+    let _op = history::begin("configure_vscode_codex");
     if api_key.trim().is_empty() {
-        return Err("API 密钥不能为空".to_string());
+        return Err(AppError::invalid_input(t!("input.api_key_empty")));
     }
 
-    // 如果 base_url 为空，使用 Codex 默认值
-    let base_url = if base_url.trim().is_empty() {
-        DEFAULT_CODEX_BASE_URL.to_string()
-    } else {
-        base_url.trim().to_string()
-    };
+    // 如果 base_url 为空，使用 Codex 默认值，否则规范化
+    let base_url = base_url::resolve(UrlTarget::Codex, &base_url)?;
 
-    vscode::configure_vscode_codex(base_url, api_key)
+    vscode::configure_vscode_codex(base_url.url, api_key, targets.unwrap_or_default())
+}
+
+/// 为指定项目配置 VSCode Codex 扩展（target 为项目文件夹或 .code-workspace 文件）
+#[tauri::command]
+pub async fn configure_vscode_workspace_codex(
+    base_url: String,
+    target: String,
+) -> Result<vscode::TargetResult, AppError> {
+    let _op = history::begin("configure_vscode_workspace_codex");
+    // 如果 base_url 为空，使用 Codex 默认值，否则规范化
+    let base_url = base_url::resolve(UrlTarget::Codex, &base_url)?;
+
+    vscode::configure_vscode_workspace_codex(base_url.url, target)
+}
+
+/// 查询项目中生效的 chatgpt.apiBase 及其来源
+#[tauri::command]
+pub async fn get_effective_vscode_codex_setting(
+    target: String,
+    user_settings: Option<String>,
+) -> Result<vscode::EffectiveSetting, AppError> {
+    vscode::effective_codex_setting(target, user_settings)
 }
 
 /// 获取 VSCode 配置路径
 #[tauri::command]
-pub async fn get_vscode_paths() -> Result<Vec<String>, String> {
+pub async fn get_vscode_paths() -> Result<Vec<String>, AppError> {
     Ok(vscode::get_vscode_paths_info())
 }
 
+/// 获取检测到的编辑器安装（VS Code/Cursor/Windsurf/Trae/Flatpak/Snap/远程服务端）
+#[tauri::command]
+pub async fn get_vscode_installations() -> Result<Vec<vscode::EditorInstallation>, AppError> {
+    Ok(vscode::detect_installations())
+}
+
+/// 获取检测到的 JetBrains IDE 列表
+#[tauri::command]
+pub async fn get_jetbrains_ides() -> Result<Vec<jetbrains::JetBrainsIde>, AppError> {
+    Ok(jetbrains::discover_ides())
+}
+
+/// 配置 JetBrains Claude Code 插件（targets 为空时配置全部检测到的 IDE）
+#[tauri::command]
+pub async fn configure_jetbrains_claude(
+    base_url: String,
+    api_key: String,
+    targets: Vec<String>,
+) -> Result<String, AppError> {
+    let _op = history::begin("configure_jetbrains_claude");
+    // 如果 base_url 为空，使用 Claude 默认值，否则规范化
+    let base_url = base_url::resolve(UrlTarget::Claude, &base_url)?;
+    let key = api_key::check(UrlTarget::Claude, &api_key, Some(&base_url.url))?;
+
+    let message = jetbrains::configure_jetbrains_claude(base_url.url.clone(), key.key.clone(), targets)?;
+    Ok(key.annotate(base_url.annotate(message)))
+}
+
+/// 配置 JetBrains Codex 插件（targets 为空时配置全部检测到的 IDE）
+#[tauri::command]
+pub async fn configure_jetbrains_codex(api_key: String, targets: Vec<String>) -> Result<String, AppError> {
+    let _op = history::begin("configure_jetbrains_codex");
+    let key = api_key::check(UrlTarget::Codex, &api_key, None)?;
+
+    let message = jetbrains::configure_jetbrains_codex(key.key.clone(), targets)?;
+    Ok(key.annotate(message))
+}
+
 /// 高级配置 Claude Code（允许用户自定义完整配置内容）
 #[tauri::command]
-pub async fn configure_claude_advanced(config_content: String) -> Result<String, String> {
+pub async fn configure_claude_advanced(config_content: String) -> Result<String, AppError> {
+    let _op = history::begin("configure_claude_advanced");
     claude_config::configure_claude_advanced(config_content)?;
-    Ok("Claude Code 高级配置成功！".to_string())
+    Ok(t!("claude.advanced_configured"))
+}
+
+/// 校验 Claude Code 高级配置内容，返回诊断（供编辑器实时显示）
+#[tauri::command]
+pub async fn validate_claude_advanced(config_content: String) -> Result<Vec<schema::Diagnostic>, AppError> {
+    Ok(schema::validate_claude_settings(&config_content))
+}
+
+/// 校验 Codex 高级配置内容，返回 auth.json 与 config.toml 的诊断
+#[tauri::command]
+pub async fn validate_codex_advanced(
+    auth_json: String,
+    config_toml: String,
+) -> Result<schema::CodexDiagnostics, AppError> {
+    Ok(schema::CodexDiagnostics {
+        auth: schema::validate_codex_auth(&auth_json),
+        config: schema::validate_codex_config(&config_toml),
+    })
 }
 
 /// 高级配置 Codex（允许用户自定义完整配置内容）
@@ -132,72 +373,158 @@ pub async fn configure_codex_advanced(
     auth_json: String,
     config_toml: String,
     api_key: String,
-) -> Result<String, String> {
-    codex_config::configure_codex_advanced(auth_json, config_toml, api_key.clone())?;
+) -> Result<String, AppError> {
+    let _op = history::begin("configure_codex_advanced");
+    let key = api_key::check(UrlTarget::Codex, &api_key, None)?;
+    codex_config::configure_codex_advanced(auth_json, config_toml, key.key.clone())?;
 
     // 设置环境变量 key88
-    env_manager::set_key88_env(api_key)?;
+    env_manager::set_key88_env(key.key.clone())?;
 
     #[cfg(windows)]
     {
-        Ok("Codex 高级配置成功！环境变量 key88 已设置，请重启 Codex 以使环境变量生效。".to_string())
+        Ok(key.annotate(t!("codex.advanced_configured_windows")))
     }
 
     #[cfg(not(windows))]
     {
-        Ok("Codex 高级配置成功！环境变量 key88 已添加到 shell 配置文件，请重启终端或运行 'source ~/.zshrc' (或相应的配置文件) 以使环境变量生效。".to_string())
+        Ok(key.annotate(t!("codex.advanced_configured_unix")))
     }
 }
 
-/// 删除 Claude Code 配置文件
+/// 移除 Claude Code 配置中由 88code 写入的键（保留权限、hooks 等其他设置）
 #[tauri::command]
-pub async fn delete_claude_config() -> Result<String, String> {
-    let settings_path = config::get_claude_settings_path();
+pub async fn delete_claude_config() -> Result<String, AppError> {
+    let _op = history::begin("delete_claude_config");
+    let removed = claude_config::remove_claude_config()?;
+    if removed.is_empty() {
+        Ok(t!("config.not_found_88code"))
+    } else {
+        Ok(t!("config.removed", items = removed.join(", ")))
+    }
+}
 
-    if settings_path.exists() {
-        std::fs::remove_file(&settings_path)
-            .map_err(|e| format!("删除配置文件失败: {}", e))?;
-        Ok(format!("已删除配置文件: {:?}", settings_path))
+/// 移除 Codex 配置中由 88code 写入的键（保留其他 provider 和设置）
+#[tauri::command]
+pub async fn delete_codex_config() -> Result<String, AppError> {
+    let _op = history::begin("delete_codex_config");
+    let removed = codex_config::remove_codex_config()?;
+    if removed.is_empty() {
+        Ok(t!("config.not_found_88code"))
     } else {
-        Ok("配置文件不存在".to_string())
+        Ok(t!("config.removed", items = removed.join("；")))
     }
 }
 
-/// 删除 Codex 配置文件
+/// 移除 VSCode ChatGPT 扩展中由 88code 写入的配置（targets 为空时处理全部检测到的配置文件）
 #[tauri::command]
-pub async fn delete_codex_config() -> Result<String, String> {
-    let auth_path = config::get_codex_auth_path();
-    let config_path = config::get_codex_config_path();
+pub async fn remove_vscode_codex(
+    targets: Option<Vec<String>>,
+) -> Result<Vec<vscode::TargetResult>, AppError> {
+    let _op = history::begin("remove_vscode_codex");
+    vscode::remove_vscode_codex(targets.unwrap_or_default())
+}
 
-    let mut deleted = Vec::new();
-    let mut not_exist = Vec::new();
+/// 获取应用所做修改的记录
+#[tauri::command]
+pub async fn get_change_journal() -> Result<changes::ChangeJournal, AppError> {
+    Ok(changes::load_journal())
+}
 
-    if auth_path.exists() {
-        std::fs::remove_file(&auth_path)
-            .map_err(|e| format!("删除 auth.json 失败: {}", e))?;
-        deleted.push("auth.json");
-    } else {
-        not_exist.push("auth.json");
-    }
+/// 撤销应用对所有配置文件所做的修改，恢复到首次配置前的状态
+#[tauri::command]
+pub async fn revert_all_changes() -> Result<Vec<changes::RevertResult>, AppError> {
+    let _op = history::begin("revert_all_changes");
+    changes::revert_all()
+}
 
-    if config_path.exists() {
-        std::fs::remove_file(&config_path)
-            .map_err(|e| format!("删除 config.toml 失败: {}", e))?;
-        deleted.push("config.toml");
-    } else {
-        not_exist.push("config.toml");
-    }
+/// 浏览配置写入审计日志（最新的在前，可按文件路径过滤）
+#[tauri::command]
+pub async fn get_history(
+    limit: Option<usize>,
+    path: Option<String>,
+) -> Result<Vec<history::HistoryEntry>, AppError> {
+    history::read_history(limit, path.as_deref())
+}
 
-    let mut msg = String::new();
-    if !deleted.is_empty() {
-        msg.push_str(&format!("已删除: {}", deleted.join(", ")));
-    }
-    if !not_exist.is_empty() {
-        if !msg.is_empty() {
-            msg.push_str("；");
-        }
-        msg.push_str(&format!("不存在: {}", not_exist.join(", ")));
-    }
+/// 导出审计日志（JSON Lines），用于排查问题
+#[tauri::command]
+pub async fn export_history(target: String) -> Result<String, AppError> {
+    let count = history::export_history(std::path::Path::new(&target))?;
+    Ok(t!("history.exported", count = count, path = target))
+}
+
+/// 检查含密钥文件和配置目录的权限
+#[tauri::command]
+pub async fn get_permission_issues() -> Result<Vec<permissions::PermissionIssue>, AppError> {
+    Ok(permissions::check_permissions())
+}
 
-    Ok(msg)
+/// 将含密钥文件收紧为 0600、配置目录收紧为 0700，返回已修复的项
+#[tauri::command]
+pub async fn fix_permissions() -> Result<Vec<permissions::PermissionIssue>, AppError> {
+    permissions::fix_permissions()
+}
+
+/// 导出配置包（key_mode: omit 不含密钥 / plain 明文 / encrypt 使用口令加密）
+#[tauri::command]
+pub async fn export_bundle(
+    target: String,
+    key_mode: bundle::KeyMode,
+    passphrase: Option<String>,
+) -> Result<String, AppError> {
+    bundle::export_bundle(std::path::Path::new(&target), key_mode, passphrase.as_deref())?;
+    Ok(t!("bundle.exported", path = target))
+}
+
+/// 预览导入配置包会带来的变化
+#[tauri::command]
+pub async fn preview_bundle(
+    path: String,
+    passphrase: Option<String>,
+) -> Result<bundle::BundlePreview, AppError> {
+    bundle::preview_bundle(std::path::Path::new(&path), passphrase.as_deref())
+}
+
+/// 导入配置包
+#[tauri::command]
+pub async fn import_bundle(path: String, passphrase: Option<String>) -> Result<Vec<String>, AppError> {
+    let _op = history::begin("import_bundle");
+    bundle::apply_bundle(std::path::Path::new(&path), passphrase.as_deref())
+}
+
+/// 比较团队配置清单与当前配置
+#[tauri::command]
+pub async fn check_manifest(path: String) -> Result<manifest::DriftReport, AppError> {
+    manifest::check_manifest(std::path::Path::new(&path))
+}
+
+/// 列出清单中的密钥命令，供应用前确认
+#[tauri::command]
+pub async fn get_manifest_commands(path: String) -> Result<Vec<String>, AppError> {
+    let manifest = manifest::read_manifest(std::path::Path::new(&path))?;
+    Ok(manifest::secret_commands(&manifest))
+}
+
+/// 应用团队配置清单（只修改与清单不一致的部分）
+/// allow_commands 表示用户已确认执行清单中的密钥命令
+#[tauri::command]
+pub async fn apply_manifest(path: String, allow_commands: bool) -> Result<manifest::ApplyReport, AppError> {
+    let _op = history::begin("apply_manifest");
+    manifest::apply_manifest(std::path::Path::new(&path), allow_commands)
+}
+
+/// 获取后端消息语言
+#[tauri::command]
+pub async fn get_locale() -> Result<i18n::Locale, AppError> {
+    Ok(i18n::current())
+}
+
+/// 设置后端消息语言，接受 zh-CN、en-US 或系统语言标识（如 navigator.language）
+#[tauri::command]
+pub async fn set_locale(locale: String) -> Result<i18n::Locale, AppError> {
+    let locale = i18n::Locale::parse(&locale)
+        .ok_or_else(|| AppError::invalid_input(format!("{}: {}", t!("i18n.unsupported_locale"), locale)))?;
+    i18n::set_locale(locale);
+    Ok(locale)
 }
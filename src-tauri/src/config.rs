@@ -1,13 +1,26 @@
+use crate::error::{AppError, AppResult, ErrorKind, SuggestedAction};
+use crate::i18n::t;
 use serde::{Deserialize, Serialize};
-use std::fs;
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::fs::{self, OpenOptions};
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// 用户主目录（Claude Code、Codex 的配置都在这里）
+fn user_home_dir() -> PathBuf {
+    // 测试中不读写用户真实的 ~/.claude、~/.codex
+    #[cfg(test)]
+    return std::env::temp_dir().join(format!("com.88code.desktop-test-home-{}", std::process::id()));
+
+    #[cfg(not(test))]
+    dirs::home_dir().expect("无法获取用户主目录")
+}
 
 /// 获取 Claude Code 配置目录路径
 pub fn get_claude_config_dir() -> PathBuf {
-    dirs::home_dir()
-        .expect("无法获取用户主目录")
-        .join(".claude")
+    user_home_dir().join(".claude")
 }
 
 /// 获取 Claude Code settings.json 文件路径
@@ -15,11 +28,14 @@ pub fn get_claude_settings_path() -> PathBuf {
     get_claude_config_dir().join("settings.json")
 }
 
+/// 获取 Claude Code 用户级状态文件 ~/.claude.json 路径（用户级 MCP 服务器保存在这里）
+pub fn get_claude_json_path() -> PathBuf {
+    user_home_dir().join(".claude.json")
+}
+
 /// 获取 Codex 配置目录路径
 pub fn get_codex_config_dir() -> PathBuf {
-    dirs::home_dir()
-        .expect("无法获取用户主目录")
-        .join(".codex")
+    user_home_dir().join(".codex")
 }
 
 /// 获取 Codex auth.json 路径
@@ -32,95 +48,286 @@ pub fn get_codex_config_path() -> PathBuf {
     get_codex_config_dir().join("config.toml")
 }
 
-/// 原子写入：写入临时文件后 rename 替换，避免半写状态
-pub fn atomic_write(path: &Path, data: &[u8]) -> Result<(), String> {
-    // 确保父目录存在
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| format!("创建目录失败: {}", e))?;
+/// 获取应用数据目录（与 tauri.conf.json 中的 identifier 一致）
+pub fn get_app_data_dir() -> PathBuf {
+    // 测试中不写入用户的数据目录（锁文件、审计日志、变更记录）
+    #[cfg(test)]
+    return std::env::temp_dir().join(format!("com.88code.desktop-test-{}", std::process::id()));
+
+    #[cfg(not(test))]
+    dirs::data_dir()
+        .or_else(dirs::home_dir)
+        .expect("无法获取应用数据目录")
+        .join("com.88code.desktop")
+}
+
+/// 获取应用配置目录（与 Tauri 的 app_config_dir 一致，用户可在此放置覆盖文件）
+pub fn get_app_config_dir() -> PathBuf {
+    // 测试中不读取用户的预设覆盖文件
+    #[cfg(test)]
+    return std::env::temp_dir().join(format!("com.88code.desktop-test-config-{}", std::process::id()));
+
+    #[cfg(not(test))]
+    dirs::config_dir()
+        .or_else(dirs::home_dir)
+        .expect("无法获取应用配置目录")
+        .join("com.88code.desktop")
+}
+
+/// 等待其他进程释放写锁的最长时间
+const LOCK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 解析符号链接，返回实际要写入的文件（例如 dotfiles 仓库中的文件），链接本身保持不变
+pub(crate) fn resolve_write_target(path: &Path) -> PathBuf {
+    let mut target = path.to_path_buf();
+    // 最多跟随 32 层，防止循环链接
+    for _ in 0..32 {
+        let Ok(link) = fs::read_link(&target) else {
+            break;
+        };
+        target = match target.parent() {
+            Some(parent) if link.is_relative() => parent.join(link),
+            _ => link,
+        };
     }
+    target
+}
 
-    // 生成临时文件路径（带纳秒时间戳）
-    let parent = path.parent().ok_or_else(|| "无效的路径".to_string())?;
-    let mut tmp = parent.to_path_buf();
-    let file_name = path
-        .file_name()
-        .ok_or_else(|| "无效的文件名".to_string())?
-        .to_string_lossy()
-        .to_string();
-    let ts = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .unwrap_or_default()
-        .as_nanos();
-    tmp.push(format!("{}.tmp.{}", file_name, ts));
+thread_local! {
+    /// 当前线程已持有写锁的目标文件，同一线程再次加锁时直接复用
+    static HELD_LOCKS: RefCell<Vec<PathBuf>> = const { RefCell::new(Vec::new()) };
+}
 
-    // 写入临时文件
-    {
-        let mut f = fs::File::create(&tmp)
-            .map_err(|e| format!("创建临时文件失败: {}", e))?;
-        f.write_all(data)
-            .map_err(|e| format!("写入临时文件失败: {}", e))?;
-        f.flush()
-            .map_err(|e| format!("刷新临时文件失败: {}", e))?;
+/// 文件写锁，离开作用域时释放
+pub struct FileLock {
+    target: PathBuf,
+    /// 同一线程重复加锁时为 None，由外层持有者释放
+    file: Option<fs::File>,
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        if self.file.take().is_some() {
+            HELD_LOCKS.with(|held| held.borrow_mut().retain(|p| p != &self.target));
+        }
+    }
+}
+
+/// 为“读取-修改-写入”加锁：在读取文件前调用并持有到写入完成，
+/// 期间其他进程和线程无法写入同一文件；atomic_write 会复用当前线程已持有的锁
+pub fn lock_for_update(path: &Path) -> AppResult<FileLock> {
+    let target = resolve_write_target(path);
+    if HELD_LOCKS.with(|held| held.borrow().contains(&target)) {
+        return Ok(FileLock { target, file: None });
+    }
+
+    let file = acquire_write_lock(&target)?;
+    HELD_LOCKS.with(|held| held.borrow_mut().push(target.clone()));
+    Ok(FileLock {
+        target,
+        file: Some(file),
+    })
+}
+
+/// 获取跨进程写锁（锁文件位于应用数据目录，按目标路径区分），返回的文件关闭时自动释放
+fn acquire_write_lock(target: &Path) -> AppResult<fs::File> {
+    let lock_dir = get_app_data_dir().join("locks");
+    fs::create_dir_all(&lock_dir).map_err(|e| AppError::io(t!("fs.create_lock_dir_failed"), &lock_dir, e))?;
+
+    let name: String = Sha256::digest(target.to_string_lossy().as_bytes())
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    let lock_path = lock_dir.join(format!("{}.lock", name));
+    let file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(&lock_path)
+        .map_err(|e| AppError::io(t!("fs.open_lock_failed"), &lock_path, e))?;
+
+    let start = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => return Ok(file),
+            Err(fs::TryLockError::WouldBlock) if start.elapsed() < LOCK_TIMEOUT => {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            Err(fs::TryLockError::WouldBlock) => {
+                return Err(AppError::new(ErrorKind::Locked, t!("fs.locked"))
+                    .with_path(target)
+                    .with_action(SuggestedAction::Retry))
+            }
+            Err(fs::TryLockError::Error(e)) => {
+                return Err(AppError::io(t!("fs.lock_failed"), &lock_path, e))
+            }
+        }
     }
+}
+
+/// 清理之前中断的写入留下的临时文件（持有写锁时调用，不会误删正在进行的写入）
+fn cleanup_stale_temp_files(target: &Path) {
+    let (Some(parent), Some(file_name)) = (target.parent(), target.file_name()) else {
+        return;
+    };
+    let prefix = format!("{}.tmp.", file_name.to_string_lossy());
 
-    // Unix 系统：复制原文件权限
+    let Ok(entries) = fs::read_dir(parent) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().starts_with(&prefix) {
+            log::warn!("清理上次中断写入留下的临时文件: {:?}", entry.path());
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// 复制原文件的权限和所有者到临时文件（原文件不存在时保持默认）
+/// 密钥文件始终使用 0600，不沿用原文件过于宽松的权限
+#[cfg(unix)]
+fn copy_file_metadata(from: &Path, file: &fs::File, secret: bool) {
+    use std::os::unix::fs::{fchown, MetadataExt, PermissionsExt};
+
+    if let Ok(meta) = fs::metadata(from) {
+        let mode = if secret { crate::permissions::SECRET_FILE_MODE } else { meta.mode() };
+        let _ = file.set_permissions(fs::Permissions::from_mode(mode));
+        // 以其他用户身份运行（如 sudo）时保持原所有者，无权限时忽略
+        let _ = fchown(file, Some(meta.uid()), Some(meta.gid()));
+    }
+}
+
+#[cfg(not(unix))]
+fn copy_file_metadata(_from: &Path, _file: &fs::File, _secret: bool) {}
+
+/// 同步父目录，确保 rename 在断电后依然生效（Windows 无法打开目录，跳过）
+fn sync_parent_dir(target: &Path) {
     #[cfg(unix)]
-    {
-        use std::os::unix::fs::PermissionsExt;
-        if let Ok(meta) = fs::metadata(path) {
-            let perm = meta.permissions().mode();
-            let _ = fs::set_permissions(&tmp, fs::Permissions::from_mode(perm));
+    if let Some(parent) = target.parent() {
+        if let Ok(dir) = fs::File::open(parent) {
+            let _ = dir.sync_all();
         }
     }
 
-    // Windows 系统：先删除目标文件（rename 不支持覆盖）
-    #[cfg(windows)]
+    #[cfg(not(unix))]
+    let _ = target;
+}
+
+/// 写入并同步临时文件，再 rename 替换目标文件
+/// secret 为 true 时临时文件以 0600 创建，避免新建的密钥文件在默认 umask 下可被其他用户读取
+fn write_and_replace(target: &Path, tmp: &Path, data: &[u8], secret: bool) -> AppResult<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
     {
-        if path.exists() {
-            let _ = fs::remove_file(path);
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(if secret { crate::permissions::SECRET_FILE_MODE } else { 0o666 });
+    }
+    #[cfg(not(unix))]
+    let _ = secret;
+
+    let mut f = options
+        .open(tmp)
+        .map_err(|e| AppError::io(t!("fs.create_temp_failed"), tmp, e))?;
+    f.write_all(data)
+        .map_err(|e| AppError::io(t!("fs.write_temp_failed"), tmp, e))?;
+    copy_file_metadata(target, &f, secret);
+    f.sync_all()
+        .map_err(|e| AppError::io(t!("fs.sync_temp_failed"), tmp, e))?;
+    drop(f);
+
+    // rename 在 Unix 上是原子操作，在 Windows 上会直接替换已存在的文件
+    fs::rename(tmp, target).map_err(|e| AppError::io(t!("fs.replace_failed"), target, e))?;
+    sync_parent_dir(target);
+    Ok(())
+}
+
+/// 原子写入：加锁后写入临时文件、fsync，再 rename 替换，避免半写状态
+/// 目标为符号链接时写入链接指向的文件，并保留原文件的权限和所有者
+pub fn atomic_write(path: &Path, data: &[u8]) -> AppResult<()> {
+    let target = resolve_write_target(path);
+    if crate::dotfiles::is_read_only_target(&target) {
+        return Err(AppError::new(ErrorKind::ReadOnly, t!("fs.nix_read_only")).with_path(path));
+    }
+    let invalid = || AppError::invalid_input(t!("fs.invalid_path")).with_path(path);
+    let parent = target.parent().ok_or_else(invalid)?;
+    let file_name = target
+        .file_name()
+        .ok_or_else(invalid)?
+        .to_string_lossy()
+        .to_string();
+
+    // 确保父目录存在，应用新建的 .claude/.codex 目录仅所有者可访问
+    if !parent.exists() {
+        fs::create_dir_all(parent).map_err(|e| AppError::io(t!("fs.create_dir_failed"), parent, e))?;
+        if crate::permissions::is_secret_dir(parent) {
+            crate::permissions::restrict(parent, true)?;
         }
-        fs::rename(&tmp, path).map_err(|e| format!("原子替换失败: {}", e))?;
     }
+    let secret = crate::permissions::is_secret_file(path) || crate::permissions::is_secret_file(&target);
 
-    // Unix/macOS 系统：直接 rename（原子操作）
-    #[cfg(not(windows))]
-    {
-        fs::rename(&tmp, path).map_err(|e| format!("原子替换失败: {}", e))?;
+    let _lock = lock_for_update(&target)?;
+    cleanup_stale_temp_files(&target);
+
+    // 记录写入前的内容，用于审计日志
+    let before = fs::read(&target).ok();
+    // 外部修改尚未被发现时拒绝覆盖；写入期间监听线程跳过该文件，完成后记录新指纹
+    let write = crate::watcher::begin_write(path, before.as_deref())?;
+
+    // 生成临时文件路径（带进程号和纳秒时间戳）
+    let ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let tmp = parent.join(format!("{}.tmp.{}.{}", file_name, std::process::id(), ts));
+
+    if let Err(e) = write_and_replace(&target, &tmp, data, secret) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e);
     }
+    write.finish(Some(data));
+
+    crate::history::record(path, before.as_deref(), data);
+    Ok(())
+}
 
+/// 删除配置文件（加锁，并记录到监听状态，避免被当作外部修改）
+pub fn remove_file(path: &Path) -> AppResult<()> {
+    let _lock = lock_for_update(path)?;
+    let before = fs::read(path).ok();
+    let write = crate::watcher::begin_write(path, before.as_deref())?;
+    fs::remove_file(path).map_err(|e| AppError::io(t!("fs.delete_failed"), path, e))?;
+    write.finish(None);
     Ok(())
 }
 
 /// 写入 JSON 配置文件
-pub fn write_json_file<T: Serialize>(path: &Path, data: &T) -> Result<(), String> {
+pub fn write_json_file<T: Serialize>(path: &Path, data: &T) -> AppResult<()> {
     // 序列化为格式化 JSON
     let json = serde_json::to_string_pretty(data)
-        .map_err(|e| format!("序列化 JSON 失败: {}", e))?;
+        .map_err(|e| AppError::new(ErrorKind::Other, t!("fs.serialize_json_failed")).with_source(e))?;
 
     // 调用原子写入
     atomic_write(path, json.as_bytes())
 }
 
 /// 原子写入文本文件（用于 TOML/纯文本）
-pub fn write_text_file(path: &Path, data: &str) -> Result<(), String> {
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)
-            .map_err(|e| format!("创建目录失败: {}", e))?;
-    }
+pub fn write_text_file(path: &Path, data: &str) -> AppResult<()> {
     atomic_write(path, data.as_bytes())
 }
 
 /// 读取 JSON 文件并解析
-pub fn read_json_file<T: for<'de> Deserialize<'de>>(path: &Path) -> Result<T, String> {
+pub fn read_json_file<T: for<'de> Deserialize<'de>>(path: &Path) -> AppResult<T> {
     if !path.exists() {
-        return Err(format!("文件不存在: {}", path.display()));
+        return Err(AppError::not_found(t!("fs.file_not_found")).with_path(path));
     }
 
     let content = fs::read_to_string(path)
-        .map_err(|e| format!("读取文件失败: {}", e))?;
+        .map_err(|e| AppError::io(t!("fs.read_failed"), path, e))?;
 
     serde_json::from_str(&content)
-        .map_err(|e| format!("解析 JSON 失败: {}", e))
+        .map_err(|e| AppError::json(t!("fs.parse_json_failed"), &e).with_path(path))
 }
 
 /// 获取当前系统的配置路径信息（用于前端显示）
@@ -143,32 +350,160 @@ pub fn get_config_paths_info() -> ConfigPaths {
     }
 }
 
+/// 生成备份文件路径（settings.json -> settings.json.bak）
+pub fn backup_path_for(file_path: &Path) -> PathBuf {
+    file_path.with_extension(
+        format!("{}.bak", file_path.extension()
+            .and_then(|s| s.to_str())
+            .unwrap_or("")
+        ).trim_start_matches('.')
+    )
+}
+
 /// 创建备份文件(仅在.bak文件不存在时创建)
+/// 符号链接会备份其指向的实际内容，备份放在链接旁边，不写入 dotfiles 仓库（避免被 stow/chezmoi 当作新文件管理）
 /// 返回 Ok(true) 表示创建了备份, Ok(false) 表示备份已存在
-pub fn create_backup_if_not_exists(file_path: &Path) -> Result<bool, String> {
+pub fn create_backup_if_not_exists(file_path: &Path) -> AppResult<bool> {
     // 如果原文件不存在,无需备份
     if !file_path.exists() {
         return Ok(false);
     }
 
-    // 生成备份文件路径
-    let backup_path = file_path.with_extension(
-        format!("{}.bak", file_path.extension()
-            .and_then(|s| s.to_str())
-            .unwrap_or("")
-        ).trim_start_matches('.')
-    );
+    let backup_path = backup_path_for(file_path);
 
-    // 如果备份文件已存在,不再覆盖
-    if backup_path.exists() {
+    // 如果备份文件已存在,不再覆盖（悬空的链接也算存在，避免 copy 写入链接目标）
+    if fs::symlink_metadata(&backup_path).is_ok() {
         log::info!("备份文件已存在,跳过: {:?}", backup_path);
         return Ok(false);
     }
 
-    // 创建备份
-    fs::copy(file_path, &backup_path)
-        .map_err(|e| format!("创建备份文件失败: {}", e))?;
+    // 创建备份（读取链接指向的实际文件，备份本身为普通文件）
+    fs::copy(resolve_write_target(file_path), &backup_path)
+        .map_err(|e| AppError::io(t!("fs.create_backup_failed"), &backup_path, e))?;
+
+    // 含密钥文件的备份同样只允许所有者读写
+    if crate::permissions::is_secret_file(file_path) {
+        crate::permissions::restrict(&backup_path, false)?;
+    }
 
     log::info!("首次备份成功: {:?} -> {:?}", file_path, backup_path);
     Ok(true)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_files(dir: &Path) -> Vec<String> {
+        fs::read_dir(dir)
+            .unwrap()
+            .flatten()
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|name| name.contains(".tmp."))
+            .collect()
+    }
+
+    #[test]
+    fn writes_new_and_existing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("settings.json");
+        atomic_write(&path, b"one").unwrap();
+        atomic_write(&path, b"two").unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"two");
+        assert!(temp_files(path.parent().unwrap()).is_empty());
+    }
+
+    #[test]
+    fn removes_stale_temp_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        fs::write(dir.path().join("config.toml.tmp.1.2"), b"half written").unwrap();
+        fs::write(dir.path().join("other.toml.tmp.1.2"), b"not ours").unwrap();
+
+        atomic_write(&path, b"model = \"gpt-5\"").unwrap();
+        assert_eq!(temp_files(dir.path()), vec!["other.toml.tmp.1.2"]);
+    }
+
+    #[test]
+    fn failure_before_rename_keeps_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("auth.json");
+        fs::write(&path, b"original").unwrap();
+
+        // 临时文件无法创建：目标保持不变
+        let tmp = dir.path().join("missing").join("auth.json.tmp.1.2");
+        assert!(write_and_replace(&path, &tmp, b"new", false).is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"original");
+
+        // rename 失败（目标是目录）：临时文件被清理
+        let target_dir = dir.path().join("settings.json");
+        fs::create_dir(&target_dir).unwrap();
+        fs::write(target_dir.join("keep"), b"").unwrap();
+        assert!(atomic_write(&target_dir, b"new").is_err());
+        assert!(target_dir.join("keep").exists());
+        assert!(temp_files(dir.path()).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn writes_through_symlinks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let dotfiles = dir.path().join("dotfiles");
+        fs::create_dir(&dotfiles).unwrap();
+        let real = dotfiles.join("settings.json");
+        fs::write(&real, b"old").unwrap();
+        fs::set_permissions(&real, fs::Permissions::from_mode(0o640)).unwrap();
+        let link = dir.path().join("settings.json");
+        std::os::unix::fs::symlink("dotfiles/settings.json", &link).unwrap();
+
+        atomic_write(&link, b"new").unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read(&real).unwrap(), b"new");
+        assert_eq!(fs::metadata(&real).unwrap().permissions().mode() & 0o777, 0o640);
+        assert!(temp_files(&dotfiles).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn secret_files_are_written_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("auth.json");
+        fs::write(&path, b"{}").unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o644)).unwrap();
+
+        let tmp = dir.path().join("auth.json.tmp.1.2");
+        write_and_replace(&path, &tmp, b"{\"OPENAI_API_KEY\": \"sk\"}", true).unwrap();
+        assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+
+        let new_path = dir.path().join("settings.json");
+        write_and_replace(&new_path, &dir.path().join("settings.json.tmp.1.2"), b"{}", true).unwrap();
+        assert_eq!(fs::metadata(&new_path).unwrap().permissions().mode() & 0o777, 0o600);
+    }
+
+    #[test]
+    fn lock_is_reentrant_within_thread_and_exclusive_across_threads() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+
+        let outer = lock_for_update(&path).unwrap();
+        // 同一线程中 atomic_write 复用已持有的锁
+        atomic_write(&path, b"inside").unwrap();
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let other = path.clone();
+        let handle = std::thread::spawn(move || {
+            let _lock = lock_for_update(&other).unwrap();
+            tx.send(fs::read(&other).unwrap()).unwrap();
+        });
+        // 外层锁释放前，其他线程拿不到锁
+        assert!(rx.recv_timeout(Duration::from_millis(200)).is_err());
+        atomic_write(&path, b"after").unwrap();
+        drop(outer);
+        assert_eq!(rx.recv_timeout(LOCK_TIMEOUT).unwrap(), b"after");
+        handle.join().unwrap();
+    }
+}
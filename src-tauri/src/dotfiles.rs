@@ -0,0 +1,233 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// 常见的 dotfiles 管理工具
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DotfileManager {
+    /// GNU Stow：符号链接指向 stow 目录中的包
+    Stow,
+    /// chezmoi：源文件位于 ~/.local/share/chezmoi，下次 `chezmoi apply` 会覆盖本地修改
+    Chezmoi,
+    /// Nix home-manager：链接指向只读的 /nix/store，需要修改 Nix 配置
+    HomeManager,
+    /// 链接指向某个 git 仓库（手动管理的 dotfiles 仓库）
+    GitRepo,
+}
+
+/// 配置文件的符号链接与 dotfiles 管理信息
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LinkInfo {
+    /// 路径本身是符号链接时为其最终指向的文件
+    pub symlink_target: Option<String>,
+    pub manager: Option<DotfileManager>,
+    /// 实际文件不可写（如 /nix/store），应用无法修改
+    pub read_only: bool,
+}
+
+/// chezmoi 源文件名可能带的属性前缀
+const CHEZMOI_PREFIXES: &[&str] = &[
+    "private_",
+    "readonly_",
+    "executable_",
+    "empty_",
+    "exact_",
+    "create_",
+    "modify_",
+    "encrypted_",
+    "symlink_",
+];
+
+/// chezmoi 源目录
+fn chezmoi_source_dir(home: &Path) -> Option<PathBuf> {
+    let dir = home.join(".local/share/chezmoi");
+    dir.is_dir().then_some(dir)
+}
+
+/// 把目标文件名转换为 chezmoi 源文件名（".claude" -> "dot_claude"）
+fn chezmoi_name(component: &str) -> String {
+    match component.strip_prefix('.') {
+        Some(rest) => format!("dot_{}", rest),
+        None => component.to_string(),
+    }
+}
+
+/// 在 chezmoi 源目录中查找与 name 对应的条目（允许属性前缀和 .tmpl 后缀）
+fn find_chezmoi_entry(dir: &Path, name: &str) -> Option<PathBuf> {
+    let entries = std::fs::read_dir(dir).ok()?;
+    for entry in entries.flatten() {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let mut stripped = file_name.trim_end_matches(".tmpl");
+        while let Some(rest) = CHEZMOI_PREFIXES.iter().find_map(|p| stripped.strip_prefix(p)) {
+            stripped = rest;
+        }
+        if stripped == name {
+            return Some(entry.path());
+        }
+    }
+    None
+}
+
+/// 判断主目录下的文件是否由 chezmoi 管理
+fn is_chezmoi_managed(path: &Path, home: &Path) -> bool {
+    let Some(source) = chezmoi_source_dir(home) else {
+        return false;
+    };
+    let Ok(relative) = path.strip_prefix(home) else {
+        return false;
+    };
+
+    let mut dir = source;
+    for component in relative.components() {
+        let name = chezmoi_name(&component.as_os_str().to_string_lossy());
+        match find_chezmoi_entry(&dir, &name) {
+            Some(next) => dir = next,
+            None => return false,
+        }
+    }
+    true
+}
+
+/// 实际写入的文件是否只读（位于 /nix/store，由 Nix home-manager 生成）
+pub fn is_read_only_target(target: &Path) -> bool {
+    target.starts_with("/nix/store")
+}
+
+/// 根据链接指向的实际文件判断管理工具
+fn detect_from_target(target: &Path, home: Option<&Path>) -> Option<DotfileManager> {
+    if is_read_only_target(target) {
+        return Some(DotfileManager::HomeManager);
+    }
+    if home
+        .and_then(chezmoi_source_dir)
+        .is_some_and(|source| target.starts_with(source))
+    {
+        return Some(DotfileManager::Chezmoi);
+    }
+
+    // stow 目录中通常有 .stow 标记文件，或包内有 .stow-local-ignore
+    for dir in target.ancestors().skip(1) {
+        if dir.join(".stow").exists() || dir.join(".stow-local-ignore").exists() {
+            return Some(DotfileManager::Stow);
+        }
+        if dir.join(".git").exists() {
+            return Some(DotfileManager::GitRepo);
+        }
+    }
+    None
+}
+
+/// 检测配置文件是否为符号链接以及由哪个 dotfiles 工具管理
+pub fn inspect(path: &Path) -> LinkInfo {
+    inspect_in(path, dirs::home_dir().as_deref())
+}
+
+/// 以 home 为主目录检测（chezmoi 源目录位于 home/.local/share/chezmoi）
+fn inspect_in(path: &Path, home: Option<&Path>) -> LinkInfo {
+    let is_symlink = std::fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink());
+
+    if is_symlink {
+        let target = crate::config::resolve_write_target(path);
+        let manager = detect_from_target(&target, home);
+        return LinkInfo {
+            symlink_target: Some(target.to_string_lossy().to_string()),
+            read_only: is_read_only_target(&target),
+            manager,
+        };
+    }
+
+    // chezmoi 默认复制文件而不是创建链接
+    LinkInfo {
+        manager: home
+            .is_some_and(|home| is_chezmoi_managed(path, home))
+            .then_some(DotfileManager::Chezmoi),
+        ..Default::default()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::os::unix::fs::symlink;
+
+    #[test]
+    fn plain_file_is_unmanaged() {
+        let home = tempfile::tempdir().unwrap();
+        let path = home.path().join(".claude/settings.json");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "{}").unwrap();
+
+        let info = inspect_in(&path, Some(home.path()));
+        assert!(info.symlink_target.is_none());
+        assert!(info.manager.is_none());
+        assert!(!info.read_only);
+    }
+
+    #[test]
+    fn finds_chezmoi_entries_with_prefixes_and_templates() {
+        let home = tempfile::tempdir().unwrap();
+        let source = home.path().join(".local/share/chezmoi");
+        fs::create_dir_all(source.join("private_dot_claude")).unwrap();
+        fs::write(source.join("private_dot_claude/readonly_settings.json.tmpl"), "{}").unwrap();
+        fs::create_dir_all(source.join("dot_codex")).unwrap();
+        fs::write(source.join("dot_codex/config.toml"), "").unwrap();
+
+        assert_eq!(chezmoi_name(".claude"), "dot_claude");
+        assert_eq!(chezmoi_name("settings.json"), "settings.json");
+        assert_eq!(find_chezmoi_entry(&source, "dot_claude"), Some(source.join("private_dot_claude")));
+        assert_eq!(
+            find_chezmoi_entry(&source.join("private_dot_claude"), "settings.json"),
+            Some(source.join("private_dot_claude/readonly_settings.json.tmpl"))
+        );
+        assert!(find_chezmoi_entry(&source, "dot_gemini").is_none());
+
+        let claude = home.path().join(".claude/settings.json");
+        assert!(is_chezmoi_managed(&claude, home.path()));
+        assert!(is_chezmoi_managed(&home.path().join(".codex/config.toml"), home.path()));
+        assert!(!is_chezmoi_managed(&home.path().join(".codex/auth.json"), home.path()));
+        assert!(!is_chezmoi_managed(Path::new("/etc/hosts"), home.path()));
+        assert_eq!(inspect_in(&claude, Some(home.path())).manager, Some(DotfileManager::Chezmoi));
+    }
+
+    #[test]
+    fn detects_managers_from_symlink_targets() {
+        let home = tempfile::tempdir().unwrap();
+        let link_to = |name: &str, target: &Path| {
+            fs::create_dir_all(target.parent().unwrap()).unwrap();
+            fs::write(target, "{}").unwrap();
+            let link = home.path().join(name);
+            symlink(target, &link).unwrap();
+            inspect_in(&link, Some(home.path()))
+        };
+
+        let stow = home.path().join("dotfiles");
+        fs::create_dir_all(&stow).unwrap();
+        fs::write(stow.join(".stow"), "").unwrap();
+        let stow_target = stow.join("claude/.claude/settings.json");
+        let info = link_to("stow.json", &stow_target);
+        assert_eq!(info.manager, Some(DotfileManager::Stow));
+        assert_eq!(info.symlink_target.as_deref(), Some(stow_target.to_string_lossy().as_ref()));
+        assert!(!info.read_only);
+
+        let repo = home.path().join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        let info = link_to("git.json", &repo.join("claude/settings.json"));
+        assert_eq!(info.manager, Some(DotfileManager::GitRepo));
+
+        let info = link_to("chezmoi.json", &home.path().join(".local/share/chezmoi/dot_claude/settings.json"));
+        assert_eq!(info.manager, Some(DotfileManager::Chezmoi));
+
+        let info = link_to("loose.json", &home.path().join("elsewhere/settings.json"));
+        assert!(info.manager.is_none());
+        assert!(info.symlink_target.is_some());
+    }
+
+    #[test]
+    fn nix_store_targets_are_read_only_home_manager() {
+        let target = Path::new("/nix/store/abc-home-manager-files/.claude/settings.json");
+        assert!(is_read_only_target(target));
+        assert_eq!(detect_from_target(target, None), Some(DotfileManager::HomeManager));
+        assert!(!is_read_only_target(Path::new("/home/user/.claude/settings.json")));
+    }
+}
@@ -1,64 +1,133 @@
-use std::process::Command;
+use crate::error::{AppError, AppResult};
+use crate::i18n::t;
 
+#[cfg(windows)]
+use std::process::Command;
 #[cfg(not(windows))]
 use std::env;
 #[cfg(not(windows))]
-use std::fs::{self, OpenOptions};
-#[cfg(not(windows))]
-use std::io::{BufRead, BufReader, Write};
+use std::fs;
 #[cfg(not(windows))]
 use std::path::PathBuf;
 
 /// 设置环境变量 key88 (跨平台)
-pub fn set_key88_env(api_key: String) -> Result<(), String> {
+pub fn set_key88_env(api_key: String) -> AppResult<()> {
+    set_user_env("key88", &api_key)
+}
+
+/// 设置用户级环境变量（Windows 写入注册表，Unix 写入 shell 配置文件）
+pub fn set_user_env(key: &str, value: &str) -> AppResult<()> {
     #[cfg(windows)]
     {
-        set_windows_env("key88", &api_key)
+        set_windows_env(key, value)
     }
 
     #[cfg(not(windows))]
     {
-        set_unix_env("key88", &api_key)
+        set_unix_env(key, value)
+    }
+}
+
+/// 读取应用设置的用户级环境变量（Unix 从 shell 配置文件解析，未写入时回退到当前进程环境）
+pub fn read_user_env(key: &str) -> Option<String> {
+    #[cfg(not(windows))]
+    {
+        let marker = format!("export {}=", key);
+        let from_rc = detect_shell_config()
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|content| {
+                content.lines().find_map(|line| {
+                    let value = line.trim().strip_prefix(&marker)?;
+                    Some(value.trim_matches(|c| c == '"' || c == '\'').to_string())
+                })
+            });
+        if from_rc.is_some() {
+            return from_rc;
+        }
+    }
+
+    std::env::var(key).ok()
+}
+
+/// 获取写入环境变量的 shell 配置文件路径（Windows 使用注册表，返回 None）
+pub fn shell_config_path() -> Option<std::path::PathBuf> {
+    #[cfg(windows)]
+    {
+        None
+    }
+
+    #[cfg(not(windows))]
+    {
+        detect_shell_config().ok()
     }
 }
 
 /// Windows: 使用 setx 命令设置用户环境变量
 #[cfg(windows)]
-fn set_windows_env(key: &str, value: &str) -> Result<(), String> {
+pub(crate) fn set_windows_env(key: &str, value: &str) -> AppResult<()> {
+    crate::changes::remember(
+        crate::changes::ManagedFormat::WindowsEnv,
+        std::path::Path::new("HKCU\\Environment"),
+        &[&[key]],
+    );
+    write_windows_env(key, value)
+}
+
+/// Windows: 设置用户环境变量但不写入变更记录（撤销配置时使用）
+#[cfg(windows)]
+pub(crate) fn write_windows_env(key: &str, value: &str) -> AppResult<()> {
     let output = Command::new("setx")
         .arg(key)
         .arg(value)
         .output()
-        .map_err(|e| format!("执行 setx 命令失败: {}", e))?;
+        .map_err(|e| AppError::new(crate::error::ErrorKind::Command, t!("env.run_command_failed", command = "setx")).with_source(e))?;
 
     if !output.status.success() {
         let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("设置环境变量失败: {}", stderr));
+        return Err(AppError::new(crate::error::ErrorKind::Command, t!("env.set_failed")).with_source(stderr.trim()));
     }
 
-    log::info!("Windows 环境变量设置成功: {}={}", key, value);
+    log::info!("Windows 环境变量设置成功: {}", key);
+    Ok(())
+}
+
+/// Windows: 删除用户环境变量（撤销配置时使用）
+#[cfg(windows)]
+pub(crate) fn remove_windows_env(key: &str) -> AppResult<()> {
+    let output = Command::new("reg")
+        .args(["delete", "HKCU\\Environment", "/v", key, "/f"])
+        .output()
+        .map_err(|e| AppError::new(crate::error::ErrorKind::Command, t!("env.run_command_failed", command = "reg")).with_source(e))?;
+
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::new(crate::error::ErrorKind::Command, t!("env.delete_failed")).with_source(stderr.trim()));
+    }
+
+    log::info!("Windows 环境变量已删除: {}", key);
     Ok(())
 }
 
 /// Unix/macOS: 写入 shell 配置文件
 #[cfg(not(windows))]
-fn set_unix_env(key: &str, value: &str) -> Result<(), String> {
+fn set_unix_env(key: &str, value: &str) -> AppResult<()> {
     let shell_config = detect_shell_config()?;
+    crate::changes::remember(crate::changes::ManagedFormat::ShellRc, &shell_config, &[&[key]]);
     append_env_to_shell_config(&shell_config, key, value)?;
+    // 首次写入 key88 前文件还不是密钥文件，写入后再收紧权限
+    if crate::permissions::is_secret_file(&shell_config) {
+        crate::permissions::restrict(&shell_config, false)?;
+    }
 
-    log::info!(
-        "Unix 环境变量已添加到: {:?}, {}={}",
-        shell_config,
-        key,
-        value
-    );
+    log::info!("Unix 环境变量 {} 已添加到: {:?}", key, shell_config);
     Ok(())
 }
 
 /// 检测当前使用的 shell 配置文件
 #[cfg(not(windows))]
-fn detect_shell_config() -> Result<PathBuf, String> {
-    let home = dirs::home_dir().ok_or_else(|| "无法获取用户主目录".to_string())?;
+fn detect_shell_config() -> AppResult<PathBuf> {
+    let home = dirs::home_dir().ok_or_else(|| AppError::not_found(t!("env.no_home")))?;
 
     // 检查 SHELL 环境变量
     if let Ok(shell) = env::var("SHELL") {
@@ -92,56 +161,32 @@ fn detect_shell_config() -> Result<PathBuf, String> {
 
 /// 将环境变量添加到 shell 配置文件（避免重复）
 #[cfg(not(windows))]
-fn append_env_to_shell_config(config_path: &PathBuf, key: &str, value: &str) -> Result<(), String> {
-    // 创建配置文件(如果不存在)
-    if !config_path.exists() {
-        if let Some(parent) = config_path.parent() {
-            fs::create_dir_all(parent).map_err(|e| format!("创建配置目录失败: {}", e))?;
-        }
-        fs::File::create(config_path).map_err(|e| format!("创建配置文件失败: {}", e))?;
-    }
-
-    // 读取现有内容，检查是否已存在该变量
-    let file = fs::File::open(config_path).map_err(|e| format!("打开配置文件失败: {}", e))?;
-    let reader = BufReader::new(file);
-
-    let marker = format!("export {}=", key);
-    let mut already_exists = false;
-
-    for line in reader.lines() {
-        if let Ok(line) = line {
-            if line.trim().starts_with(&marker) {
-                already_exists = true;
-                break;
-            }
-        }
-    }
-
-    // 如果已存在，先删除旧的，再添加新的
-    if already_exists {
-        remove_env_from_shell_config(config_path, key)?;
-    }
+fn append_env_to_shell_config(config_path: &PathBuf, key: &str, value: &str) -> AppResult<()> {
+    // 持有写锁直到写入完成，避免读取后、写入前被其他进程修改
+    let _lock = crate::config::lock_for_update(config_path)?;
+    // 读取现有内容（文件不存在时视为空），并删除已存在的同名变量
+    let content = if config_path.exists() {
+        fs::read_to_string(config_path).map_err(|e| AppError::io(t!("config.read_failed"), config_path, e))?
+    } else {
+        String::new()
+    };
+    let mut content = strip_env_lines(&content, key);
 
     // 追加新的环境变量
-    let mut file = OpenOptions::new()
-        .append(true)
-        .open(config_path)
-        .map_err(|e| format!("打开配置文件失败: {}", e))?;
-
-    let env_line = format!("\n# Added by 88code-desktop\nexport {}=\"{}\"\n", key, value);
-    file.write_all(env_line.as_bytes())
-        .map_err(|e| format!("写入配置文件失败: {}", e))?;
+    content.push_str(&format!("\n# Added by 88code-desktop\nexport {}=\"{}\"\n", key, value));
+    crate::config::write_text_file(config_path, &content)?;
 
     Ok(())
 }
 
-/// 从 shell 配置文件中删除指定环境变量
+/// 删除指定环境变量的 export 行、其后的空行以及标记注释
 #[cfg(not(windows))]
-fn remove_env_from_shell_config(config_path: &PathBuf, key: &str) -> Result<(), String> {
-    let content = fs::read_to_string(config_path)
-        .map_err(|e| format!("读取配置文件失败: {}", e))?;
-
+fn strip_env_lines(content: &str, key: &str) -> String {
     let marker = format!("export {}=", key);
+    if !content.lines().any(|line| line.trim().starts_with(&marker)) {
+        return content.to_string();
+    }
+
     let mut new_lines = Vec::new();
     let mut skip_next_blank = false;
 
@@ -163,8 +208,26 @@ fn remove_env_from_shell_config(config_path: &PathBuf, key: &str) -> Result<(),
         new_lines.push(line);
     }
 
-    fs::write(config_path, new_lines.join("\n"))
-        .map_err(|e| format!("写入配置文件失败: {}", e))?;
+    new_lines.join("\n")
+}
 
-    Ok(())
+/// 从 shell 配置文件中删除指定环境变量
+#[cfg(not(windows))]
+pub(crate) fn remove_env_from_shell_config(config_path: &PathBuf, key: &str) -> AppResult<()> {
+    let _lock = crate::config::lock_for_update(config_path)?;
+    let content = fs::read_to_string(config_path)
+        .map_err(|e| AppError::io(t!("config.read_failed"), config_path, e))?;
+
+    crate::config::write_text_file(config_path, &strip_env_lines(&content, key))
+}
+
+/// 恢复 shell 配置文件中原有的 export 行（撤销配置时使用）
+#[cfg(not(windows))]
+pub(crate) fn restore_shell_line(config_path: &PathBuf, line: &str) -> AppResult<()> {
+    let _lock = crate::config::lock_for_update(config_path)?;
+    let mut content = fs::read_to_string(config_path)
+        .map_err(|e| AppError::io(t!("config.read_failed"), config_path, e))?;
+    content.push_str(&format!("\n{}\n", line));
+
+    crate::config::write_text_file(config_path, &content)
 }
@@ -0,0 +1,154 @@
+use serde::Serialize;
+use std::fmt;
+use std::path::Path;
+
+/// 错误类别，前端与命令行据此本地化提示或决定后续操作
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// 用户输入无效（如密钥为空、地址格式错误、配置校验失败）
+    InvalidInput,
+    /// 文件、目录或前置配置不存在
+    NotFound,
+    /// 没有读写权限
+    PermissionDenied,
+    /// 其他文件读写错误
+    Io,
+    /// JSON/TOML 解析失败
+    Parse,
+    /// 文件正被其他程序写入
+    Locked,
+    /// 文件只读（如由 Nix home-manager 管理）
+    ReadOnly,
+    /// 文件在读取后被外部修改
+    ExternalChange,
+    /// 外部命令（setx、reg 等）执行失败
+    Command,
+    Other,
+}
+
+/// 建议用户执行的操作，前端据此显示对应按钮
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SuggestedAction {
+    /// 稍后重试
+    Retry,
+    /// 修复文件权限（fix_permissions 命令）
+    FixPermissions,
+    /// 重新读取配置后再修改
+    Reload,
+    /// 手动编辑出错的文件
+    EditFile,
+    /// 检查输入内容
+    CheckInput,
+}
+
+/// 后端统一错误类型（所有 Tauri 命令的错误返回值）
+#[derive(Debug, Clone, Serialize)]
+pub struct AppError {
+    pub kind: ErrorKind,
+    /// 面向用户的说明
+    pub message: String,
+    pub path: Option<String>,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    /// 底层 io/解析错误原文
+    pub source: Option<String>,
+    pub action: Option<SuggestedAction>,
+}
+
+pub type AppResult<T> = Result<T, AppError>;
+
+impl AppError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            message: message.into(),
+            path: None,
+            line: None,
+            column: None,
+            source: None,
+            action: None,
+        }
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::InvalidInput, message).with_action(SuggestedAction::CheckInput)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::NotFound, message)
+    }
+
+    /// 文件读写错误，按 io 错误类型区分权限不足和文件不存在
+    pub fn io(message: impl Into<String>, path: &Path, err: std::io::Error) -> Self {
+        let (kind, action) = match err.kind() {
+            std::io::ErrorKind::PermissionDenied => {
+                (ErrorKind::PermissionDenied, Some(SuggestedAction::FixPermissions))
+            }
+            std::io::ErrorKind::NotFound => (ErrorKind::NotFound, None),
+            _ => (ErrorKind::Io, Some(SuggestedAction::Retry)),
+        };
+        let mut error = Self::new(kind, message).with_path(path).with_source(err);
+        error.action = action;
+        error
+    }
+
+    /// JSON 解析错误（带行列号）
+    pub fn json(message: impl Into<String>, err: &serde_json::Error) -> Self {
+        Self::new(ErrorKind::Parse, message)
+            .with_source(err)
+            .at(err.line(), err.column())
+            .with_action(SuggestedAction::EditFile)
+    }
+
+    /// TOML 解析错误（根据出错位置计算行列号）
+    pub fn toml(message: impl Into<String>, content: &str, err: &toml::de::Error) -> Self {
+        let mut error = Self::new(ErrorKind::Parse, message)
+            .with_source(err.message())
+            .with_action(SuggestedAction::EditFile);
+        if let Some(span) = err.span() {
+            let before = &content[..span.start.min(content.len())];
+            let line = before.matches('\n').count() + 1;
+            let column = before.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+            error = error.at(line, column);
+        }
+        error
+    }
+
+    pub fn with_path(mut self, path: &Path) -> Self {
+        self.path = Some(path.to_string_lossy().to_string());
+        self
+    }
+
+    pub fn with_source(mut self, source: impl fmt::Display) -> Self {
+        self.source = Some(source.to_string());
+        self
+    }
+
+    pub fn with_action(mut self, action: SuggestedAction) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    pub fn at(mut self, line: usize, column: usize) -> Self {
+        self.line = Some(line);
+        self.column = Some(column);
+        self
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(path) = &self.path {
+            write!(f, " {}", path)?;
+        }
+        if let Some(source) = &self.source {
+            write!(f, ": {}", source)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for AppError {}
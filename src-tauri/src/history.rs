@@ -0,0 +1,404 @@
+use crate::config::get_app_data_dir;
+use crate::error::{AppError, AppResult, ErrorKind};
+use crate::i18n::t;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::cell::RefCell;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// 单条记录中 diff 的最大行数，超出部分截断
+const MAX_DIFF_LINES: usize = 200;
+
+/// 写入前后任一内容超过此大小时不计算 diff，只记录哈希
+const MAX_DIFF_BYTES: usize = 1024 * 1024;
+
+/// 去掉首尾相同的行后，LCS 矩阵的最大单元数（约 4 MB），超出时不计算 diff
+const MAX_DIFF_CELLS: usize = 1024 * 1024;
+
+/// 未通过 begin 标记操作时使用的名称
+const DEFAULT_OPERATION: &str = "write";
+
+/// 被视为敏感信息的键名片段（小写匹配），对应的值在 diff 中会被隐藏
+const SECRET_MARKERS: &[&str] = &["key", "token", "secret", "password", "auth"];
+
+/// 审计日志中的一条写入记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// 写入时间（Unix 毫秒）
+    pub timestamp: u64,
+    /// 触发写入的操作（通常为命令名）
+    pub operation: String,
+    pub path: String,
+    /// 写入前内容的 SHA-256，文件原本不存在时为 None
+    pub before_hash: Option<String>,
+    pub after_hash: String,
+    /// 已隐藏密钥的逐行 diff（"- " 删除，"+ " 新增）
+    pub diff: Vec<String>,
+    /// diff 是否因超出行数限制被截断，或因内容过大未计算（此时只有哈希）
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+thread_local! {
+    static CURRENT_OPERATION: RefCell<Option<&'static str>> = const { RefCell::new(None) };
+}
+
+/// 操作标记，离开作用域时自动清除
+pub struct OperationGuard {
+    previous: Option<&'static str>,
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        CURRENT_OPERATION.with(|op| *op.borrow_mut() = self.previous);
+    }
+}
+
+/// 标记当前线程接下来的写入所属的操作，用法：`let _op = history::begin("configure_codex");`
+pub fn begin(operation: &'static str) -> OperationGuard {
+    let previous = CURRENT_OPERATION.with(|op| op.borrow_mut().replace(operation));
+    OperationGuard { previous }
+}
+
+fn current_operation() -> &'static str {
+    CURRENT_OPERATION.with(|op| op.borrow().unwrap_or(DEFAULT_OPERATION))
+}
+
+/// 审计日志文件路径（JSON Lines）
+pub fn history_path() -> PathBuf {
+    get_app_data_dir().join("history.jsonl")
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 键名是否表示敏感信息
+fn is_secret_key(name: &str) -> bool {
+    let lower = name.to_lowercase();
+    SECRET_MARKERS.iter().any(|m| lower.contains(m))
+}
+
+/// 返回从 start（引号）开始的字符串结束位置（结束引号之后）
+fn quoted_end(bytes: &[u8], start: usize) -> usize {
+    let quote = bytes[start];
+    let mut i = start + 1;
+    while i < bytes.len() && bytes[i] != quote {
+        if bytes[i] == b'\\' {
+            i += 1;
+        }
+        i += 1;
+    }
+    (i + 1).min(bytes.len())
+}
+
+/// 返回从 start 开始的标量值的结束位置，值为对象、数组或为空时返回 None
+fn scalar_end(bytes: &[u8], start: usize) -> Option<usize> {
+    match bytes.get(start)? {
+        b'"' | b'\'' => Some(quoted_end(bytes, start)),
+        b'{' | b'[' => None,
+        _ => {
+            let end = (start..bytes.len())
+                .find(|&i| bytes[i].is_ascii_whitespace() || matches!(bytes[i], b',' | b'}' | b']' | b';'))
+                .unwrap_or(bytes.len());
+            (end > start).then_some(end)
+        }
+    }
+}
+
+/// 隐藏单行中每个敏感键的值，支持 "key": "value"、key = value、export key=value、
+/// 紧凑写法 {"a": "x", "b": "y"} 以及 JetBrains 的 <entry key="名称" value="值" />
+fn redact_line(line: &str) -> String {
+    let bytes = line.as_bytes();
+    let mut result = String::with_capacity(line.len());
+    let mut copied = 0;
+    let mut i = 0;
+    // 分隔符前最近的键（文本, 是否带引号）
+    let mut key: Option<(&str, bool)> = None;
+    // XML 属性 key="名称" 中的名称，决定随后 value 属性是否敏感
+    let mut entry_name: Option<&str> = None;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' | b'\'' => {
+                let end = quoted_end(bytes, i);
+                // 字符串未闭合时取到行尾
+                let inner_end = if end > i + 1 && bytes[end - 1] == bytes[i] { end - 1 } else { end };
+                key = Some((&line[i + 1..inner_end], true));
+                i = end;
+            }
+            b':' | b'=' => {
+                let sep = bytes[i];
+                i += 1;
+                let Some((name, quoted)) = key.take() else {
+                    continue;
+                };
+                let start = (i..bytes.len()).find(|&j| !bytes[j].is_ascii_whitespace()).unwrap_or(bytes.len());
+                let Some(end) = scalar_end(bytes, start) else {
+                    continue;
+                };
+
+                let is_entry_name = sep == b'=' && !quoted && (name == "key" || name == "name");
+                if is_entry_name {
+                    entry_name = Some(line[start..end].trim_matches(['"', '\'']));
+                } else if is_secret_key(name) || (name == "value" && entry_name.is_some_and(is_secret_key)) {
+                    result.push_str(&line[copied..start]);
+                    result.push_str("\"<redacted>\"");
+                    copied = end;
+                }
+                i = end;
+            }
+            c if c.is_ascii_alphanumeric() || matches!(c, b'_' | b'-' | b'.') => {
+                let end = (i..bytes.len())
+                    .find(|&j| !(bytes[j].is_ascii_alphanumeric() || matches!(bytes[j], b'_' | b'-' | b'.')))
+                    .unwrap_or(bytes.len());
+                key = Some((&line[i..end], false));
+                i = end;
+            }
+            c if c.is_ascii_whitespace() => i += 1,
+            _ => {
+                key = None;
+                i += 1;
+            }
+        }
+    }
+
+    result.push_str(&line[copied..]);
+    result
+}
+
+/// 计算逐行 diff（最长公共子序列）：先去掉首尾相同的行，只对中间改动的部分建 O(n*m) 矩阵
+/// 内容或改动部分过大时返回 None，由调用方只记录哈希
+fn line_diff(before: &str, after: &str) -> Option<Vec<String>> {
+    if before.len() > MAX_DIFF_BYTES || after.len() > MAX_DIFF_BYTES {
+        return None;
+    }
+    let a: Vec<&str> = before.lines().collect();
+    let b: Vec<&str> = after.lines().collect();
+
+    let prefix = a.iter().zip(&b).take_while(|(x, y)| x == y).count();
+    let suffix = a[prefix..]
+        .iter()
+        .rev()
+        .zip(b[prefix..].iter().rev())
+        .take_while(|(x, y)| x == y)
+        .count();
+    let a = &a[prefix..a.len() - suffix];
+    let b = &b[prefix..b.len() - suffix];
+    if (a.len() + 1).saturating_mul(b.len() + 1) > MAX_DIFF_CELLS {
+        return None;
+    }
+
+    let mut lcs = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push(format!("- {}", redact_line(a[i])));
+            i += 1;
+        } else {
+            diff.push(format!("+ {}", redact_line(b[j])));
+            j += 1;
+        }
+    }
+    Some(diff)
+}
+
+/// 记录一次写入（由 config::atomic_write 调用），失败只输出警告
+pub fn record(path: &Path, before: Option<&[u8]>, after: &[u8]) {
+    // 应用自身的数据文件（变更记录、审计日志等）不记录
+    if path.starts_with(get_app_data_dir()) {
+        return;
+    }
+
+    let before_text = before.map(String::from_utf8_lossy).unwrap_or_default();
+    let after_text = String::from_utf8_lossy(after);
+    let (diff, truncated) = match line_diff(&before_text, &after_text) {
+        Some(mut diff) => {
+            let truncated = diff.len() > MAX_DIFF_LINES;
+            diff.truncate(MAX_DIFF_LINES);
+            (diff, truncated)
+        }
+        None => (Vec::new(), true),
+    };
+
+    let entry = HistoryEntry {
+        timestamp: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64,
+        operation: current_operation().to_string(),
+        path: path.to_string_lossy().to_string(),
+        before_hash: before.map(sha256_hex),
+        after_hash: sha256_hex(after),
+        diff,
+        truncated,
+    };
+
+    if let Err(e) = append_entry(&entry) {
+        log::warn!("写入审计日志失败: {}", e);
+    }
+}
+
+fn append_entry(entry: &HistoryEntry) -> AppResult<()> {
+    let path = history_path();
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| AppError::io(t!("fs.create_dir_failed"), parent, e))?;
+    }
+
+    let mut line = serde_json::to_string(entry)
+        .map_err(|e| AppError::new(ErrorKind::Other, t!("history.serialize_failed")).with_source(e))?;
+    line.push('\n');
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(|e| AppError::io(t!("history.open_failed"), &path, e))?;
+    file.write_all(line.as_bytes())
+        .map_err(|e| AppError::io(t!("history.write_failed"), &path, e))
+}
+
+/// 读取审计日志（最新的在前），可按文件路径过滤；损坏的行会被跳过
+pub fn read_history(limit: Option<usize>, path: Option<&str>) -> AppResult<Vec<HistoryEntry>> {
+    let history_path = history_path();
+    if !history_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content =
+        fs::read_to_string(&history_path).map_err(|e| AppError::io(t!("history.read_failed"), &history_path, e))?;
+    let entries = content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<HistoryEntry>(line).ok())
+        .filter(|entry| path.is_none_or(|p| entry.path == p))
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+    Ok(entries)
+}
+
+/// 导出审计日志到指定文件（原样复制 JSON Lines），返回导出的记录数
+pub fn export_history(target: &Path) -> AppResult<usize> {
+    // 直接写入，导出本身不计入审计日志
+    let content = fs::read_to_string(history_path()).unwrap_or_default();
+    fs::write(target, &content).map_err(|e| AppError::io(t!("history.export_failed"), target, e))?;
+    Ok(content.lines().filter(|line| !line.trim().is_empty()).count())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_json_member() {
+        assert_eq!(
+            redact_line(r#"    "ANTHROPIC_AUTH_TOKEN": "sk-abc","#),
+            r#"    "ANTHROPIC_AUTH_TOKEN": "<redacted>","#
+        );
+        assert_eq!(redact_line(r#"  "OPENAI_API_KEY": null"#), r#"  "OPENAI_API_KEY": "<redacted>""#);
+    }
+
+    #[test]
+    fn redacts_every_pair_in_compact_line() {
+        assert_eq!(
+            redact_line(r#""env": {"ANTHROPIC_BASE_URL": "https://88code.org/api", "ANTHROPIC_AUTH_TOKEN": "sk-abc", "apiKeyHelper": "echo sk"}"#),
+            r#""env": {"ANTHROPIC_BASE_URL": "https://88code.org/api", "ANTHROPIC_AUTH_TOKEN": "<redacted>", "apiKeyHelper": "<redacted>"}"#
+        );
+    }
+
+    #[test]
+    fn redacts_toml_values() {
+        assert_eq!(redact_line("experimental_bearer_token = 'sk-abc'"), "experimental_bearer_token = \"<redacted>\"");
+        assert_eq!(
+            redact_line(r#"88code = { name = "88code", api_key = "sk-abc", base_url = "https://88code.org/openai/v1" }"#),
+            r#"88code = { name = "88code", api_key = "<redacted>", base_url = "https://88code.org/openai/v1" }"#
+        );
+    }
+
+    #[test]
+    fn redacts_shell_exports() {
+        assert_eq!(redact_line("export key88=sk-abc"), "export key88=\"<redacted>\"");
+        assert_eq!(redact_line("export key88=\"sk abc\" # 88code"), "export key88=\"<redacted>\" # 88code");
+        assert_eq!(redact_line("export PATH=$HOME/bin:$PATH"), "export PATH=$HOME/bin:$PATH");
+    }
+
+    #[test]
+    fn redacts_jetbrains_entry_value() {
+        assert_eq!(
+            redact_line(r#"            <entry key="ANTHROPIC_AUTH_TOKEN" value="sk-abc" />"#),
+            r#"            <entry key="ANTHROPIC_AUTH_TOKEN" value="<redacted>" />"#
+        );
+        let url = r#"            <entry key="ANTHROPIC_BASE_URL" value="https://88code.org/api" />"#;
+        assert_eq!(redact_line(url), url);
+    }
+
+    #[test]
+    fn keeps_non_secret_lines() {
+        for line in [
+            r#"  "chatgpt.apiBase": "https://88code.org/openai/v1","#,
+            "model = \"gpt-5\"",
+            "// 注释：没有密钥",
+            "}",
+            "",
+            r#""unterminated: "中"#,
+        ] {
+            assert_eq!(redact_line(line), line);
+        }
+    }
+
+    #[test]
+    fn diff_reports_changed_lines_redacted() {
+        let before = "{\n  \"model\": \"opus\",\n  \"ANTHROPIC_AUTH_TOKEN\": \"old\"\n}\n";
+        let after = "{\n  \"model\": \"opus\",\n  \"ANTHROPIC_AUTH_TOKEN\": \"new\",\n  \"x\": 1\n}\n";
+        assert_eq!(
+            line_diff(before, after).unwrap(),
+            vec![
+                "-   \"ANTHROPIC_AUTH_TOKEN\": \"<redacted>\"",
+                "+   \"ANTHROPIC_AUTH_TOKEN\": \"<redacted>\",",
+                "+   \"x\": 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn diff_of_identical_or_new_file() {
+        assert!(line_diff("a\nb\n", "a\nb\n").unwrap().is_empty());
+        assert_eq!(line_diff("", "a\nb").unwrap(), vec!["+ a", "+ b"]);
+        assert_eq!(line_diff("a\nb", "b").unwrap(), vec!["- a"]);
+    }
+
+    #[test]
+    fn diff_of_large_files_is_bounded() {
+        // 大文件中的局部修改：去掉首尾相同的行后仍能给出 diff
+        let lines: Vec<String> = (0..50_000).map(|i| format!("line {i}")).collect();
+        let before = lines.join("\n");
+        let after = before.replace("line 25000\n", "line 25000 changed\n");
+        assert_eq!(line_diff(&before, &after).unwrap(), vec!["- line 25000", "+ line 25000 changed"]);
+
+        // 整体改写：改动部分过大，不计算 diff
+        let rewritten: Vec<String> = (0..50_000).map(|i| format!("other {i}")).collect();
+        assert!(line_diff(&before, &rewritten.join("\n")).is_none());
+
+        let huge = "x".repeat(MAX_DIFF_BYTES + 1);
+        assert!(line_diff("", &huge).is_none());
+    }
+}
@@ -0,0 +1,390 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// 后端消息语言
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Locale {
+    #[serde(rename = "zh-CN")]
+    ZhCn,
+    #[serde(rename = "en-US")]
+    EnUs,
+}
+
+impl Locale {
+    /// 解析语言标识，兼容 BCP 47（zh-CN、en-US）和 POSIX（zh_CN.UTF-8）格式；
+    /// 中文（含繁体）使用简体中文，其他语言使用英文
+    pub fn parse(tag: &str) -> Option<Self> {
+        let lang = tag.split(['-', '_', '.', '@']).next()?.to_ascii_lowercase();
+        match lang.as_str() {
+            "" | "c" | "posix" => None,
+            "zh" => Some(Locale::ZhCn),
+            _ => Some(Locale::EnUs),
+        }
+    }
+
+    fn index(self) -> u8 {
+        match self {
+            Locale::ZhCn => 1,
+            Locale::EnUs => 2,
+        }
+    }
+}
+
+/// 当前语言，0 表示尚未确定
+static LOCALE: AtomicU8 = AtomicU8::new(0);
+
+/// 从 LC_ALL、LC_MESSAGES、LANG 推断语言（命令行等没有界面的场景），都未设置时使用简体中文
+fn locale_from_env() -> Locale {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find_map(|value| Locale::parse(&value))
+        .unwrap_or(Locale::ZhCn)
+}
+
+/// 获取当前语言
+pub fn current() -> Locale {
+    match LOCALE.load(Ordering::Relaxed) {
+        1 => Locale::ZhCn,
+        2 => Locale::EnUs,
+        _ => {
+            let locale = locale_from_env();
+            LOCALE.store(locale.index(), Ordering::Relaxed);
+            locale
+        }
+    }
+}
+
+/// 设置当前语言（由界面根据用户选择或系统语言调用）
+pub fn set_locale(locale: Locale) {
+    LOCALE.store(locale.index(), Ordering::Relaxed);
+}
+
+/// 消息目录：(ID, 简体中文, English)，参数使用 {name} 占位
+const MESSAGES: &[(&str, &str, &str)] = &[
+    // 通用
+    ("i18n.unsupported_locale", "不支持的语言", "Unsupported locale"),
+    ("input.api_key_empty", "API 密钥不能为空", "API key must not be empty"),
+    ("config.not_found_88code", "未找到 88code 配置", "No 88code configuration found"),
+    ("config.removed", "已移除: {items}", "Removed: {items}"),
+    ("config.read_failed", "读取配置文件失败", "Failed to read the config file"),
+    ("config.malformed", "{file} 格式错误", "{file} is malformed"),
+    ("config.unsafe_to_remove", "{file} 格式错误，无法安全移除", "{file} is malformed and cannot be edited safely"),
+    ("target.env", "环境变量", "Environment variable"),
+    // 文件读写
+    ("fs.read_failed", "读取文件失败", "Failed to read file"),
+    ("fs.read_named_failed", "读取 {file} 失败", "Failed to read {file}"),
+    ("fs.parse_named_failed", "解析 {file} 失败", "Failed to parse {file}"),
+    ("fs.delete_failed", "删除文件失败", "Failed to delete file"),
+    ("fs.create_dir_failed", "创建目录失败", "Failed to create directory"),
+    ("fs.create_lock_dir_failed", "创建锁目录失败", "Failed to create the lock directory"),
+    ("fs.open_lock_failed", "打开锁文件失败", "Failed to open the lock file"),
+    ("fs.lock_failed", "获取文件锁失败", "Failed to acquire the file lock"),
+    ("fs.locked", "配置文件正被其他程序写入，请稍后重试", "The config file is being written by another program; please try again later"),
+    ("fs.create_temp_failed", "创建临时文件失败", "Failed to create temporary file"),
+    ("fs.write_temp_failed", "写入临时文件失败", "Failed to write temporary file"),
+    ("fs.sync_temp_failed", "同步临时文件失败", "Failed to sync temporary file"),
+    ("fs.replace_failed", "原子替换失败", "Failed to replace the file atomically"),
+    ("fs.nix_read_only", "文件由 Nix home-manager 管理（只读），请在 Nix 配置中修改后重新生成", "The file is managed by Nix home-manager (read-only); change it in your Nix configuration and rebuild"),
+    ("fs.invalid_path", "无效的路径", "Invalid path"),
+    ("fs.serialize_json_failed", "序列化 JSON 失败", "Failed to serialize JSON"),
+    ("fs.file_not_found", "文件不存在", "File not found"),
+    ("fs.parse_json_failed", "解析 JSON 失败", "Failed to parse JSON"),
+    ("fs.create_backup_failed", "创建备份文件失败", "Failed to create backup file"),
+    ("fs.external_change", "配置文件在上次读取后已被其他程序修改，请重新加载配置后再保存，以免覆盖外部修改", "The config file was changed by another program since it was last read; reload it before saving so those changes are not overwritten"),
+    // Base URL
+    ("url.empty", "Base URL 不能为空", "Base URL must not be empty"),
+    ("url.invalid_char", "Base URL 中不能包含字符 {ch}", "Base URL must not contain the character {ch}"),
+    ("url.inner_whitespace", "Base URL 中不能包含空格", "Base URL must not contain spaces"),
+    ("url.unsupported_scheme", "不支持的协议 {scheme}，请使用 https://", "Unsupported scheme {scheme}; use https://"),
+    ("url.missing_host", "Base URL 缺少域名", "Base URL has no host"),
+    ("url.trimmed_whitespace", "已去除首尾空白", "Removed surrounding whitespace"),
+    ("url.added_scheme", "已补全 https://", "Added https://"),
+    ("url.removed_query", "已移除查询参数 {part}", "Removed query string {part}"),
+    ("url.upgraded_https", "已将 http 改为 https", "Changed http to https"),
+    ("url.insecure_http", "使用 http 时密钥会以明文传输，建议改用 https", "The API key is sent in clear text over http; consider https"),
+    ("url.trimmed_slash", "已移除多余的斜杠", "Removed extra slashes"),
+    ("url.removed_endpoint", "已移除接口路径 {suffix}（客户端会自动追加）", "Removed endpoint path {suffix} (the client appends it)"),
+    ("url.duplicate_v1", "已移除重复的 /v1", "Removed duplicated /v1"),
+    ("url.added_v1", "已补全 /v1", "Added /v1"),
+    ("url.missing_v1", "Codex 的 Base URL 通常以 /v1 结尾，请确认地址是否正确", "Codex base URLs usually end with /v1; please double-check"),
+    ("url.codex_url_for_claude", "这是 Codex 的地址，不能用于 Claude Code", "This is a Codex URL and cannot be used for Claude Code"),
+    ("url.claude_url_for_codex", "这是 Claude Code 的地址，不能用于 Codex", "This is a Claude Code URL and cannot be used for Codex"),
+    ("url.use_default", "，请使用 {url}", "; use {url}"),
+    // API 密钥
+    ("key.trimmed_whitespace", "已去除密钥首尾的空白", "Removed whitespace around the key"),
+    ("key.removed_quotes", "已去除密钥两侧的引号", "Removed quotes around the key"),
+    ("key.removed_bearer", "已去除 Bearer 前缀", "Removed the Bearer prefix"),
+    ("key.inner_whitespace", "密钥中包含空白字符，可能复制时混入了其他内容", "The key contains whitespace; something else may have been pasted with it"),
+    ("key.placeholder", "这是模板中的占位值，请填写真实的 API 密钥", "This is a template placeholder; enter your real API key"),
+    ("key.truncated", "88code 密钥长度异常，可能复制不完整", "The 88code key looks truncated or malformed"),
+    ("key.openai_for_claude", "这看起来是 OpenAI 密钥，Claude Code 需要 Claude 密钥", "This looks like an OpenAI key; Claude Code needs a Claude key"),
+    ("key.anthropic_for_codex", "这看起来是 Anthropic 密钥，Codex 需要 OpenAI 格式的密钥", "This looks like an Anthropic key; Codex needs an OpenAI-style key"),
+    ("key.not_code88", "使用 88code 地址时需要填写 88code 密钥", "88code URLs require an 88code API key"),
+    ("key.vscode_placeholder", "未填写密钥，已写入占位值 key；扩展会使用 settings.json 中配置的密钥", "No key entered, so the placeholder \"key\" was written; the extension uses the key from settings.json"),
+    // 后台任务
+    ("task.failed", "后台任务异常退出", "A background task failed"),
+    // 服务地址预设
+    ("presets.invalid", "预设文件格式错误", "Malformed presets file"),
+    ("presets.unsupported_version", "不支持的预设文件版本 {version}（当前支持 {supported}）", "Unsupported presets file version {version} (supported: {supported})"),
+    ("presets.invalid_url", "预设 {id} 中的地址无效: {url}", "Invalid URL in preset {id}: {url}"),
+    // 测速
+    ("bench.no_candidates", "没有可测速的地址，请填写 Base URL 或配置预设", "No endpoints to benchmark; enter a base URL or configure presets"),
+    ("bench.invalid_url", "无效的地址: {url}", "Invalid URL: {url}"),
+    ("bench.resolve_failed", "域名解析失败", "DNS lookup failed"),
+    ("bench.connect_failed", "连接失败", "Connection failed"),
+    ("bench.tls_failed", "TLS 握手失败", "TLS handshake failed"),
+    ("bench.request_failed", "请求失败", "Request failed"),
+    ("bench.timeout", "请求超时", "Timed out"),
+    ("bench.no_response", "服务器未返回任何数据", "The server closed the connection without responding"),
+    ("bench.not_http", "响应不是 HTTP 格式", "The response is not HTTP"),
+    ("bench.server_error", "服务器返回错误状态码 {status}", "The server returned status {status}"),
+    ("bench.no_winner", "所有地址均不可用，未写入配置", "No endpoint is reachable; the configuration was not changed"),
+    // 账户信息
+    ("account.not_configured", "尚未配置该客户端的 API 密钥，请先完成配置", "No API key is configured for this client yet; configure it first"),
+    ("account.unauthorized", "API 密钥无效或已过期", "The API key is invalid or has expired"),
+    ("account.unsupported", "该服务不提供账户查询接口: {url}", "This server does not provide an account API: {url}"),
+    ("account.http_error", "账户查询失败，服务器返回状态码 {status}", "Account query failed with status {status}"),
+    ("account.request_failed", "无法连接账户服务", "Could not reach the account service"),
+    ("account.invalid_response", "账户接口返回的数据格式错误", "The account API returned malformed data"),
+    ("account.rejected", "账户接口返回错误: {message}", "The account API returned an error: {message}"),
+    // 本地用量统计
+    ("usage.invalid_date", "日期格式应为 YYYY-MM-DD: {date}", "Dates must use the YYYY-MM-DD format: {date}"),
+    ("usage.export_failed", "导出用量统计失败", "Failed to export usage statistics"),
+    ("usage.exported", "已导出 {count} 行用量统计到 {path}", "Exported {count} usage rows to {path}"),
+    // 会话浏览
+    ("session.empty_query", "请输入搜索内容", "Enter text to search for"),
+    ("session.not_found", "会话记录不存在或无法读取", "The session log does not exist or cannot be read"),
+    ("session.outside_dirs", "只能打开 Claude Code 或 Codex 会话目录中的日志", "Only logs inside the Claude Code or Codex session directories can be opened"),
+    ("session.export_failed", "导出会话失败", "Failed to export the session"),
+    ("session.exported", "会话已导出到 {path}", "Session exported to {path}"),
+    ("session.md_client", "客户端", "Client"),
+    ("session.md_project", "项目", "Project"),
+    ("session.md_session", "会话", "Session"),
+    ("session.md_model", "模型", "Model"),
+    ("session.md_started", "开始时间", "Started"),
+    ("session.md_user", "用户", "User"),
+    ("session.md_assistant", "助手", "Assistant"),
+    ("session.md_tool", "工具", "Tool"),
+    // 本地中转
+    ("relay.already_running", "本地中转已在端口 {port} 运行", "The local relay is already running on port {port}"),
+    ("relay.not_running", "本地中转未运行", "The local relay is not running"),
+    ("relay.bind_failed", "无法监听端口 {port}，可能已被占用", "Cannot listen on port {port}; it may already be in use"),
+    ("relay.no_routes", "没有可转发的客户端，请先配置 Claude Code 或 Codex 的密钥", "Nothing to relay; configure a key for Claude Code or Codex first"),
+    ("relay.no_key", "未填写 {client} 的密钥，且客户端尚未配置密钥", "No key was entered for {client} and the client has no key configured"),
+    ("relay.no_endpoints", "{client} 没有可用的上游地址", "{client} has no upstream endpoints"),
+    ("relay.loop", "上游地址不能指向本地中转: {url}", "The upstream must not point at the local relay: {url}"),
+    ("relay.route_in_use", "{client} 已指向本地中转，不能移除其转发", "{client} points at the local relay; its route cannot be removed"),
+    ("relay.invalid_proxy", "无效的代理地址: {proxy}", "Invalid proxy: {proxy}"),
+    ("relay.bad_request", "无法解析请求", "Malformed request"),
+    ("relay.body_too_large", "请求内容过大", "Request body too large"),
+    ("relay.forbidden_origin", "拒绝来自网页的请求", "Requests from web pages are not allowed"),
+    ("relay.forbidden_host", "拒绝 Host 不是本机地址的请求: {host}", "Rejected a request whose Host is not local: {host}"),
+    ("relay.forbidden_token", "请求路径缺少有效的中转令牌，请使用中转面板中显示的本地地址", "The request path lacks a valid relay token; use the local URL shown in the relay panel"),
+    ("relay.unknown_route", "未知的中转路径: {path}", "Unknown relay path: {path}"),
+    ("relay.upstream_status", "所有上游均不可用，最后一个返回状态码 {status}", "All upstreams failed; the last one returned status {status}"),
+    ("relay.upstream_failed", "所有上游均不可用: {error}", "All upstreams failed: {error}"),
+    ("relay.no_upstream", "没有可用的上游地址", "No upstream available"),
+    // Claude Code
+    ("claude.configured", "Claude Code 配置成功！", "Claude Code configured successfully!"),
+    ("claude.advanced_configured", "Claude Code 高级配置成功！", "Claude Code advanced configuration saved!"),
+    ("claude.invalid_content", "配置内容格式错误", "The configuration content is malformed"),
+    // Codex
+    ("codex.configured_windows", "Codex 配置成功！环境变量 key88 已设置，请重启 Codex 以使环境变量生效。", "Codex configured successfully! The key88 environment variable is set; restart Codex for it to take effect."),
+    ("codex.configured_unix", "Codex 配置成功！环境变量 key88 已添加到 shell 配置文件，请重启终端或运行 'source ~/.zshrc' (或相应的配置文件) 以使环境变量生效。", "Codex configured successfully! key88 was added to your shell profile; restart the terminal or run 'source ~/.zshrc' (or your shell's profile) for it to take effect."),
+    ("codex.advanced_configured_windows", "Codex 高级配置成功！环境变量 key88 已设置，请重启 Codex 以使环境变量生效。", "Codex advanced configuration saved! The key88 environment variable is set; restart Codex for it to take effect."),
+    ("codex.advanced_configured_unix", "Codex 高级配置成功！环境变量 key88 已添加到 shell 配置文件，请重启终端或运行 'source ~/.zshrc' (或相应的配置文件) 以使环境变量生效。", "Codex advanced configuration saved! key88 was added to your shell profile; restart the terminal or run 'source ~/.zshrc' (or your shell's profile) for it to take effect."),
+    ("codex.remove_breaks_toml", "移除 88code 配置后 config.toml 无法解析，已取消", "config.toml would no longer parse after removing the 88code settings; nothing was changed"),
+    // 环境变量
+    ("env.run_command_failed", "执行 {command} 命令失败", "Failed to run {command}"),
+    ("env.set_failed", "设置环境变量失败", "Failed to set the environment variable"),
+    ("env.delete_failed", "删除环境变量失败", "Failed to delete the environment variable"),
+    ("env.no_home", "无法获取用户主目录", "Cannot determine the home directory"),
+    // VSCode
+    ("vscode.claude_required", "请先配置 Claude Code 客户端！需要先完成客户端配置才能配置 VSCode。", "Configure the Claude Code client first; VSCode can only be configured after the client."),
+    ("vscode.configured", "VSCode 配置成功！已写入: {path}\n请重新加载 VSCode 窗口以使配置生效。", "VSCode configured successfully! Written to: {path}\nReload the VSCode window for it to take effect."),
+    ("vscode.config_dir_unknown", "无法确定 VSCode 配置目录路径。\n请检查：\n1. 是否已安装 VSCode\n2. Windows 系统环境变量 %APPDATA% 是否设置正确\n3. 可以尝试手动创建配置文件：%APPDATA%\\Code\\User\\settings.json", "Cannot determine the VSCode settings directory.\nPlease check:\n1. VSCode is installed\n2. The %APPDATA% environment variable is set correctly on Windows\n3. You can also create the settings file manually: %APPDATA%\\Code\\User\\settings.json"),
+    ("vscode.unsupported_path", "不是受支持的编辑器配置路径", "Not a supported editor settings path"),
+    ("vscode.settings_missing", "配置文件不存在", "Settings file does not exist"),
+    ("vscode.read_failed", "读取 VSCode 设置失败: {error}", "Failed to read VSCode settings: {error}"),
+    ("vscode.parse_failed", "无法解析 VSCode settings.json 文件: {error}", "Cannot parse VSCode settings.json: {error}"),
+    ("vscode.up_to_date", "配置已是最新，无需修改", "Already up to date"),
+    ("vscode.workspace_no_root", "工作区文件格式错误: 缺少顶层对象", "Malformed workspace file: missing top-level object"),
+    ("vscode.workspace_missing", "工作区文件不存在", "Workspace file does not exist"),
+    ("vscode.pick_folder", "请选择项目文件夹或 .code-workspace 文件", "Choose a project folder or a .code-workspace file"),
+    // JetBrains
+    ("jetbrains.unclosed_tag", "terminal.xml 格式错误: {tag} 标签未闭合", "Malformed terminal.xml: unclosed {tag} tag"),
+    ("jetbrains.missing_application_end", "terminal.xml 格式错误: 缺少 </application>", "Malformed terminal.xml: missing </application>"),
+    ("jetbrains.missing_map", "terminal.xml 格式错误: envDataOptions 中缺少 <map>", "Malformed terminal.xml: envDataOptions has no <map>"),
+    ("jetbrains.not_config", "terminal.xml 格式错误: 不是有效的 JetBrains 配置文件", "Malformed terminal.xml: not a JetBrains settings file"),
+    ("jetbrains.no_ide", "未检测到 JetBrains IDE 配置目录，请先启动一次 IDE 后重试。", "No JetBrains IDE settings directory found; start the IDE once and try again."),
+    ("jetbrains.unsupported_path", "只能配置检测到的 JetBrains IDE 配置目录", "Only detected JetBrains IDE settings directories can be configured"),
+    ("jetbrains.failed", "JetBrains 配置失败:\n{details}", "JetBrains configuration failed:\n{details}"),
+    ("jetbrains.configured", "JetBrains 配置成功！已写入:\n{targets}", "JetBrains configured successfully! Written to:\n{targets}"),
+    ("jetbrains.partial_failed", "\n以下 IDE 配置失败:\n{details}", "\nThe following IDEs failed:\n{details}"),
+    ("jetbrains.restart_terminal", "\n请重启 IDE 内置终端以使环境变量生效。", "\nRestart the IDE's built-in terminal for the environment variables to take effect."),
+    ("jetbrains.codex_required", "请先配置 Codex 客户端！JetBrains 插件使用 ~/.codex/config.toml 中的服务地址。", "Configure the Codex client first; the JetBrains plugin uses the endpoint in ~/.codex/config.toml."),
+    // 撤销修改
+    ("changes.cannot_restore", "无法恢复 {key}", "Cannot restore {key}"),
+    ("changes.no_backup", "无法恢复原文件：没有可用的备份", "Cannot restore the original file: no backup is available"),
+    ("changes.unsupported", "当前系统不支持撤销此类修改", "Reverting this kind of change is not supported on this system"),
+    // 审计日志
+    ("history.serialize_failed", "序列化失败", "Serialization failed"),
+    ("history.open_failed", "打开审计日志失败", "Failed to open the audit log"),
+    ("history.write_failed", "写入审计日志失败", "Failed to write the audit log"),
+    ("history.read_failed", "读取审计日志失败", "Failed to read the audit log"),
+    ("history.export_failed", "导出审计日志失败", "Failed to export the audit log"),
+    ("history.exported", "已导出 {count} 条记录到: {path}", "Exported {count} entries to: {path}"),
+    // 文件权限
+    ("permissions.fix_failed", "修改权限失败 {path}", "Failed to change permissions of {path}"),
+    // 配置包
+    ("bundle.exported", "配置包已导出到: {path}", "Config bundle exported to: {path}"),
+    ("bundle.derive_key_failed", "派生密钥失败", "Failed to derive the key"),
+    ("bundle.init_cipher_failed", "初始化加密失败", "Failed to initialize encryption"),
+    ("bundle.encrypt_failed", "加密失败", "Encryption failed"),
+    ("bundle.passphrase_required", "配置包中的密钥已加密，请输入口令", "The keys in this bundle are encrypted; enter the passphrase"),
+    ("bundle.corrupted", "配置包已损坏", "The config bundle is corrupted"),
+    ("bundle.init_decipher_failed", "初始化解密失败", "Failed to initialize decryption"),
+    ("bundle.wrong_passphrase", "口令错误或配置包已损坏", "Wrong passphrase or corrupted bundle"),
+    ("bundle.export_passphrase_required", "加密导出需要设置口令", "A passphrase is required for encrypted export"),
+    ("bundle.serialize_failed", "序列化配置包失败", "Failed to serialize the config bundle"),
+    ("bundle.write_failed", "写入配置包失败", "Failed to write the config bundle"),
+    ("bundle.read_failed", "读取配置包失败", "Failed to read the config bundle"),
+    ("bundle.invalid", "配置包格式错误", "Malformed config bundle"),
+    ("bundle.unsupported_version", "不支持的配置包版本 {version}（当前支持 {supported}），请升级应用后再导入", "Unsupported bundle version {version} (supported: {supported}); upgrade the app before importing"),
+    ("bundle.invalid_url", "配置包中的地址无效: {url}", "Invalid URL in config bundle: {url}"),
+    ("bundle.imported", "{target}: 已导入", "{target}: imported"),
+    ("bundle.missing_key", "{target}: 缺少 API 密钥，已跳过", "{target}: no API key, skipped"),
+    ("bundle.rolled_back", "导入失败，已恢复导入前的配置: {error}", "Import failed; the previous configuration was restored: {error}"),
+    // 团队配置清单
+    ("manifest.read_failed", "读取清单失败", "Failed to read the manifest"),
+    ("manifest.invalid", "清单格式错误", "Malformed manifest"),
+    ("manifest.unsupported_version", "不支持的清单版本 {version}（当前支持 {supported}），请升级应用", "Unsupported manifest version {version} (supported: {supported}); upgrade the app"),
+    ("manifest.invalid_url", "清单中的地址无效: {url}", "Invalid URL in manifest: {url}"),
+    ("manifest.cannot_write", "无法写入 {key}", "Cannot write {key}"),
+    ("manifest.updated_key", "{target}: 已更新 {key}", "{target}: updated {key}"),
+    ("manifest.updated_endpoint", "{target}: 已更新地址和密钥", "{target}: updated URL and key"),
+    ("manifest.missing_endpoint", "{target}: 缺少地址或 API 密钥，已跳过", "{target}: no URL or API key, skipped"),
+    ("manifest.env_set", "{target} {name}: 已设置", "{target} {name}: set"),
+    ("manifest.unresolved", "未解析（应用时确认后执行 {command}）", "Unresolved (runs {command} on apply after confirmation)"),
+    ("secret.command_spawn_failed", "执行密钥命令失败", "Failed to run the secret command"),
+    ("secret.command_failed", "密钥命令执行失败", "The secret command failed"),
+    ("secret.command_no_output", "密钥命令没有输出: {command}", "The secret command produced no output: {command}"),
+    ("secret.env_unset", "环境变量 {name} 未设置", "Environment variable {name} is not set"),
+    ("secret.source_invalid", "密钥来源必须指定 env 或 command 其中之一", "A secret source must specify exactly one of env or command"),
+    // 高级配置校验
+    ("schema.type.any", "任意值", "any value"),
+    ("schema.type.string", "字符串", "a string"),
+    ("schema.type.bool", "布尔值", "a boolean"),
+    ("schema.type.integer", "整数", "an integer"),
+    ("schema.type.number", "数字", "a number"),
+    ("schema.type.string_array", "字符串数组", "an array of strings"),
+    ("schema.type.nullable", "{kind}或 null", "{kind} or null"),
+    ("schema.type.object", "对象（表）", "an object (table)"),
+    ("schema.unquote", "去掉引号，写作 {value}", "Remove the quotes: {value}"),
+    ("schema.wrap_array", "写成数组，如 [\"{value}\"]", "Use an array, e.g. [\"{value}\"]"),
+    ("schema.type_mismatch", "类型错误：应为{kind}", "Type error: expected {kind}"),
+    ("schema.invalid_value", "未知的取值 \"{value}\"，已知可选值：{options}", "Unknown value \"{value}\"; known values: {options}"),
+    ("schema.did_you_mean", "是否为 \"{value}\"？", "Did you mean \"{value}\"?"),
+    ("schema.unknown_key", "未知的配置项 \"{key}\"", "Unknown setting \"{key}\""),
+    ("schema.json_error", "JSON 格式错误: {error}", "Invalid JSON: {error}"),
+    ("schema.toml_error", "TOML 格式错误: {error}", "Invalid TOML: {error}"),
+    ("schema.undefined_provider", "model_providers 中没有定义 \"{name}\"", "\"{name}\" is not defined in model_providers"),
+    ("schema.undefined_profile", "profiles 中没有定义 \"{name}\"", "\"{name}\" is not defined in profiles"),
+    ("schema.add_table", "添加 [{table}] 表", "Add a [{table}] table"),
+    ("schema.validation_failed", "{file} 校验失败", "{file} failed validation"),
+];
+
+/// 按当前语言查找消息，目录中没有的 ID 原样返回
+pub fn text(id: &str) -> &str {
+    let locale = current();
+    MESSAGES
+        .iter()
+        .find(|(key, _, _)| *key == id)
+        .map(|(_, zh, en)| match locale {
+            Locale::ZhCn => *zh,
+            Locale::EnUs => *en,
+        })
+        .unwrap_or_else(|| {
+            log::warn!("消息目录中缺少 {}", id);
+            id
+        })
+}
+
+/// 查找消息并替换 {name} 占位符
+pub fn format(id: &str, args: &[(&str, &dyn Display)]) -> String {
+    let mut message = text(id).to_string();
+    for (name, value) in args {
+        message = message.replace(&format!("{{{}}}", name), &value.to_string());
+    }
+    message
+}
+
+/// 按 ID 获取当前语言的消息：`t!("fs.read_failed")`、`t!("config.malformed", file = "auth.json")`
+macro_rules! t {
+    ($id:expr) => {
+        $crate::i18n::text($id).to_string()
+    };
+    ($id:expr, $($name:ident = $value:expr),+ $(,)?) => {
+        $crate::i18n::format($id, &[$((stringify!($name), &$value as &dyn std::fmt::Display)),+])
+    };
+}
+
+pub(crate) use t;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    /// 源码中所有 t!("...") 使用的消息 ID（不含本文件）
+    fn used_ids() -> BTreeSet<String> {
+        let src = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut ids = BTreeSet::new();
+        for entry in std::fs::read_dir(src).unwrap().flatten() {
+            let path = entry.path();
+            if path.extension().is_none_or(|ext| ext != "rs") || path.ends_with("i18n.rs") {
+                continue;
+            }
+            let content = std::fs::read_to_string(&path).unwrap();
+            for (pos, _) in content.match_indices("t!(") {
+                let preceded_by_ident = content[..pos]
+                    .chars()
+                    .next_back()
+                    .is_some_and(|c| c.is_alphanumeric() || c == '_');
+                let Some(rest) = content[pos + 3..].trim_start().strip_prefix('"') else {
+                    continue;
+                };
+                if !preceded_by_ident {
+                    ids.insert(rest[..rest.find('"').unwrap()].to_string());
+                }
+            }
+        }
+        ids
+    }
+
+    #[test]
+    fn every_used_id_is_in_catalog_and_every_entry_is_used() {
+        let catalog: BTreeSet<String> = MESSAGES.iter().map(|(id, _, _)| id.to_string()).collect();
+        assert_eq!(catalog.len(), MESSAGES.len(), "重复的消息 ID");
+        let used = used_ids();
+        let missing: Vec<&String> = used.difference(&catalog).collect();
+        assert!(missing.is_empty(), "消息目录中缺少: {missing:?}");
+        let unused: Vec<&String> = catalog.difference(&used).collect();
+        assert!(unused.is_empty(), "未使用的消息: {unused:?}");
+    }
+
+    #[test]
+    fn parses_locale_tags() {
+        assert_eq!(Locale::parse("zh-CN"), Some(Locale::ZhCn));
+        assert_eq!(Locale::parse("zh_CN.UTF-8"), Some(Locale::ZhCn));
+        assert_eq!(Locale::parse("zh_TW.UTF-8@stroke"), Some(Locale::ZhCn));
+        assert_eq!(Locale::parse("en-US"), Some(Locale::EnUs));
+        assert_eq!(Locale::parse("de_DE.UTF-8"), Some(Locale::EnUs));
+        assert_eq!(Locale::parse("C"), None);
+        assert_eq!(Locale::parse("C.UTF-8"), None);
+        assert_eq!(Locale::parse("POSIX"), None);
+        assert_eq!(Locale::parse(""), None);
+    }
+}
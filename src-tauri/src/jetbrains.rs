@@ -0,0 +1,701 @@
+use crate::error::{AppError, AppResult, ErrorKind, SuggestedAction};
+use crate::i18n::t;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 支持的 JetBrains IDE 配置目录前缀（目录名形如 "PyCharm2024.2"）
+fn jetbrains_products() -> Vec<(&'static str, &'static str)> {
+    vec![
+        ("IntelliJIdea", "IntelliJ IDEA Ultimate"),
+        ("IdeaIC", "IntelliJ IDEA Community"),
+        ("PyCharmCE", "PyCharm Community"),
+        ("PyCharm", "PyCharm"),
+        ("GoLand", "GoLand"),
+        ("WebStorm", "WebStorm"),
+        ("PhpStorm", "PhpStorm"),
+        ("CLion", "CLion"),
+        ("Rider", "Rider"),
+        ("RubyMine", "RubyMine"),
+        ("RustRover", "RustRover"),
+        ("DataGrip", "DataGrip"),
+        ("DataSpell", "DataSpell"),
+        ("Aqua", "Aqua"),
+    ]
+}
+
+/// Claude Code / Codex 插件通过 IDE 内置终端启动 CLI，
+/// 因此把环境变量写入终端设置（Settings > Tools > Terminal > Environment variables）
+const TERMINAL_OPTIONS_FILE: &str = "terminal.xml";
+
+/// 新建 terminal.xml 时使用的模板
+const TERMINAL_OPTIONS_TEMPLATE: &str = r#"<application>
+  <component name="TerminalOptionsProvider">
+    <option name="envDataOptions">
+      <EnvironmentVariablesDataOptions>
+        <option name="envs">
+          <map>
+          </map>
+        </option>
+      </EnvironmentVariablesDataOptions>
+    </option>
+  </component>
+</application>
+"#;
+
+/// 检测到的 JetBrains IDE 安装
+#[derive(Debug, Clone, Serialize)]
+pub struct JetBrainsIde {
+    pub product: String,
+    pub version: String,
+    pub config_dir: String,
+    pub terminal_options_path: String,
+    pub claude_configured: bool,
+    pub codex_configured: bool,
+}
+
+/// 获取 JetBrains 配置根目录
+/// Linux: ~/.config/JetBrains, macOS: ~/Library/Application Support/JetBrains, Windows: %APPDATA%\JetBrains
+pub fn jetbrains_config_root() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("JetBrains"))
+}
+
+/// 从目录名解析产品名称和版本号
+fn parse_product_dir(dir_name: &str) -> Option<(&'static str, String)> {
+    for (prefix, display_name) in jetbrains_products() {
+        if let Some(version) = dir_name.strip_prefix(prefix) {
+            // 前缀之后必须紧跟版本号，避免 "PyCharm" 误匹配 "PyCharmCE"
+            if version.chars().next().is_some_and(|c| c.is_ascii_digit()) {
+                return Some((display_name, version.to_string()));
+            }
+        }
+    }
+    None
+}
+
+/// 扫描指定根目录下的 IDE 配置目录
+pub fn discover_ides_in(root: &Path) -> Vec<JetBrainsIde> {
+    let mut ides = Vec::new();
+
+    let Ok(entries) = fs::read_dir(root) else {
+        return ides;
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_dir() {
+            continue;
+        }
+        let dir_name = entry.file_name().to_string_lossy().to_string();
+        let Some((product, version)) = parse_product_dir(&dir_name) else {
+            continue;
+        };
+
+        let terminal_options_path = path.join("options").join(TERMINAL_OPTIONS_FILE);
+        let content = fs::read_to_string(&terminal_options_path).unwrap_or_default();
+
+        ides.push(JetBrainsIde {
+            product: product.to_string(),
+            version,
+            config_dir: path.to_string_lossy().to_string(),
+            terminal_options_path: terminal_options_path.to_string_lossy().to_string(),
+            claude_configured: content.contains(&entry_key_marker("ANTHROPIC_BASE_URL")),
+            codex_configured: content.contains(&entry_key_marker("key88")),
+        });
+    }
+
+    ides.sort_by(|a, b| {
+        a.product
+            .cmp(&b.product)
+            .then_with(|| version_key(&b.version).cmp(&version_key(&a.version)))
+    });
+    ides
+}
+
+/// 版本号按数字逐段比较（2024.10 排在 2024.2 之后）
+fn version_key(version: &str) -> Vec<u32> {
+    version
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
+/// 检测当前用户安装的所有 JetBrains IDE
+pub fn discover_ides() -> Vec<JetBrainsIde> {
+    match jetbrains_config_root() {
+        Some(root) => discover_ides_in(&root),
+        None => Vec::new(),
+    }
+}
+
+/// XML 属性值转义
+fn escape_xml_attr(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// 用于查找环境变量条目的文本标记
+fn entry_key_marker(key: &str) -> String {
+    format!(r#"<entry key="{}""#, escape_xml_attr(key))
+}
+
+/// 从 start 开始查找标签结束位置（返回 '>' 之后的下标）
+fn tag_end(content: &str, start: usize) -> Option<usize> {
+    content[start..].find('>').map(|i| start + i + 1)
+}
+
+/// terminal.xml 结构异常，需要用户手动检查文件
+fn malformed(message: String) -> AppError {
+    AppError::new(ErrorKind::Parse, message).with_action(SuggestedAction::EditFile)
+}
+
+/// 在 terminal.xml 内容中插入或更新单个环境变量（在原文本基础上修改，保持其余内容不变）
+fn upsert_env_entry(content: &str, key: &str, value: &str) -> AppResult<String> {
+    let entry = format!(
+        r#"<entry key="{}" value="{}" />"#,
+        escape_xml_attr(key),
+        escape_xml_attr(value)
+    );
+    let mut xml = content.to_string();
+
+    // 1. 已存在该变量：替换整个 entry 标签
+    if let Some(start) = xml.find(&entry_key_marker(key)) {
+        let end = tag_end(&xml, start).ok_or_else(|| malformed(t!("jetbrains.unclosed_tag", tag = "entry")))?;
+        xml.replace_range(start..end, &entry);
+        return Ok(xml);
+    }
+
+    // 2. 缺少 TerminalOptionsProvider 组件：在 </application> 前插入完整组件
+    let component_marker = r#"<component name="TerminalOptionsProvider""#;
+    let Some(component_start) = xml.find(component_marker) else {
+        let app_end = xml
+            .rfind("</application>")
+            .ok_or_else(|| malformed(t!("jetbrains.missing_application_end")))?;
+        let component = TERMINAL_OPTIONS_TEMPLATE
+            .trim_start_matches("<application>\n")
+            .trim_end_matches("</application>\n")
+            .replace("          </map>", &format!("            {}\n          </map>", entry));
+        xml.insert_str(app_end, &component);
+        return Ok(xml);
+    };
+
+    // 组件为自闭合标签时，替换为完整组件
+    let component_tag_end = tag_end(&xml, component_start)
+        .ok_or_else(|| malformed(t!("jetbrains.unclosed_tag", tag = "component")))?;
+    if xml[component_start..component_tag_end].ends_with("/>") {
+        let component = TERMINAL_OPTIONS_TEMPLATE
+            .trim_start_matches("<application>\n  ")
+            .trim_end_matches("\n</application>\n")
+            .replace("          </map>", &format!("            {}\n          </map>", entry));
+        xml.replace_range(component_start..component_tag_end, &component);
+        return Ok(xml);
+    }
+
+    let component_end = xml[component_start..]
+        .find("</component>")
+        .map(|i| component_start + i)
+        .ok_or_else(|| malformed(t!("jetbrains.unclosed_tag", tag = "component")))?;
+
+    // 3. 组件中没有 envDataOptions：在 </component> 前插入
+    let env_options_marker = r#"<option name="envDataOptions""#;
+    let Some(env_options_start) = xml[component_start..component_end]
+        .find(env_options_marker)
+        .map(|i| component_start + i)
+    else {
+        let options = format!(
+            "  <option name=\"envDataOptions\">\n      <EnvironmentVariablesDataOptions>\n        <option name=\"envs\">\n          <map>\n            {}\n          </map>\n        </option>\n      </EnvironmentVariablesDataOptions>\n    </option>\n  ",
+            entry
+        );
+        xml.insert_str(component_end, &options);
+        return Ok(xml);
+    };
+
+    // 4. 找到 <map> 并在 </map> 前插入条目；只设置过 passParentEnvs 时没有 envs/map，需要补上
+    let Some(map_start) = xml[env_options_start..component_end]
+        .find("<map")
+        .map(|i| env_options_start + i)
+    else {
+        insert_envs_option(&mut xml, env_options_start, component_end, &entry)?;
+        return Ok(xml);
+    };
+    let map_tag_end = tag_end(&xml, map_start)
+        .ok_or_else(|| malformed(t!("jetbrains.unclosed_tag", tag = "map")))?;
+
+    if xml[map_start..map_tag_end].ends_with("/>") {
+        xml.replace_range(
+            map_start..map_tag_end,
+            &format!("<map>\n            {}\n          </map>", entry),
+        );
+        return Ok(xml);
+    }
+
+    let map_end = xml[map_tag_end..component_end]
+        .find("</map>")
+        .map(|i| map_tag_end + i)
+        .ok_or_else(|| malformed(t!("jetbrains.unclosed_tag", tag = "map")))?;
+
+    // 保持 </map> 所在行的缩进
+    let line_start = xml[..map_end].rfind('\n').map(|i| i + 1).unwrap_or(map_end);
+    let map_indent = xml[line_start..map_end].to_string();
+    if map_indent.trim().is_empty() {
+        xml.insert_str(line_start, &format!("{}  {}\n", map_indent, entry));
+    } else {
+        xml.insert_str(map_end, &entry);
+    }
+
+    Ok(xml)
+}
+
+/// 在 EnvironmentVariablesDataOptions 中补上包含 entry 的 envs 选项
+fn insert_envs_option(xml: &mut String, from: usize, to: usize, entry: &str) -> AppResult<()> {
+    let data_marker = "<EnvironmentVariablesDataOptions";
+    let data_start = xml[from..to]
+        .find(data_marker)
+        .map(|i| from + i)
+        .ok_or_else(|| malformed(t!("jetbrains.missing_map")))?;
+    let data_tag_end = tag_end(xml, data_start)
+        .ok_or_else(|| malformed(t!("jetbrains.unclosed_tag", tag = "EnvironmentVariablesDataOptions")))?;
+    let line_start = xml[..data_start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let indent = xml[line_start..data_start].to_string();
+    let indent = if indent.trim().is_empty() { indent } else { String::new() };
+    let envs = format!(
+        "{i}  <option name=\"envs\">\n{i}    <map>\n{i}      {e}\n{i}    </map>\n{i}  </option>\n",
+        i = indent,
+        e = entry
+    );
+
+    // 自闭合标签：替换为完整标签
+    if xml[data_start..data_tag_end].ends_with("/>") {
+        let replacement = format!("<EnvironmentVariablesDataOptions>\n{}{}</EnvironmentVariablesDataOptions>", envs, indent);
+        xml.replace_range(data_start..data_tag_end, &replacement);
+        return Ok(());
+    }
+
+    let data_end = xml[data_tag_end..to]
+        .find("</EnvironmentVariablesDataOptions>")
+        .map(|i| data_tag_end + i)
+        .ok_or_else(|| malformed(t!("jetbrains.unclosed_tag", tag = "EnvironmentVariablesDataOptions")))?;
+    let close_line_start = xml[..data_end].rfind('\n').map(|i| i + 1).unwrap_or(data_end);
+    if xml[close_line_start..data_end].trim().is_empty() {
+        xml.insert_str(close_line_start, &envs);
+    } else {
+        xml.insert_str(data_end, &format!("<option name=\"envs\"><map>{}</map></option>", entry));
+    }
+    Ok(())
+}
+
+/// XML 属性值反转义
+fn unescape_xml_attr(value: &str) -> String {
+    value
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+/// 读取 terminal.xml 中某个环境变量的值
+pub fn read_env_entry(content: &str, key: &str) -> Option<String> {
+    let start = content.find(&entry_key_marker(key))?;
+    let end = tag_end(content, start)?;
+    let tag = &content[start..end];
+    let value_start = tag.find("value=\"")? + "value=\"".len();
+    let value_len = tag[value_start..].find('"')?;
+    Some(unescape_xml_attr(&tag[value_start..value_start + value_len]))
+}
+
+/// 从 terminal.xml 中删除某个环境变量（连同所在行）
+pub fn remove_env_entry(content: &str, key: &str) -> String {
+    let Some(start) = content.find(&entry_key_marker(key)) else {
+        return content.to_string();
+    };
+    let Some(end) = tag_end(content, start) else {
+        return content.to_string();
+    };
+
+    // 条目独占一行时删除整行
+    let line_start = content[..start].rfind('\n').map(|i| i + 1).unwrap_or(start);
+    let (start, end) = if content[line_start..start].trim().is_empty() && content[end..].starts_with('\n') {
+        (line_start, end + 1)
+    } else {
+        (start, end)
+    };
+
+    let mut result = content.to_string();
+    result.replace_range(start..end, "");
+    result
+}
+
+/// 在 terminal.xml 中写入一组环境变量
+pub fn upsert_terminal_envs(content: Option<&str>, envs: &[(&str, &str)]) -> AppResult<String> {
+    let mut xml = match content {
+        Some(c) if !c.trim().is_empty() => c.to_string(),
+        _ => TERMINAL_OPTIONS_TEMPLATE.to_string(),
+    };
+
+    if !xml.contains("<application") {
+        return Err(malformed(t!("jetbrains.not_config")));
+    }
+
+    for (key, value) in envs {
+        xml = upsert_env_entry(&xml, key, value)?;
+    }
+
+    Ok(xml)
+}
+
+/// 将环境变量写入单个 IDE 的终端设置
+fn write_terminal_envs(config_dir: &Path, envs: &[(&str, &str)]) -> AppResult<PathBuf> {
+    let options_path = config_dir.join("options").join(TERMINAL_OPTIONS_FILE);
+    // 持有写锁直到写入完成，避免读取后、写入前被其他进程修改
+    let _lock = crate::config::lock_for_update(&options_path)?;
+
+    // 首次配置前创建备份，并记录原值以便撤销
+    crate::config::create_backup_if_not_exists(&options_path)?;
+    let keys: Vec<[&str; 1]> = envs.iter().map(|(key, _)| [*key]).collect();
+    let keys: Vec<&[&str]> = keys.iter().map(|k| k.as_slice()).collect();
+    crate::changes::remember(crate::changes::ManagedFormat::JetBrainsXml, &options_path, &keys);
+
+    let existing = if options_path.exists() {
+        Some(
+            fs::read_to_string(&options_path)
+                .map_err(|e| AppError::io(t!("fs.read_named_failed", file = TERMINAL_OPTIONS_FILE), &options_path, e))?,
+        )
+    } else {
+        None
+    };
+
+    let new_content = upsert_terminal_envs(existing.as_deref(), envs)?;
+    crate::config::atomic_write(&options_path, new_content.as_bytes())?;
+
+    Ok(options_path)
+}
+
+/// 解析要配置的 IDE 目录：未指定时使用全部检测到的 IDE，指定时只允许检测到的目录
+fn resolve_targets(targets: Vec<String>) -> AppResult<Vec<PathBuf>> {
+    let discovered: Vec<PathBuf> = discover_ides()
+        .into_iter()
+        .map(|ide| PathBuf::from(ide.config_dir))
+        .collect();
+    resolve_targets_in(targets, &discovered)
+}
+
+fn resolve_targets_in(targets: Vec<String>, discovered: &[PathBuf]) -> AppResult<Vec<PathBuf>> {
+    if targets.is_empty() {
+        if discovered.is_empty() {
+            return Err(AppError::not_found(t!("jetbrains.no_ide")));
+        }
+        return Ok(discovered.to_vec());
+    }
+
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for target in targets {
+        let path = PathBuf::from(&target);
+        if !discovered.contains(&path) {
+            return Err(AppError::invalid_input(t!("jetbrains.unsupported_path")).with_path(&path));
+        }
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
+}
+
+/// 对每个目标 IDE 写入环境变量，汇总结果
+fn configure_targets(targets: Vec<String>, envs: &[(&str, &str)]) -> AppResult<String> {
+    let mut succeeded = Vec::new();
+    let mut failed = Vec::new();
+
+    for dir in resolve_targets(targets)? {
+        match write_terminal_envs(&dir, envs) {
+            Ok(path) => succeeded.push(path.display().to_string()),
+            Err(e) => failed.push(format!("{}: {}", dir.display(), e)),
+        }
+    }
+
+    if succeeded.is_empty() {
+        return Err(AppError::new(ErrorKind::Other, t!("jetbrains.failed", details = failed.join("\n"))));
+    }
+
+    let mut msg = t!("jetbrains.configured", targets = succeeded.join("\n"));
+    if !failed.is_empty() {
+        msg.push_str(&t!("jetbrains.partial_failed", details = failed.join("\n")));
+    }
+    msg.push_str(&t!("jetbrains.restart_terminal"));
+    Ok(msg)
+}
+
+/// 配置 JetBrains Claude Code 插件：在终端环境变量中写入 88code 地址和密钥
+pub fn configure_jetbrains_claude(
+    base_url: String,
+    api_key: String,
+    targets: Vec<String>,
+) -> AppResult<String> {
+    let envs = [
+        ("ANTHROPIC_BASE_URL", base_url.as_str()),
+        ("ANTHROPIC_AUTH_TOKEN", api_key.as_str()),
+    ];
+    let msg = configure_targets(targets, &envs)?;
+    log::info!("JetBrains Claude Code 配置成功: {}", base_url);
+    Ok(msg)
+}
+
+/// 配置 JetBrains Codex 插件：在终端环境变量中写入 key88
+/// base_url 由 ~/.codex/config.toml 中的 88code provider 提供
+pub fn configure_jetbrains_codex(api_key: String, targets: Vec<String>) -> AppResult<String> {
+    let codex_config = crate::config::get_codex_config_path();
+    if !codex_config.exists() {
+        return Err(AppError::not_found(t!("jetbrains.codex_required")).with_path(&codex_config));
+    }
+
+    let envs = [("key88", api_key.as_str())];
+    let msg = configure_targets(targets, &envs)?;
+    log::info!("JetBrains Codex 配置成功");
+    Ok(msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ENVS: &[(&str, &str)] = &[("ANTHROPIC_BASE_URL", "https://example.com/api")];
+
+    /// 只设置过其它终端选项，没有 envDataOptions
+    const WITHOUT_ENV_OPTIONS: &str = r#"<application>
+  <component name="TerminalOptionsProvider">
+    <option name="shellPath" value="/bin/zsh" />
+  </component>
+</application>
+"#;
+
+    /// 只取消过 "Include parent environment variables"，没有 envs/map
+    const WITHOUT_MAP: &str = r#"<application>
+  <component name="TerminalOptionsProvider">
+    <option name="envDataOptions">
+      <EnvironmentVariablesDataOptions>
+        <option name="passParentEnvs" value="false" />
+      </EnvironmentVariablesDataOptions>
+    </option>
+  </component>
+</application>
+"#;
+
+    const WITH_ENTRIES: &str = r#"<application>
+  <component name="TerminalOptionsProvider">
+    <option name="envDataOptions">
+      <EnvironmentVariablesDataOptions>
+        <option name="envs">
+          <map>
+            <entry key="ANTHROPIC_BASE_URL" value="https://old.example.com" />
+            <entry key="FOO" value="bar" />
+          </map>
+        </option>
+      </EnvironmentVariablesDataOptions>
+    </option>
+  </component>
+</application>
+"#;
+
+    fn entry_count(xml: &str, key: &str) -> usize {
+        xml.matches(&entry_key_marker(key)).count()
+    }
+
+    #[test]
+    fn creates_template_for_missing_or_empty_file() {
+        for content in [None, Some(""), Some("  \n")] {
+            let xml = upsert_terminal_envs(content, ENVS).unwrap();
+            assert!(xml.starts_with("<application>"));
+            assert_eq!(read_env_entry(&xml, "ANTHROPIC_BASE_URL").as_deref(), Some("https://example.com/api"));
+        }
+    }
+
+    #[test]
+    fn rejects_non_application_xml() {
+        assert!(upsert_terminal_envs(Some("<project version=\"4\" />"), ENVS).is_err());
+    }
+
+    #[test]
+    fn inserts_component_when_missing() {
+        let content = "<application>\n  <component name=\"Other\" />\n</application>\n";
+        let xml = upsert_terminal_envs(Some(content), ENVS).unwrap();
+        assert!(xml.contains("<component name=\"Other\" />"));
+        assert!(xml.contains("<component name=\"TerminalOptionsProvider\">"));
+        assert!(xml.trim_end().ends_with("</application>"));
+        assert_eq!(read_env_entry(&xml, "ANTHROPIC_BASE_URL").as_deref(), Some("https://example.com/api"));
+    }
+
+    #[test]
+    fn expands_self_closing_component() {
+        let content = "<application>\n  <component name=\"TerminalOptionsProvider\" />\n</application>\n";
+        let xml = upsert_terminal_envs(Some(content), ENVS).unwrap();
+        assert!(!xml.contains("TerminalOptionsProvider\" />"));
+        assert_eq!(xml.matches("</component>").count(), 1);
+        assert_eq!(read_env_entry(&xml, "ANTHROPIC_BASE_URL").as_deref(), Some("https://example.com/api"));
+    }
+
+    #[test]
+    fn inserts_env_options_when_missing() {
+        let xml = upsert_terminal_envs(Some(WITHOUT_ENV_OPTIONS), ENVS).unwrap();
+        assert!(xml.contains("<option name=\"shellPath\" value=\"/bin/zsh\" />"));
+        assert!(xml.contains("<option name=\"envDataOptions\">"));
+        let env_options = xml.find("envDataOptions").unwrap();
+        assert!(env_options < xml.find("</component>").unwrap());
+        assert_eq!(read_env_entry(&xml, "ANTHROPIC_BASE_URL").as_deref(), Some("https://example.com/api"));
+    }
+
+    #[test]
+    fn inserts_map_when_missing() {
+        let xml = upsert_terminal_envs(Some(WITHOUT_MAP), ENVS).unwrap();
+        assert!(xml.contains("<option name=\"passParentEnvs\" value=\"false\" />"));
+        let map = xml.find("<map>").unwrap();
+        assert!(xml.find("<EnvironmentVariablesDataOptions>").unwrap() < map);
+        assert!(map < xml.find("</EnvironmentVariablesDataOptions>").unwrap());
+        assert!(xml.contains("\n          <map>\n            <entry key=\"ANTHROPIC_BASE_URL\""));
+        assert_eq!(read_env_entry(&xml, "ANTHROPIC_BASE_URL").as_deref(), Some("https://example.com/api"));
+    }
+
+    #[test]
+    fn expands_self_closing_data_options() {
+        let content = WITHOUT_MAP.replace(
+            "<EnvironmentVariablesDataOptions>\n        <option name=\"passParentEnvs\" value=\"false\" />\n      </EnvironmentVariablesDataOptions>",
+            "<EnvironmentVariablesDataOptions />",
+        );
+        let xml = upsert_terminal_envs(Some(&content), ENVS).unwrap();
+        assert!(!xml.contains("<EnvironmentVariablesDataOptions />"));
+        assert_eq!(xml.matches("</EnvironmentVariablesDataOptions>").count(), 1);
+        assert_eq!(read_env_entry(&xml, "ANTHROPIC_BASE_URL").as_deref(), Some("https://example.com/api"));
+    }
+
+    #[test]
+    fn expands_self_closing_map() {
+        let content = WITH_ENTRIES.replace(
+            "<map>\n            <entry key=\"ANTHROPIC_BASE_URL\" value=\"https://old.example.com\" />\n            <entry key=\"FOO\" value=\"bar\" />\n          </map>",
+            "<map />",
+        );
+        let xml = upsert_terminal_envs(Some(&content), ENVS).unwrap();
+        assert!(!xml.contains("<map />"));
+        assert_eq!(read_env_entry(&xml, "ANTHROPIC_BASE_URL").as_deref(), Some("https://example.com/api"));
+    }
+
+    #[test]
+    fn replaces_existing_entry_and_keeps_others() {
+        let xml = upsert_terminal_envs(Some(WITH_ENTRIES), &[("ANTHROPIC_BASE_URL", "https://example.com/api"), ("NEW", "1")]).unwrap();
+        assert_eq!(entry_count(&xml, "ANTHROPIC_BASE_URL"), 1);
+        assert_eq!(read_env_entry(&xml, "ANTHROPIC_BASE_URL").as_deref(), Some("https://example.com/api"));
+        assert_eq!(read_env_entry(&xml, "FOO").as_deref(), Some("bar"));
+        // 新条目插入到 </map> 前并沿用缩进
+        assert!(xml.contains("            <entry key=\"NEW\" value=\"1\" />\n          </map>"));
+    }
+
+    #[test]
+    fn upsert_is_idempotent() {
+        let once = upsert_terminal_envs(Some(WITH_ENTRIES), ENVS).unwrap();
+        let twice = upsert_terminal_envs(Some(&once), ENVS).unwrap();
+        assert_eq!(once, twice);
+    }
+
+    #[test]
+    fn escapes_and_unescapes_values() {
+        let value = r#"a&b<c>"d"'e'"#;
+        let xml = upsert_terminal_envs(None, &[("KEY", value)]).unwrap();
+        assert!(xml.contains("a&amp;b&lt;c&gt;&quot;d&quot;&apos;e&apos;"));
+        assert_eq!(read_env_entry(&xml, "KEY").as_deref(), Some(value));
+    }
+
+    #[test]
+    fn removes_entry_with_its_line() {
+        let xml = remove_env_entry(WITH_ENTRIES, "ANTHROPIC_BASE_URL");
+        assert!(read_env_entry(&xml, "ANTHROPIC_BASE_URL").is_none());
+        assert_eq!(read_env_entry(&xml, "FOO").as_deref(), Some("bar"));
+        assert!(xml.contains("          <map>\n            <entry key=\"FOO\""));
+        assert_eq!(remove_env_entry(WITH_ENTRIES, "MISSING"), WITH_ENTRIES);
+    }
+
+    #[test]
+    fn parses_product_dirs() {
+        assert_eq!(parse_product_dir("PyCharm2024.2"), Some(("PyCharm", "2024.2".to_string())));
+        assert_eq!(parse_product_dir("PyCharmCE2023.3"), Some(("PyCharm Community", "2023.3".to_string())));
+        assert_eq!(parse_product_dir("IdeaIC2024.1"), Some(("IntelliJ IDEA Community", "2024.1".to_string())));
+        assert_eq!(parse_product_dir("PyCharm"), None);
+        assert_eq!(parse_product_dir("JetBrainsClient2024.2"), None);
+        assert_eq!(parse_product_dir("consentOptions"), None);
+    }
+
+    #[test]
+    fn discovers_ides_in_config_root() {
+        let root = tempfile::tempdir().unwrap();
+        let configured = upsert_terminal_envs(None, &[("ANTHROPIC_BASE_URL", "https://example.com/api")]).unwrap();
+        for (dir, terminal) in [
+            ("PyCharm2024.1", None),
+            ("PyCharm2024.2", Some(configured.as_str())),
+            ("GoLand2024.2", Some(WITHOUT_ENV_OPTIONS)),
+            ("consentOptions", None),
+        ] {
+            let options = root.path().join(dir).join("options");
+            fs::create_dir_all(&options).unwrap();
+            if let Some(content) = terminal {
+                fs::write(options.join(TERMINAL_OPTIONS_FILE), content).unwrap();
+            }
+        }
+        fs::write(root.path().join("WebStorm2024.2"), "not a directory").unwrap();
+
+        let ides = discover_ides_in(root.path());
+        let found: Vec<(&str, &str, bool)> = ides
+            .iter()
+            .map(|ide| (ide.product.as_str(), ide.version.as_str(), ide.claude_configured))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("GoLand", "2024.2", false),
+                ("PyCharm", "2024.2", true),
+                ("PyCharm", "2024.1", false),
+            ]
+        );
+        assert!(ides.iter().all(|ide| !ide.codex_configured));
+        assert!(ides[1].terminal_options_path.ends_with(TERMINAL_OPTIONS_FILE));
+    }
+
+    #[test]
+    fn discover_ignores_missing_root() {
+        let root = tempfile::tempdir().unwrap();
+        assert!(discover_ides_in(&root.path().join("missing")).is_empty());
+    }
+
+    #[test]
+    fn sorts_versions_numerically() {
+        let root = tempfile::tempdir().unwrap();
+        for dir in ["PyCharm2024.2", "PyCharm2024.10", "PyCharm2023.3"] {
+            fs::create_dir_all(root.path().join(dir).join("options")).unwrap();
+        }
+        let versions: Vec<String> = discover_ides_in(root.path())
+            .into_iter()
+            .map(|ide| ide.version)
+            .collect();
+        assert_eq!(versions, vec!["2024.10", "2024.2", "2023.3"]);
+    }
+
+    #[test]
+    fn resolves_only_discovered_targets() {
+        let discovered = vec![PathBuf::from("/cfg/PyCharm2024.2"), PathBuf::from("/cfg/GoLand2024.2")];
+
+        assert_eq!(resolve_targets_in(Vec::new(), &discovered).unwrap(), discovered);
+        assert_eq!(
+            resolve_targets_in(
+                vec!["/cfg/GoLand2024.2".to_string(), "/cfg/GoLand2024.2".to_string()],
+                &discovered
+            )
+            .unwrap(),
+            vec![PathBuf::from("/cfg/GoLand2024.2")]
+        );
+
+        let err = resolve_targets_in(vec!["/tmp/elsewhere".to_string()], &discovered).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidInput);
+        let err = resolve_targets_in(Vec::new(), &[]).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,571 @@
+use serde_json::Value;
+
+/// 移除 JSON/JSONC 中的注释（简单实现）
+/// 处理单行注释 // 和多行注释 /* */，注释按字节替换为空格并保留换行，
+/// 使解析错误的行号和列号与原文件一致
+pub fn strip_json_comments(content: &str) -> String {
+    let mut result = String::new();
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    let mut escape_next = false;
+
+    // 用等长的空白替代被移除的字符
+    let blank = |result: &mut String, c: char| {
+        if c == '\n' {
+            result.push('\n');
+        } else {
+            result.extend(std::iter::repeat_n(' ', c.len_utf8()));
+        }
+    };
+
+    while let Some(ch) = chars.next() {
+        if escape_next {
+            result.push(ch);
+            escape_next = false;
+            continue;
+        }
+
+        if ch == '\\' && in_string {
+            result.push(ch);
+            escape_next = true;
+            continue;
+        }
+
+        if ch == '"' {
+            in_string = !in_string;
+            result.push(ch);
+            continue;
+        }
+
+        if in_string {
+            result.push(ch);
+            continue;
+        }
+
+        // 处理注释（仅在非字符串中）
+        if ch == '/' {
+            if let Some(&next_ch) = chars.peek() {
+                if next_ch == '/' {
+                    // 单行注释，跳到行尾
+                    chars.next(); // 消费第二个 /
+                    result.push_str("  ");
+                    for c in chars.by_ref() {
+                        blank(&mut result, c);
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                    continue;
+                } else if next_ch == '*' {
+                    // 多行注释，跳到 */
+                    chars.next(); // 消费 *
+                    result.push_str("  ");
+                    while let Some(c) = chars.next() {
+                        blank(&mut result, c);
+                        if c == '*' && chars.peek() == Some(&'/') {
+                            chars.next(); // 消费 /
+                            result.push(' ');
+                            break;
+                        }
+                    }
+                    continue;
+                }
+            }
+        }
+
+        result.push(ch);
+    }
+
+    result
+}
+
+/// 修复 JSON 中的尾部逗号（对象和数组最后一个元素后的逗号）
+pub fn fix_json_trailing_commas(content: &str) -> String {
+    let mut result = String::new();
+    let mut chars = content.chars().peekable();
+    let mut in_string = false;
+    let mut escape_next = false;
+
+    while let Some(ch) = chars.next() {
+        if escape_next {
+            result.push(ch);
+            escape_next = false;
+            continue;
+        }
+
+        if ch == '\\' && in_string {
+            result.push(ch);
+            escape_next = true;
+            continue;
+        }
+
+        if ch == '"' {
+            in_string = !in_string;
+            result.push(ch);
+            continue;
+        }
+
+        if in_string {
+            result.push(ch);
+            continue;
+        }
+
+        // 检查是否是尾部逗号（逗号后面只有空白字符，然后是 } 或 ]）
+        if ch == ',' {
+            result.push(ch);
+
+            // 向前看，检查后面是否只有空白和 } 或 ]
+            let mut temp_chars = chars.clone();
+            let mut found_closing = false;
+            let mut is_trailing = false;
+
+            while let Some(&next) = temp_chars.peek() {
+                if next.is_whitespace() {
+                    temp_chars.next();
+                    continue;
+                }
+                if next == '}' || next == ']' {
+                    found_closing = true;
+                    is_trailing = true;
+                }
+                break;
+            }
+
+            // 如果是尾部逗号，用空格替代，保持后续内容的列号不变
+            if is_trailing && found_closing {
+                result.pop();
+                result.push(' ');
+            }
+        } else {
+            result.push(ch);
+        }
+    }
+
+    result
+}
+
+/// 解析 JSONC 内容（移除注释和尾部逗号后按 JSON 解析）
+pub fn parse_jsonc(content: &str) -> Result<Value, serde_json::Error> {
+    let cleaned_content = strip_json_comments(content);
+    let fixed_content = fix_json_trailing_commas(&cleaned_content);
+    serde_json::from_str(&fixed_content)
+}
+
+/// 把 serde_json 报告的列号（行内字节偏移）换算为原文中的字符列号
+pub fn char_column(content: &str, line: usize, column: usize) -> usize {
+    let Some(text) = content.lines().nth(line.saturating_sub(1)) else {
+        return column;
+    };
+    let mut end = column.min(text.len());
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let chars = text[..end].chars().count();
+    // 列号指向的字符本身不完整时（多字节字符中间）按该字符计
+    if end < column.min(text.len()) {
+        chars + 1
+    } else {
+        chars
+    }
+}
+
+/// 在对象最后一个 } 之前追加键值（entries 为不含缩进的 `"key": value` 文本，可多行）
+/// 缩进沿用最后一个成员所在行，空对象时比闭合括号多缩进两格
+pub fn insert_before_closing_brace(content: &mut String, entries: &[String]) {
+    let Some(last_brace_pos) = content.rfind('}') else {
+        return;
+    };
+
+    // 闭合括号所在行的缩进
+    let brace_line_start = content[..last_brace_pos].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    let closing_indent = content[brace_line_start..last_brace_pos]
+        .chars()
+        .take_while(|c| c.is_whitespace())
+        .collect::<String>();
+
+    let before_brace = content[..last_brace_pos].trim_end();
+    let last_line = before_brace.lines().last().unwrap_or("");
+    let is_empty_object = last_line.trim().ends_with('{');
+
+    let indent = if is_empty_object {
+        format!("{}  ", closing_indent)
+    } else {
+        last_line.chars().take_while(|c| c.is_whitespace()).collect::<String>()
+    };
+    // 逗号紧跟在最后一个值之后，避免落入行尾注释
+    let last_value_end = root_object_members(content)
+        .filter(|(_, close)| *close == last_brace_pos)
+        .and_then(|(members, _)| members.last().map(|m| m.value_end));
+    let (needs_comma, comma_pos) = match last_value_end {
+        Some(end) => (content.as_bytes().get(skip_trivia(content.as_bytes(), end)) != Some(&b','), end),
+        None => (!is_empty_object && !last_line.trim_end().ends_with(','), before_brace.len()),
+    };
+
+    let body = entries
+        .iter()
+        .map(|entry| format!("{}{}", indent, entry.replace('\n', &format!("\n{}", indent))))
+        .collect::<Vec<_>>()
+        .join(",\n");
+    let insertion = format!("\n{}\n{}", body, closing_indent);
+
+    let insert_pos = before_brace.len();
+    content.replace_range(insert_pos..last_brace_pos, &insertion);
+    if needs_comma && !is_empty_object {
+        content.insert(comma_pos, ',');
+    }
+}
+
+/// 解析根对象的成员
+fn root_object_members(content: &str) -> Option<(Vec<Member>, usize)> {
+    let start = skip_trivia(content.as_bytes(), 0);
+    if content.as_bytes().get(start) != Some(&b'{') {
+        return None;
+    }
+    object_members(content, start)
+}
+
+/// 跳过空白和注释，返回下一个有效字符的位置
+fn skip_trivia(bytes: &[u8], mut i: usize) -> usize {
+    loop {
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        if bytes.get(i) == Some(&b'/') && bytes.get(i + 1) == Some(&b'/') {
+            while i < bytes.len() && bytes[i] != b'\n' {
+                i += 1;
+            }
+        } else if bytes.get(i) == Some(&b'/') && bytes.get(i + 1) == Some(&b'*') {
+            i += 2;
+            while i + 1 < bytes.len() && !(bytes[i] == b'*' && bytes[i + 1] == b'/') {
+                i += 1;
+            }
+            i = (i + 2).min(bytes.len());
+        } else {
+            return i;
+        }
+    }
+}
+
+/// 跳过字符串（i 指向开头的引号），返回结束引号之后的位置
+fn skip_string(bytes: &[u8], mut i: usize) -> usize {
+    i += 1;
+    while i < bytes.len() && bytes[i] != b'"' {
+        if bytes[i] == b'\\' {
+            i += 1;
+        }
+        i += 1;
+    }
+    (i + 1).min(bytes.len())
+}
+
+/// 跳过一个值，返回值结束的位置
+fn skip_value(bytes: &[u8], i: usize) -> usize {
+    match bytes.get(i) {
+        Some(b'"') => skip_string(bytes, i),
+        Some(b'{') | Some(b'[') => {
+            let mut depth = 0;
+            let mut j = i;
+            while j < bytes.len() {
+                match bytes[j] {
+                    b'"' => {
+                        j = skip_string(bytes, j);
+                        continue;
+                    }
+                    b'/' if matches!(bytes.get(j + 1), Some(b'/') | Some(b'*')) => {
+                        j = skip_trivia(bytes, j);
+                        continue;
+                    }
+                    b'{' | b'[' => depth += 1,
+                    b'}' | b']' => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return j + 1;
+                        }
+                    }
+                    _ => {}
+                }
+                j += 1;
+            }
+            j
+        }
+        _ => {
+            let mut j = i;
+            while j < bytes.len()
+                && !matches!(bytes[j], b',' | b'}' | b']' | b'/')
+                && !bytes[j].is_ascii_whitespace()
+            {
+                j += 1;
+            }
+            j
+        }
+    }
+}
+
+/// 对象成员在原文本中的位置
+struct Member {
+    key: String,
+    key_start: usize,
+    value_start: usize,
+    value_end: usize,
+}
+
+/// 解析对象（obj_start 指向 '{'）的成员，返回成员列表和 '}' 的位置
+fn object_members(content: &str, obj_start: usize) -> Option<(Vec<Member>, usize)> {
+    let bytes = content.as_bytes();
+    let mut members = Vec::new();
+    let mut i = obj_start + 1;
+
+    loop {
+        i = skip_trivia(bytes, i);
+        match bytes.get(i)? {
+            b'}' => return Some((members, i)),
+            b',' => i += 1,
+            b'"' => {
+                let key_start = i;
+                let key_end = skip_string(bytes, i);
+                let key: String = serde_json::from_str(&content[key_start..key_end]).ok()?;
+                i = skip_trivia(bytes, key_end);
+                if bytes.get(i) != Some(&b':') {
+                    return None;
+                }
+                let value_start = skip_trivia(bytes, i + 1);
+                let value_end = skip_value(bytes, value_start);
+                members.push(Member {
+                    key,
+                    key_start,
+                    value_start,
+                    value_end,
+                });
+                i = value_end;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// 按键路径查找值在原文本中的范围（空路径表示根值），重复键以最后一个为准
+pub fn find_value_span(content: &str, path: &[&str]) -> Option<(usize, usize)> {
+    let bytes = content.as_bytes();
+    let mut start = skip_trivia(bytes, 0);
+    let mut end = skip_value(bytes, start);
+
+    for key in path {
+        if bytes.get(start) != Some(&b'{') {
+            return None;
+        }
+        let (members, _) = object_members(content, start)?;
+        let member = members.into_iter().rev().find(|m| m.key == *key)?;
+        start = member.value_start;
+        end = member.value_end;
+    }
+
+    Some((start, end))
+}
+
+/// 获取 pos 所在行的缩进
+fn line_indent(content: &str, pos: usize) -> String {
+    let line_start = content[..pos].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    content[line_start..]
+        .chars()
+        .take_while(|c| *c == ' ' || *c == '\t')
+        .collect()
+}
+
+/// 删除键路径对应的成员（在原文本基础上修改），成员不存在时返回 None
+pub fn remove_member(content: &str, path: &[&str]) -> Option<String> {
+    let (key, parent_path) = path.split_last()?;
+    let (obj_start, _) = find_value_span(content, parent_path)?;
+    if !content[obj_start..].starts_with('{') {
+        return None;
+    }
+    let (members, close) = object_members(content, obj_start)?;
+    let idx = members.iter().rposition(|m| m.key == *key)?;
+
+    // 优先连同后面的逗号一起删除；是最后一个成员时删除前面的逗号
+    let range = if let Some(next) = members.get(idx + 1) {
+        members[idx].key_start..next.key_start
+    } else if idx > 0 {
+        members[idx - 1].value_end..members[idx].value_end
+    } else {
+        (obj_start + 1)..close
+    };
+
+    let mut result = content.to_string();
+    result.replace_range(range, "");
+    Some(result)
+}
+
+/// 设置键路径对应的值（在原文本基础上修改）
+/// 已存在则替换值，不存在则追加到父对象末尾，缺少的父对象会被创建
+pub fn set_member(content: &str, path: &[&str], value: &Value) -> Option<String> {
+    let pretty = serde_json::to_string_pretty(value).ok()?;
+
+    let Some((key, parent_path)) = path.split_last() else {
+        return Some(format!("{}\n", pretty));
+    };
+
+    if let Some((start, end)) = find_value_span(content, path) {
+        let indent = line_indent(content, start);
+        let mut result = content.to_string();
+        result.replace_range(start..end, &pretty.replace('\n', &format!("\n{}", indent)));
+        return Some(result);
+    }
+
+    let content = match find_value_span(content, parent_path) {
+        Some(_) => content.to_string(),
+        None => set_member(content, parent_path, &Value::Object(Default::default()))?,
+    };
+    let (obj_start, obj_end) = find_value_span(&content, parent_path)?;
+    if !content[obj_start..].starts_with('{') {
+        return None;
+    }
+
+    let mut object = content[obj_start..obj_end].to_string();
+    let entry = format!("{}: {}", serde_json::to_string(key).ok()?, pretty);
+    insert_before_closing_brace(&mut object, &[entry]);
+
+    Some(format!("{}{}{}", &content[..obj_start], object, &content[obj_end..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 解析失败时返回 (行, 原文字符列)
+    fn error_position(content: &str) -> (usize, usize) {
+        let err = parse_jsonc(content).unwrap_err();
+        (err.line(), char_column(content, err.line(), err.column()))
+    }
+
+    #[test]
+    fn cleaning_preserves_positions() {
+        let content = "{\n  /* 注释 */ \"a\": 1, // 行尾\n  \"b\": [1, 2,],\n}";
+        let cleaned = fix_json_trailing_commas(&strip_json_comments(content));
+        assert_eq!(cleaned.len(), content.len());
+        assert_eq!(cleaned.lines().count(), content.lines().count());
+        for (original, cleaned) in content.lines().zip(cleaned.lines()) {
+            assert_eq!(original.len(), cleaned.len());
+        }
+        assert_eq!(parse_jsonc(content).unwrap(), serde_json::json!({ "a": 1, "b": [1, 2] }));
+    }
+
+    #[test]
+    fn error_column_after_block_comment() {
+        // 错误位于注释之后：列号应与原文一致
+        let content = "{\n  /* comment */ \"a\" 1\n}";
+        assert_eq!(error_position(content), (2, 21));
+    }
+
+    #[test]
+    fn error_column_after_trailing_comma() {
+        let content = "{\n  \"a\": [1,], \"b\" 2\n}";
+        assert_eq!(error_position(content), (2, 18));
+    }
+
+    #[test]
+    fn error_column_counts_characters() {
+        let content = "{\n  /* 中文 */ \"键\" 1\n}";
+        assert_eq!(error_position(content), (2, 16));
+    }
+
+    #[test]
+    fn error_line_after_multiline_comment() {
+        let content = "{\n  /*\n   * a\n   */\n  \"a\": x\n}";
+        assert_eq!(error_position(content), (5, 8));
+    }
+
+    #[test]
+    fn trailing_commas_inside_strings_are_kept() {
+        let content = r#"{"a": ",}", "b": [",]",],}"#;
+        let value = parse_jsonc(content).unwrap();
+        assert_eq!(value, serde_json::json!({ "a": ",}", "b": [",]"] }));
+    }
+
+    #[test]
+    fn inserts_into_empty_object() {
+        let mut content = "{\n}".to_string();
+        insert_before_closing_brace(&mut content, &["\"a\": 1".to_string()]);
+        assert_eq!(content, "{\n  \"a\": 1\n}");
+    }
+
+    #[test]
+    fn inserts_after_last_member_with_its_indent() {
+        let mut content = "{\n    \"a\": 1 // 注释\n}".to_string();
+        insert_before_closing_brace(&mut content, &["\"b\": {\n  \"c\": 2\n}".to_string()]);
+        assert_eq!(content, "{\n    \"a\": 1, // 注释\n    \"b\": {\n      \"c\": 2\n    }\n}");
+        assert_eq!(parse_jsonc(&content).unwrap(), serde_json::json!({ "a": 1, "b": { "c": 2 } }));
+    }
+
+    #[test]
+    fn inserts_without_duplicate_comma() {
+        let mut content = "{\n  \"a\": 1,\n}".to_string();
+        insert_before_closing_brace(&mut content, &["\"b\": 2".to_string()]);
+        assert_eq!(content, "{\n  \"a\": 1,\n  \"b\": 2\n}");
+        assert_eq!(parse_jsonc(&content).unwrap(), serde_json::json!({ "a": 1, "b": 2 }));
+    }
+
+    #[test]
+    fn finds_nested_value_span() {
+        let content = r#"{ "a": { /* x */ "b": [1, {"c": "}"}] }, "a2": 2 }"#;
+        let (start, end) = find_value_span(content, &["a", "b"]).unwrap();
+        assert_eq!(&content[start..end], r#"[1, {"c": "}"}]"#);
+        let (start, end) = find_value_span(content, &["a2"]).unwrap();
+        assert_eq!(&content[start..end], "2");
+        assert!(find_value_span(content, &["missing"]).is_none());
+        assert!(find_value_span(content, &["a2", "x"]).is_none());
+    }
+
+    #[test]
+    fn duplicate_keys_use_last_occurrence() {
+        let content = r#"{"a": 1, "a": 2}"#;
+        let (start, end) = find_value_span(content, &["a"]).unwrap();
+        assert_eq!(&content[start..end], "2");
+    }
+
+    #[test]
+    fn removes_members_at_any_position() {
+        let content = "{\n  \"a\": 1,\n  \"b\": 2,\n  \"c\": 3\n}";
+        let first = remove_member(content, &["a"]).unwrap();
+        assert_eq!(parse_jsonc(&first).unwrap(), serde_json::json!({ "b": 2, "c": 3 }));
+        let middle = remove_member(content, &["b"]).unwrap();
+        assert_eq!(parse_jsonc(&middle).unwrap(), serde_json::json!({ "a": 1, "c": 3 }));
+        let last = remove_member(content, &["c"]).unwrap();
+        assert_eq!(parse_jsonc(&last).unwrap(), serde_json::json!({ "a": 1, "b": 2 }));
+        assert!(remove_member(content, &["d"]).is_none());
+    }
+
+    #[test]
+    fn removes_only_member_and_keeps_comments() {
+        let content = "{\n  // 保留\n  \"x\": { \"a\": 1 }\n}";
+        let result = remove_member(content, &["x", "a"]).unwrap();
+        assert!(result.contains("// 保留"));
+        assert_eq!(parse_jsonc(&result).unwrap(), serde_json::json!({ "x": {} }));
+    }
+
+    #[test]
+    fn sets_existing_and_new_members() {
+        let content = "{\n  // 注释\n  \"a\": 1\n}";
+        let replaced = set_member(content, &["a"], &serde_json::json!("x")).unwrap();
+        assert_eq!(replaced, "{\n  // 注释\n  \"a\": \"x\"\n}");
+
+        let nested = set_member(content, &["env", "KEY"], &serde_json::json!("v")).unwrap();
+        assert!(nested.contains("// 注释"));
+        assert_eq!(
+            parse_jsonc(&nested).unwrap(),
+            serde_json::json!({ "a": 1, "env": { "KEY": "v" } })
+        );
+    }
+
+    #[test]
+    fn set_member_rejects_non_object_parent() {
+        assert!(set_member(r#"{"a": 1}"#, &["a", "b"], &serde_json::json!(2)).is_none());
+    }
+
+    #[test]
+    fn comment_markers_inside_strings_are_kept() {
+        let content = r#"{"url": "https://example.com/*x*/", "s": "a\"//b"}"#;
+        let value = parse_jsonc(content).unwrap();
+        assert_eq!(value["url"], "https://example.com/*x*/");
+        assert_eq!(value["s"], "a\"//b");
+    }
+}
@@ -1,13 +1,39 @@
 // 88code-desktop modules
+mod account;
+mod api_key;
+mod base_url;
+mod benchmark;
+mod bundle;
+mod changes;
 mod claude_config;
 mod codex_config;
 mod commands;
 mod config;
+mod dotfiles;
 mod env_manager;
+mod error;
+mod history;
+mod i18n;
+mod jetbrains;
+mod jsonc;
+mod manifest;
+mod permissions;
+mod presets;
+mod relay;
+mod schema;
+mod sessions;
+mod usage;
 mod vscode;
+mod watcher;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // 命令行应用团队配置清单时不启动界面
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = manifest::run_cli(&args) {
+        std::process::exit(code);
+    }
+
     tauri::Builder::default()
         .plugin(
             tauri_plugin_log::Builder::default()
@@ -16,20 +42,71 @@ pub fn run() {
         )
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_dialog::init())
+        .setup(|app| {
+            permissions::warn_insecure_permissions();
+            watcher::start(app.handle().clone());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             commands::configure_claude_code,
             commands::configure_codex,
+            commands::check_base_url,
+            commands::check_api_key,
+            commands::list_presets,
+            commands::benchmark_endpoints,
+            commands::get_account_status,
+            commands::get_usage_stats,
+            commands::export_usage_csv,
+            commands::list_sessions,
+            commands::search_sessions,
+            commands::get_session,
+            commands::export_session_markdown,
+            commands::start_relay,
+            commands::update_relay,
+            commands::stop_relay,
+            commands::get_relay_status,
             commands::get_config_paths,
             commands::get_current_claude_config,
             commands::get_current_codex_auth,
+            commands::read_config_for_edit,
             commands::configure_vscode_claude,
             commands::configure_vscode_codex,
             commands::get_vscode_paths,
+            commands::get_vscode_installations,
+            commands::configure_vscode_workspace_codex,
+            commands::get_effective_vscode_codex_setting,
             commands::configure_claude_advanced,
             commands::configure_codex_advanced,
+            commands::validate_claude_advanced,
+            commands::validate_codex_advanced,
             commands::delete_claude_config,
             commands::delete_codex_config,
+            commands::remove_vscode_codex,
+            commands::get_change_journal,
+            commands::revert_all_changes,
+            commands::get_history,
+            commands::export_history,
+            commands::get_permission_issues,
+            commands::fix_permissions,
+            commands::export_bundle,
+            commands::preview_bundle,
+            commands::import_bundle,
+            commands::check_manifest,
+            commands::get_manifest_commands,
+            commands::apply_manifest,
+            commands::get_locale,
+            commands::set_locale,
+            commands::get_config_file_status,
+            commands::get_jetbrains_ides,
+            commands::configure_jetbrains_claude,
+            commands::configure_jetbrains_codex,
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|_app, event| {
+            // 退出时停止本地中转并恢复客户端配置
+            if let tauri::RunEvent::Exit = event {
+                relay::stop_on_exit();
+            }
+        });
 }
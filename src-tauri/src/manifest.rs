@@ -0,0 +1,898 @@
+use crate::changes::{remember, ManagedFormat};
+use crate::config::{
+    get_claude_json_path, get_claude_settings_path, get_codex_auth_path, get_codex_config_path,
+};
+use crate::error::{AppError, AppResult, ErrorKind};
+use crate::i18n::t;
+use crate::jsonc;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// 团队配置清单格式版本，拒绝更高版本
+pub const MANIFEST_VERSION: u32 = 1;
+
+/// 密钥来源：环境变量，或密钥管理工具命令的输出（如 `pass show 88code`、`op read op://...`）
+/// 清单本身不保存密钥，可以提交到仓库
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SecretSource {
+    pub env: Option<String>,
+    pub command: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PermissionsManifest {
+    pub allow: Option<Vec<String>>,
+    pub deny: Option<Vec<String>>,
+}
+
+/// MCP 服务器（stdio）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct McpServer {
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ClaudeManifest {
+    pub base_url: Option<String>,
+    pub api_key: Option<SecretSource>,
+    pub model: Option<String>,
+    pub permissions: Option<PermissionsManifest>,
+    #[serde(default)]
+    pub mcp_servers: BTreeMap<String, McpServer>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CodexManifest {
+    pub base_url: Option<String>,
+    pub api_key: Option<SecretSource>,
+    pub model: Option<String>,
+    pub model_reasoning_effort: Option<String>,
+    #[serde(default)]
+    pub mcp_servers: BTreeMap<String, McpServer>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VscodeManifest {
+    /// ChatGPT 扩展的 chatgpt.apiBase
+    pub api_base: String,
+    /// 编辑器名称（如 "VS Code"、"Cursor"）或 settings.json 路径，为空时使用全部已安装的编辑器
+    #[serde(default)]
+    pub targets: Vec<String>,
+}
+
+/// 团队配置清单：描述期望状态，提交到仓库后由脚本或界面统一应用
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub version: u32,
+    pub claude: Option<ClaudeManifest>,
+    pub codex: Option<CodexManifest>,
+    pub vscode: Option<VscodeManifest>,
+    /// 需要设置的用户环境变量（如 Codex 使用的 key88）及其值的来源
+    #[serde(default)]
+    pub env: BTreeMap<String, SecretSource>,
+}
+
+/// 单项配置与清单的差异（密钥已打码）
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftItem {
+    pub target: String,
+    pub field: String,
+    pub current: Option<String>,
+    pub desired: Option<String>,
+    pub drifted: bool,
+    /// 无法确定期望值的原因（如密钥来源不可用），此时不会修改该项
+    pub error: Option<String>,
+    /// 密钥来自命令且未获准执行（检查时从不执行命令），此时无法比较，也不会修改该项
+    pub unresolved: bool,
+}
+
+/// 检查结果（check_manifest 返回值），in_sync 不包含未解析的项
+#[derive(Debug, Clone, Serialize)]
+pub struct DriftReport {
+    pub items: Vec<DriftItem>,
+    pub in_sync: bool,
+}
+
+/// 应用结果：应用前的差异、执行的修改，以及应用后仍存在的差异
+#[derive(Debug, Clone, Serialize)]
+pub struct ApplyReport {
+    pub drift: Vec<DriftItem>,
+    pub applied: Vec<String>,
+    pub remaining: Vec<DriftItem>,
+}
+
+/// 读取并校验清单
+pub fn read_manifest(path: &Path) -> AppResult<Manifest> {
+    let content = fs::read_to_string(path).map_err(|e| AppError::io(t!("manifest.read_failed"), path, e))?;
+    let manifest: Manifest =
+        toml::from_str(&content).map_err(|e| AppError::toml(t!("manifest.invalid"), &content, &e).with_path(path))?;
+
+    if manifest.version == 0 || manifest.version > MANIFEST_VERSION {
+        return Err(AppError::new(
+            ErrorKind::Parse,
+            t!("manifest.unsupported_version", version = manifest.version, supported = MANIFEST_VERSION),
+        ));
+    }
+
+    let urls = [
+        manifest.claude.as_ref().and_then(|c| c.base_url.as_ref()),
+        manifest.codex.as_ref().and_then(|c| c.base_url.as_ref()),
+        manifest.vscode.as_ref().map(|v| &v.api_base),
+    ];
+    for url in urls.into_iter().flatten() {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return Err(AppError::invalid_input(t!("manifest.invalid_url", url = url)));
+        }
+        crate::base_url::ensure_url_chars(url)?;
+    }
+
+    Ok(manifest)
+}
+
+/// 执行密钥命令，取输出的第一行
+fn run_secret_command(command: &str) -> AppResult<String> {
+    #[cfg(windows)]
+    let output = Command::new("cmd").args(["/C", command]).output();
+    #[cfg(not(windows))]
+    let output = Command::new("sh").args(["-c", command]).output();
+
+    let output = output.map_err(|e| AppError::new(ErrorKind::Command, t!("secret.command_spawn_failed")).with_source(e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(AppError::new(ErrorKind::Command, t!("secret.command_failed")).with_source(stderr.trim()));
+    }
+
+    let value = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_string();
+    if value.is_empty() {
+        return Err(AppError::new(ErrorKind::Command, t!("secret.command_no_output", command = command)));
+    }
+    Ok(value)
+}
+
+/// 解析密钥来源；来源是命令且未获准执行时返回 Ok(None)
+fn resolve_secret(source: &SecretSource, run_commands: bool) -> AppResult<Option<String>> {
+    match (&source.env, &source.command) {
+        (Some(name), None) => std::env::var(name)
+            .ok()
+            .filter(|v| !v.is_empty())
+            .map(Some)
+            .ok_or_else(|| AppError::not_found(t!("secret.env_unset", name = name))),
+        (None, Some(command)) if run_commands => run_secret_command(command).map(Some),
+        (None, Some(_)) => Ok(None),
+        _ => Err(AppError::invalid_input(t!("secret.source_invalid"))),
+    }
+}
+
+/// 当前值是否满足期望值：对象只比较清单中声明的字段，其他值要求完全相同
+fn satisfies(current: Option<&Value>, desired: &Value) -> bool {
+    match (current, desired) {
+        (Some(Value::Object(current)), Value::Object(desired)) => desired
+            .iter()
+            .all(|(key, value)| satisfies(current.get(key), value)),
+        (Some(current), desired) => current == desired,
+        (None, _) => false,
+    }
+}
+
+fn lookup<'a>(root: Option<&'a Value>, key: &[String]) -> Option<&'a Value> {
+    key.iter().try_fold(root?, |value, k| value.get(k))
+}
+
+fn display(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+fn read_json(path: &Path) -> Option<Value> {
+    jsonc::parse_jsonc(&fs::read_to_string(path).ok()?).ok()
+}
+
+fn read_toml(path: &Path) -> Option<Value> {
+    let value = toml::from_str::<toml::Value>(&fs::read_to_string(path).ok()?).ok()?;
+    serde_json::to_value(value).ok()
+}
+
+/// 在 JSON 文件原文本上设置多个键（文件不存在时创建），写入前备份并记录原值
+fn set_json_members(path: &Path, edits: &[(Vec<String>, Value)]) -> AppResult<()> {
+    // 持有写锁直到写入完成，避免读取后、写入前被其他进程修改
+    let _lock = crate::config::lock_for_update(path)?;
+    let mut content = if path.exists() {
+        fs::read_to_string(path).map_err(|e| AppError::io(t!("fs.read_failed"), path, e))?
+    } else {
+        String::from("{\n}\n")
+    };
+    jsonc::parse_jsonc(&content)
+        .map_err(|e| AppError::json(t!("fs.parse_named_failed", file = path.display()), &e).with_path(path))?;
+
+    let keys: Vec<Vec<&str>> = edits
+        .iter()
+        .map(|(key, _)| key.iter().map(|k| k.as_str()).collect())
+        .collect();
+    let key_refs: Vec<&[&str]> = keys.iter().map(|k| k.as_slice()).collect();
+
+    crate::config::create_backup_if_not_exists(path)?;
+    remember(ManagedFormat::Json, path, &key_refs);
+
+    for (key, (_, value)) in key_refs.iter().zip(edits) {
+        content = jsonc::set_member(&content, key, value)
+            .ok_or_else(|| AppError::new(ErrorKind::Other, t!("manifest.cannot_write", key = key.join("."))).with_path(path))?;
+    }
+
+    crate::config::atomic_write(path, content.as_bytes())?;
+    Ok(())
+}
+
+/// 在 config.toml 原文本上设置多个键（文件不存在时创建，保留注释和键顺序），写入前备份并记录原值
+fn set_toml_values(path: &Path, edits: &[(Vec<String>, Value)]) -> AppResult<()> {
+    let _lock = crate::config::lock_for_update(path)?;
+    let mut content = if path.exists() {
+        fs::read_to_string(path).map_err(|e| AppError::io(t!("fs.read_failed"), path, e))?
+    } else {
+        String::new()
+    };
+    if let Err(e) = toml::from_str::<toml::Value>(&content) {
+        return Err(AppError::toml(t!("fs.parse_named_failed", file = "config.toml"), &content, &e).with_path(path));
+    }
+
+    let keys: Vec<Vec<&str>> = edits
+        .iter()
+        .map(|(key, _)| key.iter().map(|k| k.as_str()).collect())
+        .collect();
+    let key_refs: Vec<&[&str]> = keys.iter().map(|k| k.as_slice()).collect();
+
+    crate::config::create_backup_if_not_exists(path)?;
+    remember(ManagedFormat::Toml, path, &key_refs);
+
+    for (key, (_, value)) in key_refs.iter().zip(edits) {
+        content = crate::codex_config::set_toml_member(&content, key, value)
+            .ok_or_else(|| AppError::new(ErrorKind::Other, t!("manifest.cannot_write", key = key.join("."))).with_path(path))?;
+    }
+
+    crate::config::atomic_write(path, content.as_bytes())?;
+    Ok(())
+}
+
+/// 把清单中的编辑器名称或路径解析为 settings.json 路径
+fn vscode_targets(targets: &[String]) -> AppResult<Vec<(String, PathBuf)>> {
+    if targets.is_empty() {
+        let names: BTreeMap<PathBuf, String> = crate::vscode::detect_installations()
+            .into_iter()
+            .map(|inst| (PathBuf::from(inst.settings_path), inst.name))
+            .collect();
+        return Ok(crate::vscode::default_settings_targets()?
+            .into_iter()
+            .map(|path| {
+                let name = names
+                    .get(&path)
+                    .cloned()
+                    .unwrap_or_else(|| path.to_string_lossy().to_string());
+                (name, path)
+            })
+            .collect());
+    }
+
+    let installations = crate::vscode::detect_installations();
+    Ok(targets
+        .iter()
+        .map(
+            |target| match installations.iter().find(|inst| inst.name == *target) {
+                Some(inst) => (inst.name.clone(), PathBuf::from(&inst.settings_path)),
+                None => (target.clone(), PathBuf::from(target)),
+            },
+        )
+        .collect())
+}
+
+/// 比较并（可选）应用清单的过程状态
+struct Sync {
+    apply: bool,
+    /// 是否执行密钥命令（只有应用时经用户确认才会执行）
+    run_commands: bool,
+    items: Vec<DriftItem>,
+    applied: Vec<String>,
+}
+
+impl Sync {
+    /// 记录一项比较结果，返回是否存在差异
+    fn compare(
+        &mut self,
+        target: &str,
+        field: &str,
+        current: Option<&Value>,
+        desired: &Value,
+        secret: bool,
+    ) -> bool {
+        let show = |v: &Value| {
+            if secret {
+                crate::bundle::mask(&display(v))
+            } else {
+                display(v)
+            }
+        };
+        let drifted = !satisfies(current, desired);
+        self.items.push(DriftItem {
+            target: target.to_string(),
+            field: field.to_string(),
+            current: current.map(show),
+            desired: Some(show(desired)),
+            drifted,
+            error: None,
+            unresolved: false,
+        });
+        drifted
+    }
+
+    /// 解析密钥来源并与当前值比较；来源不可用或命令未获准执行时记录该项并返回 None
+    fn compare_secret(
+        &mut self,
+        target: &str,
+        field: &str,
+        current: Option<&Value>,
+        source: &SecretSource,
+    ) -> Option<(String, bool)> {
+        let (desired, error, unresolved) = match resolve_secret(source, self.run_commands) {
+            Ok(Some(value)) => {
+                let drifted = self.compare(target, field, current, &json!(value), true);
+                return Some((value, drifted));
+            }
+            Ok(None) => (
+                Some(t!("manifest.unresolved", command = source.command.as_deref().unwrap_or_default())),
+                None,
+                true,
+            ),
+            Err(e) => (None, Some(e.to_string()), false),
+        };
+        self.items.push(DriftItem {
+            target: target.to_string(),
+            field: field.to_string(),
+            current: current.map(|v| crate::bundle::mask(&display(v))),
+            desired,
+            drifted: false,
+            error,
+            unresolved,
+        });
+        None
+    }
+
+    /// 比较一组键，应用时只写入与最新内容不一致的键
+    fn sync_keys(
+        &mut self,
+        target: &str,
+        path: &Path,
+        format: ManagedFormat,
+        desired: &[(Vec<String>, Value)],
+    ) -> AppResult<()> {
+        let read = |path: &Path| match format {
+            ManagedFormat::Toml => read_toml(path),
+            _ => read_json(path),
+        };
+
+        let current = read(path);
+        for (key, value) in desired {
+            self.compare(
+                target,
+                &key.join("."),
+                lookup(current.as_ref(), key),
+                value,
+                false,
+            );
+        }
+        if !self.apply {
+            return Ok(());
+        }
+
+        // 前面的步骤可能已经重写了文件，按最新内容决定需要修改的键
+        let latest = read(path);
+        let edits: Vec<(Vec<String>, Value)> = desired
+            .iter()
+            .filter(|(key, value)| !satisfies(lookup(latest.as_ref(), key), value))
+            .cloned()
+            .collect();
+        if edits.is_empty() {
+            return Ok(());
+        }
+
+        match format {
+            ManagedFormat::Toml => set_toml_values(path, &edits)?,
+            _ => set_json_members(path, &edits)?,
+        }
+        for (key, _) in &edits {
+            self.applied
+                .push(t!("manifest.updated_key", target = target, key = key.join(".")));
+        }
+        Ok(())
+    }
+
+    fn sync_claude(&mut self, claude: &ClaudeManifest) -> AppResult<()> {
+        const TARGET: &str = "Claude Code";
+        let settings_path = get_claude_settings_path();
+        let settings = read_json(&settings_path);
+        let current_url = lookup(
+            settings.as_ref(),
+            &["env".into(), "ANTHROPIC_BASE_URL".into()],
+        );
+        let current_key = lookup(
+            settings.as_ref(),
+            &["env".into(), "ANTHROPIC_AUTH_TOKEN".into()],
+        );
+
+        let url_drifted = claude.base_url.as_ref().is_some_and(|url| {
+            self.compare(
+                TARGET,
+                "ANTHROPIC_BASE_URL",
+                current_url,
+                &json!(url),
+                false,
+            )
+        });
+        let key = claude.api_key.as_ref().and_then(|source| {
+            self.compare_secret(TARGET, "ANTHROPIC_AUTH_TOKEN", current_key, source)
+        });
+        let key_drifted = key.as_ref().is_some_and(|(_, drifted)| *drifted);
+
+        if self.apply && (url_drifted || key_drifted) {
+            let base_url = claude
+                .base_url
+                .clone()
+                .or_else(|| current_url.and_then(|v| v.as_str()).map(|s| s.to_string()));
+            let api_key = key
+                .map(|(value, _)| value)
+                .or_else(|| current_key.and_then(|v| v.as_str()).map(|s| s.to_string()));
+            match (base_url, api_key) {
+                (Some(base_url), Some(api_key)) => {
+                    crate::claude_config::configure_claude_code(base_url, api_key)?;
+                    self.applied.push(t!("manifest.updated_endpoint", target = TARGET));
+                }
+                _ => self
+                    .applied
+                    .push(t!("manifest.missing_endpoint", target = TARGET)),
+            }
+        }
+
+        let mut desired = Vec::new();
+        if let Some(model) = &claude.model {
+            desired.push((vec!["model".to_string()], json!(model)));
+        }
+        if let Some(permissions) = &claude.permissions {
+            if let Some(allow) = &permissions.allow {
+                desired.push((vec!["permissions".into(), "allow".into()], json!(allow)));
+            }
+            if let Some(deny) = &permissions.deny {
+                desired.push((vec!["permissions".into(), "deny".into()], json!(deny)));
+            }
+        }
+        if !desired.is_empty() {
+            self.sync_keys(TARGET, &settings_path, ManagedFormat::Json, &desired)?;
+        }
+
+        // 用户级 MCP 服务器保存在 ~/.claude.json
+        let servers: Vec<(Vec<String>, Value)> = claude
+            .mcp_servers
+            .iter()
+            .map(|(name, server)| {
+                (
+                    vec!["mcpServers".to_string(), name.clone()],
+                    json!({
+                        "type": "stdio",
+                        "command": server.command,
+                        "args": server.args,
+                        "env": server.env,
+                    }),
+                )
+            })
+            .collect();
+        if !servers.is_empty() {
+            self.sync_keys(
+                TARGET,
+                &get_claude_json_path(),
+                ManagedFormat::Json,
+                &servers,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn sync_codex(&mut self, codex: &CodexManifest) -> AppResult<()> {
+        const TARGET: &str = "Codex";
+        let config_path = get_codex_config_path();
+        let config = read_toml(&config_path);
+        let auth = read_json(&get_codex_auth_path());
+        let current_url = lookup(
+            config.as_ref(),
+            &["model_providers".into(), "88code".into(), "base_url".into()],
+        );
+        let current_key = lookup(auth.as_ref(), &["OPENAI_API_KEY".into()]);
+
+        let url_drifted = codex
+            .base_url
+            .as_ref()
+            .is_some_and(|url| self.compare(TARGET, "base_url", current_url, &json!(url), false));
+        let key = codex
+            .api_key
+            .as_ref()
+            .and_then(|source| self.compare_secret(TARGET, "OPENAI_API_KEY", current_key, source));
+        let key_drifted = key.as_ref().is_some_and(|(_, drifted)| *drifted);
+
+        if self.apply && (url_drifted || key_drifted) {
+            let base_url = codex
+                .base_url
+                .clone()
+                .or_else(|| current_url.and_then(|v| v.as_str()).map(|s| s.to_string()));
+            let api_key = key
+                .map(|(value, _)| value)
+                .or_else(|| current_key.and_then(|v| v.as_str()).map(|s| s.to_string()));
+            match (base_url, api_key) {
+                (Some(base_url), Some(api_key)) => {
+                    crate::codex_config::configure_codex(base_url, api_key)?;
+                    self.applied.push(t!("manifest.updated_endpoint", target = TARGET));
+                }
+                _ => self
+                    .applied
+                    .push(t!("manifest.missing_endpoint", target = TARGET)),
+            }
+        }
+
+        // configure_codex 会写入默认的 model，sync_keys 按写入后的内容再次比较
+        let mut desired = Vec::new();
+        if let Some(model) = &codex.model {
+            desired.push((vec!["model".to_string()], json!(model)));
+        }
+        if let Some(effort) = &codex.model_reasoning_effort {
+            desired.push((vec!["model_reasoning_effort".to_string()], json!(effort)));
+        }
+        for (name, server) in &codex.mcp_servers {
+            desired.push((
+                vec!["mcp_servers".to_string(), name.clone()],
+                serde_json::to_value(server)
+                    .map_err(|e| AppError::new(ErrorKind::Other, t!("fs.serialize_json_failed")).with_source(e))?,
+            ));
+        }
+        if !desired.is_empty() {
+            self.sync_keys(TARGET, &config_path, ManagedFormat::Toml, &desired)?;
+        }
+        Ok(())
+    }
+
+    fn sync_vscode(&mut self, vscode: &VscodeManifest) -> AppResult<()> {
+        let desired = json!(vscode.api_base);
+        let mut drifted = Vec::new();
+        for (name, path) in vscode_targets(&vscode.targets)? {
+            let current = read_json(&path);
+            let target = format!("VSCode {}", name);
+            if self.compare(
+                &target,
+                "chatgpt.apiBase",
+                lookup(current.as_ref(), &["chatgpt.apiBase".into()]),
+                &desired,
+                false,
+            ) {
+                drifted.push(path.to_string_lossy().to_string());
+            }
+        }
+
+        if self.apply && !drifted.is_empty() {
+            let outcomes = crate::vscode::configure_vscode_codex(
+                vscode.api_base.clone(),
+                String::new(),
+                drifted,
+            )?;
+            for outcome in outcomes {
+                self.applied
+                    .push(format!("VSCode {}: {:?}", outcome.path, outcome.outcome));
+            }
+        }
+        Ok(())
+    }
+
+    fn sync_env(&mut self, env: &BTreeMap<String, SecretSource>) -> AppResult<()> {
+        for (name, source) in env {
+            let current = crate::env_manager::read_user_env(name).map(Value::String);
+            let Some((value, drifted)) =
+                self.compare_secret(&t!("target.env"), name, current.as_ref(), source)
+            else {
+                continue;
+            };
+            if self.apply && drifted {
+                crate::env_manager::set_user_env(name, &value)?;
+                self.applied.push(t!("manifest.env_set", target = t!("target.env"), name = name));
+            }
+        }
+        Ok(())
+    }
+
+    fn run(mut self, manifest: &Manifest) -> AppResult<Self> {
+        if let Some(claude) = &manifest.claude {
+            self.sync_claude(claude)?;
+        }
+        if let Some(codex) = &manifest.codex {
+            self.sync_codex(codex)?;
+        }
+        if let Some(vscode) = &manifest.vscode {
+            self.sync_vscode(vscode)?;
+        }
+        self.sync_env(&manifest.env)?;
+        Ok(self)
+    }
+}
+
+/// 比较清单与当前配置，从不执行密钥命令
+fn check(manifest: &Manifest) -> AppResult<Vec<DriftItem>> {
+    let sync = Sync {
+        apply: false,
+        run_commands: false,
+        items: Vec::new(),
+        applied: Vec::new(),
+    };
+    Ok(sync.run(manifest)?.items)
+}
+
+/// 比较清单与当前配置，不修改任何文件，也不执行密钥命令（这些项显示为未解析）
+pub fn check_manifest(path: &Path) -> AppResult<DriftReport> {
+    let items = check(&read_manifest(path)?)?;
+    let in_sync = items
+        .iter()
+        .all(|item| !item.drifted && item.error.is_none());
+    Ok(DriftReport { items, in_sync })
+}
+
+/// 清单中需要执行的密钥命令，应用前交给用户确认
+pub fn secret_commands(manifest: &Manifest) -> Vec<String> {
+    let claude = manifest.claude.as_ref().and_then(|c| c.api_key.as_ref());
+    let codex = manifest.codex.as_ref().and_then(|c| c.api_key.as_ref());
+    claude
+        .into_iter()
+        .chain(codex)
+        .chain(manifest.env.values())
+        .filter_map(|source| source.command.clone())
+        .collect()
+}
+
+/// 应用清单：只修改与清单不一致的部分，重复执行不会产生额外写入
+/// allow_commands 为 false 时不执行密钥命令，来自命令的密钥保持不变
+pub fn apply_manifest(path: &Path, allow_commands: bool) -> AppResult<ApplyReport> {
+    let manifest = read_manifest(path)?;
+    let sync = Sync {
+        apply: true,
+        run_commands: allow_commands,
+        items: Vec::new(),
+        applied: Vec::new(),
+    }
+    .run(&manifest)?;
+
+    let remaining: Vec<DriftItem> = check(&manifest)?
+        .into_iter()
+        .filter(|item| item.drifted || item.error.is_some())
+        .collect();
+
+    log::info!("已应用清单 {:?}: {:?}", path, sync.applied);
+    Ok(ApplyReport {
+        drift: sync.items,
+        applied: sync.applied,
+        remaining,
+    })
+}
+
+/// 命令行模式（供初始化脚本使用，与界面共用同一份清单）：
+///   code-desktop --check-manifest <path>                     退出码 0 表示一致，2 表示存在差异
+///   code-desktop --apply-manifest <path> [--allow-commands]  退出码 0 表示应用后一致，2 表示仍有差异
+/// 检查从不执行清单中的密钥命令；应用时只有带 --allow-commands 才会执行
+/// 不是清单相关参数时返回 None，继续启动界面
+pub fn run_cli(args: &[String]) -> Option<i32> {
+    let (flag, path, allow_commands) = match args {
+        [flag, path] => (flag, path, false),
+        [flag, path, allow] if allow == "--allow-commands" => (flag, path, true),
+        _ => return None,
+    };
+    if flag != "--check-manifest" && flag != "--apply-manifest" {
+        return None;
+    }
+    #[cfg(windows)]
+    attach_parent_console();
+    let path = Path::new(path);
+
+    let result = if flag == "--check-manifest" {
+        check_manifest(path).and_then(|report| {
+            let code = if report.in_sync { 0 } else { 2 };
+            serde_json::to_string_pretty(&report)
+                .map(|json| (json, code))
+                .map_err(|e| AppError::new(ErrorKind::Other, t!("fs.serialize_json_failed")).with_source(e))
+        })
+    } else {
+        let _op = crate::history::begin("apply_manifest");
+        apply_manifest(path, allow_commands).and_then(|report| {
+            let code = if report.remaining.is_empty() { 0 } else { 2 };
+            serde_json::to_string_pretty(&report)
+                .map(|json| (json, code))
+                .map_err(|e| AppError::new(ErrorKind::Other, t!("fs.serialize_json_failed")).with_source(e))
+        })
+    };
+
+    match result {
+        Ok((json, code)) => {
+            println!("{}", json);
+            Some(code)
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            Some(1)
+        }
+    }
+}
+
+/// 发布版在 Windows 上是 GUI 程序，没有自己的控制台；附加到启动它的终端，命令行输出才可见
+#[cfg(windows)]
+fn attach_parent_console() {
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+
+    // 从资源管理器启动时没有父控制台，失败时忽略
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_does_not_run_secret_commands() {
+        let dir = tempfile::tempdir().unwrap();
+        let marker = dir.path().join("ran");
+        let manifest: Manifest = toml::from_str(&format!(
+            "version = 1\n[env.CODE88_MANIFEST_TEST]\ncommand = \"touch '{}'\"\n",
+            marker.display()
+        ))
+        .unwrap();
+
+        let items = check(&manifest).unwrap();
+        assert!(!marker.exists());
+        assert_eq!(items.len(), 1);
+        assert!(items[0].unresolved);
+        assert!(!items[0].drifted);
+        assert_eq!(secret_commands(&manifest), vec![format!("touch '{}'", marker.display())]);
+    }
+
+    #[test]
+    fn cli_accepts_allow_commands_only_after_path() {
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert_eq!(run_cli(&args(&["--allow-commands", "--apply-manifest", "x"])), None);
+        assert_eq!(run_cli(&args(&["--other", "x"])), None);
+    }
+
+    #[test]
+    fn satisfies_compares_only_declared_fields() {
+        let current = json!({ "type": "stdio", "command": "npx", "args": ["-y", "server"], "env": {} });
+        assert!(satisfies(Some(&current), &json!({ "command": "npx", "args": ["-y", "server"] })));
+        assert!(!satisfies(Some(&current), &json!({ "command": "uvx" })));
+        assert!(!satisfies(Some(&current), &json!({ "env": { "TOKEN": "x" } })));
+        assert!(!satisfies(Some(&json!(["a", "b"])), &json!(["a"])));
+        assert!(!satisfies(None, &json!("opus")));
+    }
+
+    #[test]
+    fn set_json_members_keeps_comments() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.json");
+        fs::write(&path, "{\n  // 团队设置\n  \"model\": \"sonnet\"\n}\n").unwrap();
+
+        set_json_members(
+            &path,
+            &[
+                (vec!["model".into()], json!("opus")),
+                (vec!["permissions".into(), "deny".into()], json!(["Bash(rm:*)"])),
+            ],
+        )
+        .unwrap();
+
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("// 团队设置"));
+        assert_eq!(
+            jsonc::parse_jsonc(&content).unwrap(),
+            json!({ "model": "opus", "permissions": { "deny": ["Bash(rm:*)"] } })
+        );
+    }
+
+    #[test]
+    fn apply_syncs_mcp_servers_keeps_comments_and_is_idempotent() {
+        let claude_json = get_claude_json_path();
+        let codex_config = get_codex_config_path();
+        fs::create_dir_all(codex_config.parent().unwrap()).unwrap();
+        fs::write(&claude_json, "{\n  \"numStartups\": 3\n}\n").unwrap();
+        fs::write(&codex_config, "# 个人设置\nmodel = \"gpt-5\" # 默认模型\n").unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let manifest_path = dir.path().join("team.toml");
+        fs::write(
+            &manifest_path,
+            "version = 1\n\
+             [claude.mcp_servers.docs]\ncommand = \"npx\"\nargs = [\"-y\", \"docs-server\"]\n\
+             [codex]\nmodel_reasoning_effort = \"high\"\n\
+             [codex.mcp_servers.docs]\ncommand = \"npx\"\nargs = [\"-y\", \"docs-server\"]\nenv = { LOG = \"1\" }\n",
+        )
+        .unwrap();
+
+        let drift = check_manifest(&manifest_path).unwrap();
+        assert!(!drift.in_sync);
+        assert_eq!(drift.items.iter().filter(|item| item.drifted).count(), 3);
+
+        let report = apply_manifest(&manifest_path, false).unwrap();
+        assert_eq!(report.applied.len(), 3);
+        assert!(report.remaining.is_empty());
+
+        let claude: Value = jsonc::parse_jsonc(&fs::read_to_string(&claude_json).unwrap()).unwrap();
+        assert_eq!(claude["numStartups"], 3);
+        assert_eq!(
+            claude["mcpServers"]["docs"],
+            json!({ "type": "stdio", "command": "npx", "args": ["-y", "docs-server"], "env": {} })
+        );
+
+        let config = fs::read_to_string(&codex_config).unwrap();
+        assert!(config.starts_with("# 个人设置\nmodel = \"gpt-5\" # 默认模型\n"));
+        let value = read_toml(&codex_config).unwrap();
+        assert_eq!(value["model_reasoning_effort"], "high");
+        assert_eq!(
+            value["mcp_servers"]["docs"],
+            json!({ "command": "npx", "args": ["-y", "docs-server"], "env": { "LOG": "1" } })
+        );
+
+        // 再次应用：没有差异，也不写入任何文件
+        let written = (fs::read(&claude_json).unwrap(), fs::read(&codex_config).unwrap());
+        let report = apply_manifest(&manifest_path, false).unwrap();
+        assert!(report.applied.is_empty());
+        assert!(report.drift.iter().all(|item| !item.drifted));
+        assert_eq!((fs::read(&claude_json).unwrap(), fs::read(&codex_config).unwrap()), written);
+        assert!(check_manifest(&manifest_path).unwrap().in_sync);
+    }
+
+    #[test]
+    fn vscode_targets_resolve_names_and_paths() {
+        let installations = crate::vscode::detect_installations();
+        let cursor = installations.iter().find(|inst| inst.name == "Cursor").unwrap();
+
+        let resolved = vscode_targets(&["Cursor".to_string(), "/work/.vscode/settings.json".to_string()]).unwrap();
+        assert_eq!(
+            resolved,
+            vec![
+                ("Cursor".to_string(), PathBuf::from(&cursor.settings_path)),
+                (
+                    "/work/.vscode/settings.json".to_string(),
+                    PathBuf::from("/work/.vscode/settings.json")
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_manifest_urls_with_invalid_characters() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("team.toml");
+        fs::write(&path, "version = 1\n[codex]\nbase_url = 'https://88code.org/openai/v1\"'\n").unwrap();
+        assert_eq!(read_manifest(&path).unwrap_err().kind, ErrorKind::InvalidInput);
+    }
+}
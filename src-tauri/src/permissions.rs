@@ -0,0 +1,258 @@
+use crate::config::{
+    backup_path_for, get_claude_config_dir, get_claude_settings_path, get_codex_auth_path,
+    get_codex_config_dir,
+};
+use crate::error::{AppError, AppResult};
+use crate::i18n::t;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// 含密钥文件的权限：仅所有者可读写
+pub const SECRET_FILE_MODE: u32 = 0o600;
+
+/// 应用创建的配置目录权限：仅所有者可访问
+pub const SECRET_DIR_MODE: u32 = 0o700;
+
+/// 权限过于宽松的文件或目录
+#[derive(Debug, Clone, Serialize)]
+pub struct PermissionIssue {
+    pub path: String,
+    pub is_dir: bool,
+    /// 当前权限（八进制字符串，如 "644"）
+    pub mode: String,
+    pub expected: String,
+}
+
+/// 固定位置的密钥文件（变更记录中保存了被替换的旧密钥）
+fn fixed_secret_files() -> Vec<PathBuf> {
+    vec![
+        get_claude_settings_path(),
+        get_claude_config_dir().join("config.json"),
+        get_codex_auth_path(),
+        crate::changes::journal_path(),
+    ]
+}
+
+/// JetBrains 终端设置（<配置根目录>/<IDE>/options/terminal.xml）中写入了 ANTHROPIC_AUTH_TOKEN / key88
+fn is_jetbrains_terminal_options(path: &Path, root: &Path) -> bool {
+    path.strip_prefix(root)
+        .is_ok_and(|relative| relative.components().count() == 3 && relative.ends_with("options/terminal.xml"))
+}
+
+/// shell 配置文件中有 export key88= 时同样视为密钥文件
+fn holds_shell_key(rc: &Path) -> bool {
+    std::fs::read_to_string(rc).is_ok_and(|content| {
+        content
+            .lines()
+            .any(|line| line.trim().starts_with("export key88="))
+    })
+}
+
+/// 保存 API 密钥的全部文件（检查权限时使用，会扫描 JetBrains 配置目录）
+fn secret_files() -> Vec<PathBuf> {
+    let mut files = fixed_secret_files();
+    files.extend(
+        crate::jetbrains::discover_ides()
+            .into_iter()
+            .map(|ide| PathBuf::from(ide.terminal_options_path)),
+    );
+    if let Some(rc) = crate::env_manager::shell_config_path().filter(|rc| holds_shell_key(rc)) {
+        files.push(rc);
+    }
+    files
+}
+
+/// 存放密钥文件的目录
+fn secret_dirs() -> Vec<PathBuf> {
+    vec![get_claude_config_dir(), get_codex_config_dir()]
+}
+
+/// 判断文件是否保存 API 密钥（含其备份），每次写入都会调用，只按路径规则匹配而不扫描目录
+pub fn is_secret_file(path: &Path) -> bool {
+    // 备份文件（settings.json.bak）按原文件判断
+    let original = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_suffix(".bak"))
+        .map(|name| path.with_file_name(name));
+    std::iter::once(path.to_path_buf())
+        .chain(original)
+        .any(|candidate| matches_secret_file(&candidate))
+}
+
+fn matches_secret_file(path: &Path) -> bool {
+    fixed_secret_files().iter().any(|secret| secret == path)
+        || crate::jetbrains::jetbrains_config_root().is_some_and(|root| is_jetbrains_terminal_options(path, &root))
+        || crate::env_manager::shell_config_path().is_some_and(|rc| rc == path && holds_shell_key(&rc))
+}
+
+/// 判断目录是否为需要 0700 的配置目录
+pub fn is_secret_dir(path: &Path) -> bool {
+    secret_dirs().iter().any(|dir| dir == path)
+}
+
+/// 把密钥文件或目录的权限收紧到要求值（非 Unix 系统不处理）
+pub fn restrict(path: &Path, is_dir: bool) -> AppResult<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = if is_dir { SECRET_DIR_MODE } else { SECRET_FILE_MODE };
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode))
+            .map_err(|e| AppError::io(t!("permissions.fix_failed", path = path.display()), path, e))?;
+    }
+
+    #[cfg(not(unix))]
+    let _ = (path, is_dir);
+
+    Ok(())
+}
+
+/// 检查单个路径，权限比要求宽松（组或其他用户有任何权限）时返回问题
+#[cfg(unix)]
+fn check_path(path: &Path, is_dir: bool) -> Option<PermissionIssue> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = std::fs::metadata(path).ok()?.permissions().mode() & 0o777;
+    if mode & 0o077 == 0 {
+        return None;
+    }
+
+    let expected = if is_dir { SECRET_DIR_MODE } else { SECRET_FILE_MODE };
+    Some(PermissionIssue {
+        path: path.to_string_lossy().to_string(),
+        is_dir,
+        mode: format!("{:o}", mode),
+        expected: format!("{:o}", expected),
+    })
+}
+
+/// 检查所有密钥文件、备份和配置目录的权限（Windows 使用 ACL，不检查）
+pub fn check_permissions() -> Vec<PermissionIssue> {
+    check_paths(&secret_dirs(), &secret_files())
+}
+
+/// 检查给定目录、文件及文件备份的权限
+#[cfg_attr(not(unix), allow(unused_variables))]
+fn check_paths(dirs: &[PathBuf], files: &[PathBuf]) -> Vec<PermissionIssue> {
+    #[cfg(unix)]
+    {
+        let mut issues = Vec::new();
+        for dir in dirs {
+            issues.extend(check_path(dir, true));
+        }
+        for file in files {
+            issues.extend(check_path(file, false));
+            issues.extend(check_path(&backup_path_for(file), false));
+        }
+        issues
+    }
+
+    #[cfg(not(unix))]
+    Vec::new()
+}
+
+/// 启动时检查权限，过于宽松时输出警告
+pub fn warn_insecure_permissions() {
+    for issue in check_permissions() {
+        log::warn!(
+            "权限过于宽松: {} 当前为 {}，建议 {}",
+            issue.path,
+            issue.mode,
+            issue.expected
+        );
+    }
+}
+
+/// 修复所有权限过于宽松的文件和目录，返回已修复的项
+pub fn fix_permissions() -> AppResult<Vec<PermissionIssue>> {
+    fix_issues(check_permissions())
+}
+
+fn fix_issues(issues: Vec<PermissionIssue>) -> AppResult<Vec<PermissionIssue>> {
+    for issue in &issues {
+        restrict(Path::new(&issue.path), issue.is_dir)?;
+        log::info!("已修复权限: {} {} -> {}", issue.path, issue.mode, issue.expected);
+    }
+    Ok(issues)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_secret_files_and_their_backups_by_path() {
+        let journal = crate::changes::journal_path();
+        assert!(is_secret_file(&journal));
+        assert!(is_secret_file(&backup_path_for(&journal)));
+        assert!(!is_secret_file(&journal.with_file_name("history.jsonl")));
+        assert!(!is_secret_file(Path::new("/tmp/settings.json.bak")));
+    }
+
+    #[test]
+    fn matches_jetbrains_terminal_options_under_root() {
+        let root = Path::new("/home/user/.config/JetBrains");
+        assert!(is_jetbrains_terminal_options(&root.join("PyCharm2024.2/options/terminal.xml"), root));
+        assert!(!is_jetbrains_terminal_options(&root.join("PyCharm2024.2/options/other.xml"), root));
+        assert!(!is_jetbrains_terminal_options(&root.join("options/terminal.xml"), root));
+        assert!(!is_jetbrains_terminal_options(Path::new("/tmp/PyCharm2024.2/options/terminal.xml"), root));
+    }
+
+    #[test]
+    fn detects_shell_key_exports() {
+        let dir = tempfile::tempdir().unwrap();
+        let rc = dir.path().join(".zshrc");
+        std::fs::write(&rc, "export PATH=$HOME/bin:$PATH\n").unwrap();
+        assert!(!holds_shell_key(&rc));
+        std::fs::write(&rc, "  export key88=\"sk-test\" # 88code\n").unwrap();
+        assert!(holds_shell_key(&rc));
+        assert!(!holds_shell_key(&dir.path().join("missing")));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn checks_and_fixes_loose_permissions() {
+        use std::os::unix::fs::PermissionsExt;
+        let set_mode = |path: &Path, mode: u32| {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).unwrap();
+        };
+        let mode_of = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join(".claude");
+        std::fs::create_dir(&dir).unwrap();
+        set_mode(&dir, 0o755);
+        let loose = dir.join("settings.json");
+        std::fs::write(&loose, "{}").unwrap();
+        set_mode(&loose, 0o644);
+        let backup = backup_path_for(&loose);
+        std::fs::write(&backup, "{}").unwrap();
+        set_mode(&backup, 0o640);
+        let tight = dir.join("config.json");
+        std::fs::write(&tight, "{}").unwrap();
+        set_mode(&tight, 0o600);
+        let missing = dir.join("auth.json");
+
+        let dirs = vec![dir.clone()];
+        let files = vec![loose.clone(), tight.clone(), missing];
+        let issues = check_paths(&dirs, &files);
+        let found: Vec<(PathBuf, bool, &str, &str)> = issues
+            .iter()
+            .map(|i| (PathBuf::from(&i.path), i.is_dir, i.mode.as_str(), i.expected.as_str()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (dir.clone(), true, "755", "700"),
+                (loose.clone(), false, "644", "600"),
+                (backup.clone(), false, "640", "600"),
+            ]
+        );
+
+        assert_eq!(fix_issues(issues).unwrap().len(), 3);
+        assert_eq!(mode_of(&dir), 0o700);
+        assert_eq!(mode_of(&loose), 0o600);
+        assert_eq!(mode_of(&backup), 0o600);
+        assert!(check_paths(&dirs, &files).is_empty());
+    }
+}
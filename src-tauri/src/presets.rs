@@ -0,0 +1,244 @@
+use crate::base_url::UrlTarget;
+use crate::config::get_app_config_dir;
+use crate::error::{AppError, AppResult, ErrorKind};
+use crate::i18n::t;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// 随应用发布的默认预设
+const BUNDLED_PRESETS: &str = include_str!("../presets.json");
+
+/// 当前支持的预设文件格式版本
+const PRESETS_VERSION: u32 = 1;
+
+/// 一组命名的服务地址（如主站、地区镜像）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Preset {
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub claude_base_url: Option<String>,
+    #[serde(default)]
+    pub codex_base_url: Option<String>,
+    /// 查询余额和用量的账户接口地址
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_api_url: Option<String>,
+}
+
+impl Preset {
+    pub fn url(&self, target: UrlTarget) -> Option<&str> {
+        match target {
+            UrlTarget::Claude => self.claude_base_url.as_deref(),
+            UrlTarget::Codex => self.codex_base_url.as_deref(),
+        }
+    }
+}
+
+/// 预设文件（内置文件与用户覆盖文件格式相同）
+#[derive(Debug, Deserialize)]
+struct PresetsFile {
+    version: u32,
+    /// 默认使用的预设 ID，未指定时使用第一个
+    #[serde(default)]
+    default: Option<String>,
+    #[serde(default)]
+    presets: Vec<Preset>,
+}
+
+/// 合并后的预设列表
+#[derive(Debug, Clone, Serialize)]
+pub struct PresetList {
+    pub presets: Vec<Preset>,
+    pub default_id: Option<String>,
+    /// 用户覆盖文件路径
+    pub user_file: String,
+    /// 用户覆盖文件读取或解析失败的原因（此时只使用内置预设）
+    pub error: Option<String>,
+}
+
+impl PresetList {
+    /// 指定客户端的默认地址：优先使用默认预设，其次使用第一个提供该地址的预设
+    pub fn default_url(&self, target: UrlTarget) -> Option<&str> {
+        let default = self
+            .default_id
+            .as_deref()
+            .and_then(|id| self.presets.iter().find(|p| p.id == id))
+            .and_then(|p| p.url(target));
+        default.or_else(|| self.presets.iter().find_map(|p| p.url(target)))
+    }
+
+    /// 所有预设中指定客户端的地址
+    pub fn urls(&self, target: UrlTarget) -> impl Iterator<Item = &str> {
+        self.presets.iter().filter_map(move |p| p.url(target))
+    }
+}
+
+/// 用户覆盖文件路径
+pub fn user_presets_path() -> PathBuf {
+    get_app_config_dir().join("presets.json")
+}
+
+fn parse(content: &str) -> AppResult<PresetsFile> {
+    let file: PresetsFile = serde_json::from_str(content).map_err(|e| AppError::json(t!("presets.invalid"), &e))?;
+    if file.version == 0 || file.version > PRESETS_VERSION {
+        return Err(AppError::new(
+            ErrorKind::Parse,
+            t!("presets.unsupported_version", version = file.version, supported = PRESETS_VERSION),
+        ));
+    }
+    for preset in &file.presets {
+        let urls = [&preset.claude_base_url, &preset.codex_base_url, &preset.account_api_url];
+        for url in urls.into_iter().flatten() {
+            let valid = (url.starts_with("http://") || url.starts_with("https://"))
+                && crate::base_url::ensure_url_chars(url).is_ok();
+            if !valid {
+                return Err(AppError::invalid_input(t!("presets.invalid_url", id = preset.id, url = url)));
+            }
+        }
+    }
+    Ok(file)
+}
+
+/// 合并内置预设与用户覆盖文件内容（同 ID 的预设被替换，新 ID 追加在后面）
+/// 覆盖文件无效时只使用内置预设，并在 error 中说明原因
+fn merge(user_content: std::io::Result<String>, path: &Path) -> PresetList {
+    let bundled = parse(BUNDLED_PRESETS).expect("内置预设文件格式错误");
+    let mut presets = bundled.presets;
+    let mut default_id = bundled.default;
+
+    let error = match user_content {
+        Ok(content) => match parse(&content) {
+            Ok(user) => {
+                for preset in user.presets {
+                    match presets.iter_mut().find(|p| p.id == preset.id) {
+                        Some(existing) => *existing = preset,
+                        None => presets.push(preset),
+                    }
+                }
+                if user.default.is_some() {
+                    default_id = user.default;
+                }
+                None
+            }
+            Err(e) => Some(e.to_string()),
+        },
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => Some(format!("{}: {}", t!("fs.read_failed"), e)),
+    };
+    if let Some(e) = &error {
+        log::warn!("用户预设文件 {:?} 无效，仅使用内置预设: {}", path, e);
+    }
+
+    PresetList {
+        presets,
+        default_id,
+        user_file: path.to_string_lossy().to_string(),
+        error,
+    }
+}
+
+/// 覆盖文件的修改时间和大小（不存在时为 None），未变化时复用缓存的预设
+type FileStamp = Option<(SystemTime, u64)>;
+
+fn preset_cache() -> &'static Mutex<Option<(FileStamp, PresetList)>> {
+    static CACHE: OnceLock<Mutex<Option<(FileStamp, PresetList)>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(None))
+}
+
+/// 加载预设：内置预设 + 用户覆盖文件；覆盖文件未变化时直接返回缓存
+pub fn load() -> PresetList {
+    let path = user_presets_path();
+    let stamp: FileStamp = fs::metadata(&path)
+        .ok()
+        .and_then(|meta| Some((meta.modified().ok()?, meta.len())));
+
+    let mut cache = preset_cache().lock().unwrap_or_else(|e| e.into_inner());
+    if let Some((cached_stamp, list)) = cache.as_ref() {
+        if *cached_stamp == stamp {
+            return list.clone();
+        }
+    }
+
+    let list = merge(fs::read_to_string(&path), &path);
+    *cache = Some((stamp, list.clone()));
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(list: &PresetList) -> Vec<&str> {
+        list.presets.iter().map(|p| p.id.as_str()).collect()
+    }
+
+    #[test]
+    fn bundled_presets_parse() {
+        let bundled = parse(BUNDLED_PRESETS).unwrap();
+        assert!(!bundled.presets.is_empty());
+        let list = merge(Err(std::io::ErrorKind::NotFound.into()), Path::new("presets.json"));
+        assert!(list.error.is_none());
+        assert!(list.default_url(UrlTarget::Claude).is_some());
+        assert!(list.default_url(UrlTarget::Codex).is_some());
+    }
+
+    #[test]
+    fn user_presets_replace_append_and_set_default() {
+        let user = r#"{
+            "version": 1,
+            "default": "mirror",
+            "presets": [
+                { "id": "88code", "name": "Replaced", "claude_base_url": "https://replaced.example/api" },
+                { "id": "mirror", "name": "Mirror", "codex_base_url": "https://mirror.example/openai/v1" }
+            ]
+        }"#;
+        let list = merge(Ok(user.to_string()), Path::new("presets.json"));
+        assert!(list.error.is_none());
+        assert_eq!(ids(&list), vec!["88code", "mirror"]);
+        assert_eq!(list.presets[0].name, "Replaced");
+        assert_eq!(list.presets[0].codex_base_url, None);
+        assert_eq!(list.default_id.as_deref(), Some("mirror"));
+        assert_eq!(list.default_url(UrlTarget::Codex), Some("https://mirror.example/openai/v1"));
+        // 默认预设没有 Claude 地址时使用第一个提供该地址的预设
+        assert_eq!(list.default_url(UrlTarget::Claude), Some("https://replaced.example/api"));
+    }
+
+    #[test]
+    fn invalid_user_file_falls_back_to_bundled() {
+        let bundled = merge(Err(std::io::ErrorKind::NotFound.into()), Path::new("presets.json"));
+        for user in [
+            r#"{ "version": 2, "presets": [] }"#,
+            r#"{ "version": 1, "presets": [{ "id": "x", "name": "X", "claude_base_url": "ftp://x.example" }] }"#,
+            r#"{ "version": 1, "presets": [{ "id": "x", "name": "X", "codex_base_url": "https://x.example/\"v1" }] }"#,
+            "{ not json",
+        ] {
+            let list = merge(Ok(user.to_string()), Path::new("presets.json"));
+            assert!(list.error.is_some(), "{user}");
+            assert_eq!(ids(&list), ids(&bundled));
+            assert_eq!(list.default_id, bundled.default_id);
+        }
+    }
+
+    #[test]
+    fn load_is_cached_until_the_override_file_changes() {
+        let path = user_presets_path();
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(ids(&load()), vec!["88code"]);
+
+        fs::write(&path, r#"{ "version": 1, "presets": [{ "id": "mirror", "name": "Mirror" }] }"#).unwrap();
+        assert_eq!(ids(&load()), vec!["88code", "mirror"]);
+        let meta = fs::metadata(&path).unwrap();
+        let cached = preset_cache().lock().unwrap().as_ref().map(|(stamp, _)| *stamp);
+        assert_eq!(cached, Some(Some((meta.modified().unwrap(), meta.len()))));
+        assert_eq!(ids(&load()), vec!["88code", "mirror"]);
+
+        fs::remove_file(&path).unwrap();
+        assert_eq!(ids(&load()), vec!["88code"]);
+    }
+}
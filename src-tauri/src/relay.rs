@@ -0,0 +1,1230 @@
+use crate::account;
+use crate::base_url::{self, UrlTarget};
+use crate::error::{AppError, AppResult, ErrorKind, SuggestedAction};
+use crate::i18n::t;
+use crate::{claude_config, codex_config, presets};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// 默认监听端口
+pub const DEFAULT_PORT: u16 = 18888;
+/// Claude Code 请求的路径前缀，其后的部分拼接到上游 Base URL
+const CLAUDE_PREFIX: &str = "/claude";
+/// Codex 请求的路径前缀（Codex 的 Base URL 以 /v1 结尾）
+const CODEX_PREFIX: &str = "/codex/v1";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+/// 流式响应可能长时间没有数据，读超时放宽
+const READ_TIMEOUT: Duration = Duration::from_secs(600);
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+const MAX_BODY_BYTES: usize = 64 * 1024 * 1024;
+/// 保留的最近请求记录条数
+const MAX_LOG_ENTRIES: usize = 200;
+/// 同时处理的连接数上限，超出时新连接留在系统的等待队列中，直到有连接结束
+const MAX_CONNECTIONS: usize = 64;
+
+/// 不转发的逐跳头及由中转重新设置的头
+const SKIPPED_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-connection",
+    "proxy-authorization",
+    "proxy-authenticate",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "expect",
+    "host",
+    "content-length",
+];
+
+/// 单个客户端的转发配置
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RouteConfig {
+    /// 上游地址，按顺序故障转移；为空时使用预设中的地址
+    pub endpoints: Vec<String>,
+    /// 注入的密钥；为空时使用客户端当前配置的密钥
+    pub api_key: Option<String>,
+    /// 为 true 时不转发该客户端
+    pub disabled: bool,
+}
+
+/// 中转配置
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RelayConfig {
+    /// 监听端口，为空时使用默认端口
+    pub port: Option<u16>,
+    /// 访问上游使用的代理，为空时读取 HTTPS_PROXY 等环境变量
+    pub proxy: Option<String>,
+    /// 为空时如客户端已配置密钥则自动启用
+    pub claude: Option<RouteConfig>,
+    pub codex: Option<RouteConfig>,
+}
+
+/// 一条请求记录（不包含密钥和请求内容）
+#[derive(Debug, Clone, Serialize)]
+pub struct RelayLogEntry {
+    /// 请求开始时间（毫秒时间戳）
+    pub timestamp: u64,
+    pub client: Option<UrlTarget>,
+    pub method: String,
+    pub path: String,
+    /// 最终使用的上游地址
+    pub upstream: Option<String>,
+    pub status: Option<u16>,
+    pub duration_ms: u64,
+    /// 尝试的上游个数，大于 1 表示发生了故障转移
+    pub attempts: u32,
+    pub error: Option<String>,
+}
+
+/// 单个客户端的转发状态
+#[derive(Debug, Clone, Serialize)]
+pub struct RouteStatus {
+    pub client: UrlTarget,
+    /// 客户端应使用的本地地址（包含本次启动的访问令牌）
+    pub local_url: String,
+    pub endpoints: Vec<String>,
+    /// 当前使用的上游地址
+    pub active: String,
+    pub key_hint: String,
+}
+
+/// 返回给界面的中转状态
+#[derive(Debug, Clone, Serialize)]
+pub struct RelayStatus {
+    pub running: bool,
+    pub port: Option<u16>,
+    pub proxy: Option<String>,
+    pub routes: Vec<RouteStatus>,
+    /// 已改为指向中转的客户端
+    pub applied: Vec<UrlTarget>,
+    /// 启动以来处理的请求数
+    pub requests: u64,
+    /// 最近的请求记录，最新的在前
+    pub recent: Vec<RelayLogEntry>,
+}
+
+struct Route {
+    client: UrlTarget,
+    endpoints: Vec<String>,
+    api_key: String,
+    /// 上次成功的上游下标，之后的请求优先使用
+    active: AtomicUsize,
+}
+
+impl Route {
+    fn active_url(&self) -> &str {
+        &self.endpoints[self.active.load(Ordering::Relaxed) % self.endpoints.len()]
+    }
+}
+
+/// 转发使用的配置快照，更新配置时整体替换
+struct Runtime {
+    routes: Vec<Route>,
+    proxy: Option<String>,
+    agent: ureq::Agent,
+}
+
+impl Runtime {
+    fn route(&self, client: UrlTarget) -> Option<&Route> {
+        self.routes.iter().find(|r| r.client == client)
+    }
+}
+
+struct Shared {
+    /// 每次启动随机生成的访问令牌，作为本地地址的第一段路径，防止其他本地程序或网页借用中转
+    token: String,
+    /// 监听端口，用于校验请求的 Host
+    port: u16,
+    runtime: RwLock<Arc<Runtime>>,
+    log: Mutex<VecDeque<RelayLogEntry>>,
+    requests: AtomicU64,
+    /// 正在处理的连接数
+    connections: AtomicUsize,
+}
+
+struct RelayHandle {
+    port: u16,
+    stop: Arc<AtomicBool>,
+    thread: JoinHandle<()>,
+    shared: Arc<Shared>,
+    /// 已改为指向中转的客户端及其修改前的 (Base URL, 密钥)，停止时原样恢复
+    applied: Vec<(UrlTarget, Option<(String, String)>)>,
+}
+
+fn relay() -> &'static Mutex<Option<RelayHandle>> {
+    static RELAY: OnceLock<Mutex<Option<RelayHandle>>> = OnceLock::new();
+    RELAY.get_or_init(|| Mutex::new(None))
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+/// 客户端应使用的本地地址
+pub fn local_url(port: u16, token: &str, client: UrlTarget) -> String {
+    let prefix = match client {
+        UrlTarget::Claude => CLAUDE_PREFIX,
+        UrlTarget::Codex => CODEX_PREFIX,
+    };
+    format!("http://127.0.0.1:{}/{}{}", port, token, prefix)
+}
+
+/// 生成 128 位随机访问令牌（十六进制）
+fn new_token() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 解析单个客户端的转发配置：补全上游地址和密钥，均无法确定时返回 None
+fn resolve_route(client: UrlTarget, config: Option<&RouteConfig>) -> AppResult<Option<Route>> {
+    let configured = account::configured_credentials(client);
+    let Some(config) = config else {
+        // 未显式配置时，仅在客户端已配置密钥时启用；配置仍指向中转（上次未恢复）时改用预设地址
+        let Some((url, key)) = configured else { return Ok(None) };
+        let endpoints: Vec<String> = if is_local(&url) {
+            presets::load().urls(client).map(str::to_string).collect()
+        } else {
+            vec![url]
+        };
+        return build_route(client, &endpoints, key).map(Some);
+    };
+    if config.disabled {
+        return Ok(None);
+    }
+
+    let api_key = match config.api_key.as_deref().map(str::trim).filter(|k| !k.is_empty()) {
+        Some(key) => key.to_string(),
+        None => configured
+            .map(|(_, key)| key)
+            .ok_or_else(|| AppError::invalid_input(t!("relay.no_key", client = client_name(client))))?,
+    };
+    let endpoints: Vec<String> = if config.endpoints.iter().all(|url| url.trim().is_empty()) {
+        presets::load().urls(client).map(str::to_string).collect()
+    } else {
+        config.endpoints.iter().filter(|url| !url.trim().is_empty()).cloned().collect()
+    };
+    build_route(client, &endpoints, api_key).map(Some)
+}
+
+fn build_route(client: UrlTarget, urls: &[String], api_key: String) -> AppResult<Route> {
+    let mut endpoints: Vec<String> = Vec::new();
+    for url in urls {
+        let url = base_url::normalize(client, url)?.url;
+        if is_local(&url) {
+            return Err(AppError::invalid_input(t!("relay.loop", url = url)));
+        }
+        if !endpoints.contains(&url) {
+            endpoints.push(url);
+        }
+    }
+    if endpoints.is_empty() {
+        return Err(AppError::invalid_input(t!("relay.no_endpoints", client = client_name(client))));
+    }
+    Ok(Route {
+        client,
+        endpoints,
+        api_key,
+        active: AtomicUsize::new(0),
+    })
+}
+
+/// 是否指向本机中转（避免请求转发回自身）
+fn is_local(url: &str) -> bool {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let (host, path) = rest.find('/').map_or((rest, ""), |i| rest.split_at(i));
+    let host = host.rsplit_once(':').map_or(host, |(host, _)| host);
+    // 路径第一段为访问令牌（旧版本没有令牌）
+    matches!(host, "127.0.0.1" | "localhost") && (path.contains(CLAUDE_PREFIX) || path.contains(CODEX_PREFIX))
+}
+
+fn client_name(client: UrlTarget) -> &'static str {
+    match client {
+        UrlTarget::Claude => "Claude Code",
+        UrlTarget::Codex => "Codex",
+    }
+}
+
+fn build_runtime(config: &RelayConfig) -> AppResult<Runtime> {
+    let mut routes = Vec::new();
+    for client in [UrlTarget::Claude, UrlTarget::Codex] {
+        let route_config = match client {
+            UrlTarget::Claude => config.claude.as_ref(),
+            UrlTarget::Codex => config.codex.as_ref(),
+        };
+        if let Some(route) = resolve_route(client, route_config)? {
+            routes.push(route);
+        }
+    }
+    if routes.is_empty() {
+        return Err(AppError::invalid_input(t!("relay.no_routes")).with_action(SuggestedAction::CheckInput));
+    }
+
+    let proxy = config.proxy.as_deref().map(str::trim).filter(|p| !p.is_empty()).map(str::to_string);
+    let builder = ureq::AgentBuilder::new()
+        .timeout_connect(CONNECT_TIMEOUT)
+        .timeout_read(READ_TIMEOUT)
+        .redirects(0);
+    let builder = match &proxy {
+        Some(proxy) => builder.proxy(ureq::Proxy::new(proxy).map_err(|e| {
+            AppError::invalid_input(t!("relay.invalid_proxy", proxy = proxy)).with_source(e)
+        })?),
+        None => builder.try_proxy_from_env(true),
+    };
+
+    Ok(Runtime {
+        routes,
+        proxy,
+        agent: builder.build(),
+    })
+}
+
+/// 启动中转。apply_config 为 true 时将已启用的客户端配置改为指向中转
+pub fn start(config: RelayConfig, apply_config: bool) -> AppResult<RelayStatus> {
+    let mut guard = relay().lock().unwrap();
+    if let Some(handle) = guard.as_ref() {
+        return Err(AppError::invalid_input(t!("relay.already_running", port = handle.port)));
+    }
+
+    let runtime = build_runtime(&config)?;
+    let port = config.port.unwrap_or(DEFAULT_PORT);
+    let listener = TcpListener::bind(SocketAddr::from((Ipv4Addr::LOCALHOST, port))).map_err(|e| {
+        AppError::new(ErrorKind::Other, t!("relay.bind_failed", port = port))
+            .with_source(e)
+            .with_action(SuggestedAction::CheckInput)
+    })?;
+
+    let shared = Arc::new(Shared {
+        token: new_token(),
+        port,
+        runtime: RwLock::new(Arc::new(runtime)),
+        log: Mutex::new(VecDeque::new()),
+        requests: AtomicU64::new(0),
+        connections: AtomicUsize::new(0),
+    });
+    let stop = Arc::new(AtomicBool::new(false));
+    let thread = {
+        let shared = shared.clone();
+        let stop = stop.clone();
+        thread::spawn(move || accept_loop(listener, shared, stop))
+    };
+    log::info!("本地中转已启动: 127.0.0.1:{}", port);
+
+    let mut handle = RelayHandle {
+        port,
+        stop,
+        thread,
+        shared,
+        applied: Vec::new(),
+    };
+
+    if apply_config {
+        let routes: Vec<(UrlTarget, String)> = handle
+            .shared
+            .runtime
+            .read()
+            .unwrap()
+            .routes
+            .iter()
+            .map(|r| (r.client, r.api_key.clone()))
+            .collect();
+        for (client, api_key) in routes {
+            let original = account::configured_credentials(client);
+            let url = local_url(port, &handle.shared.token, client);
+            let result = match client {
+                UrlTarget::Claude => claude_config::configure_claude_code(url, api_key),
+                UrlTarget::Codex => codex_config::configure_codex(url, api_key),
+            };
+            if let Err(e) = result {
+                // 配置写入失败时恢复已修改的客户端并停止中转，避免客户端指向未运行的中转
+                let _ = shutdown(handle, true);
+                return Err(e);
+            }
+            handle.applied.push((client, original));
+        }
+    }
+
+    let status = status_of(Some(&handle));
+    *guard = Some(handle);
+    Ok(status)
+}
+
+/// 运行中更新上游地址、密钥和代理（端口变更需重启中转）
+pub fn update(config: RelayConfig) -> AppResult<RelayStatus> {
+    let guard = relay().lock().unwrap();
+    let handle = guard.as_ref().ok_or_else(|| AppError::not_found(t!("relay.not_running")))?;
+    let runtime = build_runtime(&config)?;
+    // 已指向中转的客户端必须保留转发，否则其请求将无处可去
+    if let Some((client, _)) = handle.applied.iter().find(|(c, _)| runtime.route(*c).is_none()) {
+        return Err(AppError::invalid_input(t!("relay.route_in_use", client = client_name(*client))));
+    }
+    *handle.shared.runtime.write().unwrap() = Arc::new(runtime);
+    log::info!("本地中转配置已更新");
+    Ok(status_of(Some(handle)))
+}
+
+/// 停止中转。restore_config 为 true 时将启动时修改的客户端配置恢复为启动前的地址和密钥
+pub fn stop(restore_config: bool) -> AppResult<RelayStatus> {
+    let handle = relay()
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| AppError::not_found(t!("relay.not_running")))?;
+    shutdown(handle, restore_config)?;
+    Ok(status_of(None))
+}
+
+/// 应用退出时停止中转并恢复客户端配置，避免客户端指向已关闭的端口
+pub fn stop_on_exit() {
+    let Some(handle) = relay().lock().unwrap().take() else { return };
+    if let Err(e) = shutdown(handle, true) {
+        log::error!("退出时恢复客户端配置失败: {}", e);
+    }
+}
+
+fn shutdown(handle: RelayHandle, restore_config: bool) -> AppResult<()> {
+    let runtime = handle.shared.runtime.read().unwrap().clone();
+    handle.stop.store(true, Ordering::SeqCst);
+    // accept 是阻塞的，连接一次使其返回并检查停止标志
+    let _ = TcpStream::connect_timeout(&SocketAddr::from((Ipv4Addr::LOCALHOST, handle.port)), CONNECT_TIMEOUT);
+    let _ = handle.thread.join();
+    log::info!("本地中转已停止: 127.0.0.1:{}", handle.port);
+
+    if restore_config {
+        for (client, original) in &handle.applied {
+            match (client, restore_target(original.as_ref(), runtime.route(*client))) {
+                (UrlTarget::Claude, Some((url, api_key))) => claude_config::configure_claude_code(url, api_key)?,
+                (UrlTarget::Codex, Some((url, api_key))) => codex_config::configure_codex(url, api_key)?,
+                // 没有可恢复的配置：移除中转写入的地址和密钥，不把上游密钥留在客户端
+                (UrlTarget::Claude, None) => {
+                    claude_config::remove_claude_config()?;
+                }
+                (UrlTarget::Codex, None) => {
+                    codex_config::remove_codex_config()?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 停止中转时写回客户端的 (Base URL, 密钥)：优先使用启动前的配置；
+/// 启动前仍指向中转（上次未恢复）时改用当前使用的上游地址。
+/// 返回 None 表示没有可恢复的配置，应移除中转写入的配置
+fn restore_target(original: Option<&(String, String)>, route: Option<&Route>) -> Option<(String, String)> {
+    match original? {
+        (url, key) if !is_local(url) => Some((url.clone(), key.clone())),
+        _ => route.map(|r| (r.active_url().to_string(), r.api_key.clone())),
+    }
+}
+
+/// 当前中转状态
+pub fn status() -> RelayStatus {
+    status_of(relay().lock().unwrap().as_ref())
+}
+
+fn status_of(handle: Option<&RelayHandle>) -> RelayStatus {
+    let Some(handle) = handle else {
+        return RelayStatus {
+            running: false,
+            port: None,
+            proxy: None,
+            routes: Vec::new(),
+            applied: Vec::new(),
+            requests: 0,
+            recent: Vec::new(),
+        };
+    };
+    let runtime = handle.shared.runtime.read().unwrap().clone();
+    RelayStatus {
+        running: true,
+        port: Some(handle.port),
+        proxy: runtime.proxy.clone(),
+        routes: runtime
+            .routes
+            .iter()
+            .map(|route| RouteStatus {
+                client: route.client,
+                local_url: local_url(handle.port, &handle.shared.token, route.client),
+                endpoints: route.endpoints.clone(),
+                active: route.active_url().to_string(),
+                key_hint: crate::bundle::mask(&route.api_key),
+            })
+            .collect(),
+        applied: handle.applied.iter().map(|(client, _)| *client).collect(),
+        requests: handle.shared.requests.load(Ordering::Relaxed),
+        recent: handle.shared.log.lock().unwrap().iter().rev().cloned().collect(),
+    }
+}
+
+fn accept_loop(listener: TcpListener, shared: Arc<Shared>, stop: Arc<AtomicBool>) {
+    for stream in listener.incoming() {
+        if stop.load(Ordering::SeqCst) {
+            break;
+        }
+        let Ok(stream) = stream else { continue };
+        shared.connections.fetch_add(1, Ordering::SeqCst);
+        let slot = ConnectionSlot(shared.clone());
+        thread::spawn(move || handle_connection(stream, &slot.0));
+
+        // 名额用尽时暂停接受新连接
+        while shared.connections.load(Ordering::SeqCst) >= MAX_CONNECTIONS {
+            if stop.load(Ordering::SeqCst) {
+                return;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+/// 占用一个连接名额，处理结束（包括 panic）时释放
+struct ConnectionSlot(Arc<Shared>);
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.connections.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// 客户端发来的请求
+struct IncomingRequest {
+    method: String,
+    path: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl IncomingRequest {
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// 转发过程中的错误：状态码和返回给客户端的说明
+struct RelayError {
+    status: u16,
+    message: String,
+}
+
+impl RelayError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+fn handle_connection(stream: TcpStream, shared: &Shared) {
+    let started = Instant::now();
+    let timestamp = now_millis();
+    let _ = stream.set_read_timeout(Some(CLIENT_TIMEOUT));
+    let Ok(mut writer) = stream.try_clone() else { return };
+    let mut reader = BufReader::new(stream);
+
+    let request = match read_request(&mut reader, &mut writer) {
+        Ok(Some(request)) => request,
+        // 连接在发送请求前关闭（包括停止中转时的唤醒连接）
+        Ok(None) => return,
+        Err(e) => {
+            let _ = write_error(&mut writer, &e);
+            return;
+        }
+    };
+    shared.requests.fetch_add(1, Ordering::Relaxed);
+
+    let runtime = shared.runtime.read().unwrap().clone();
+    let mut entry = RelayLogEntry {
+        timestamp,
+        client: None,
+        method: request.method.clone(),
+        path: request.path.clone(),
+        upstream: None,
+        status: None,
+        duration_ms: 0,
+        attempts: 0,
+        error: None,
+    };
+
+    let result = authorize(shared, &request).and_then(|path| {
+        // 记录中不保存访问令牌
+        entry.path = path.to_string();
+        match route_for(&runtime, path) {
+            Some((route, rest)) => {
+                entry.client = Some(route.client);
+                forward(&runtime, route, rest, &request, &mut writer, &mut entry)
+            }
+            None => Err(RelayError::new(404, t!("relay.unknown_route", path = path))),
+        }
+    });
+    if let Err(e) = result {
+        entry.status = Some(e.status);
+        entry.error = Some(e.message.clone());
+        let _ = write_error(&mut writer, &e);
+    }
+    entry.duration_ms = started.elapsed().as_millis() as u64;
+
+    match &entry.error {
+        Some(error) => log::warn!(
+            "中转请求失败: {} {} -> {} ({})",
+            entry.method,
+            entry.path,
+            entry.upstream.as_deref().unwrap_or("-"),
+            error
+        ),
+        None => log::info!(
+            "中转请求: {} {} -> {} {} {}ms",
+            entry.method,
+            entry.path,
+            entry.upstream.as_deref().unwrap_or("-"),
+            entry.status.unwrap_or_default(),
+            entry.duration_ms
+        ),
+    }
+    let mut log = shared.log.lock().unwrap();
+    if log.len() >= MAX_LOG_ENTRIES {
+        log.pop_front();
+    }
+    log.push_back(entry);
+}
+
+/// 只接受本机客户端发来的请求：拒绝带 Origin 的请求（浏览器中的网页）和 Host 不是本机地址的请求
+/// （DNS 重绑定），并要求路径以访问令牌开头。返回去掉令牌后的路径
+fn authorize<'r>(shared: &Shared, request: &'r IncomingRequest) -> Result<&'r str, RelayError> {
+    if request.header("origin").is_some() {
+        return Err(RelayError::new(403, t!("relay.forbidden_origin")));
+    }
+    let host = request.header("host").unwrap_or_default().to_ascii_lowercase();
+    if host != format!("127.0.0.1:{}", shared.port) && host != format!("localhost:{}", shared.port) {
+        return Err(RelayError::new(403, t!("relay.forbidden_host", host = host)));
+    }
+    request
+        .path
+        .strip_prefix('/')
+        .and_then(|path| path.strip_prefix(shared.token.as_str()))
+        .filter(|rest| rest.is_empty() || rest.starts_with('/'))
+        .ok_or_else(|| RelayError::new(403, t!("relay.forbidden_token")))
+}
+
+/// 按路径前缀选择客户端，返回前缀之后的部分
+fn route_for<'a, 'p>(runtime: &'a Runtime, path: &'p str) -> Option<(&'a Route, &'p str)> {
+    [(UrlTarget::Codex, CODEX_PREFIX), (UrlTarget::Claude, CLAUDE_PREFIX)]
+        .into_iter()
+        .find_map(|(client, prefix)| {
+            let rest = path.strip_prefix(prefix)?;
+            if !(rest.is_empty() || rest.starts_with('/') || rest.starts_with('?')) {
+                return None;
+            }
+            Some((runtime.route(client)?, rest))
+        })
+}
+
+/// 依次尝试上游地址，从上次成功的地址开始；连接失败或返回 5xx 时换下一个
+fn forward(
+    runtime: &Runtime,
+    route: &Route,
+    rest: &str,
+    request: &IncomingRequest,
+    writer: &mut TcpStream,
+    entry: &mut RelayLogEntry,
+) -> Result<(), RelayError> {
+    let start = route.active.load(Ordering::Relaxed);
+    let count = route.endpoints.len();
+    let mut last_error = None;
+
+    for offset in 0..count {
+        let index = (start + offset) % count;
+        let upstream = &route.endpoints[index];
+        entry.attempts += 1;
+        entry.upstream = Some(upstream.clone());
+
+        let response = match send(runtime, route, upstream, rest, request) {
+            Ok(response) => response,
+            Err(ureq::Error::Status(status, response)) if status < 500 => response,
+            Err(ureq::Error::Status(status, _)) => {
+                log::warn!("上游返回 {}，尝试下一个地址: {}", status, upstream);
+                last_error = Some(RelayError::new(502, t!("relay.upstream_status", status = status)));
+                continue;
+            }
+            Err(ureq::Error::Transport(e)) => {
+                log::warn!("上游连接失败，尝试下一个地址: {} ({})", upstream, e);
+                last_error = Some(RelayError::new(502, t!("relay.upstream_failed", error = e)));
+                continue;
+            }
+        };
+
+        route.active.store(index, Ordering::Relaxed);
+        entry.status = Some(response.status());
+        // 响应已开始写出，之后的错误只能断开连接
+        if let Err(e) = write_response(writer, response) {
+            entry.error = Some(e.to_string());
+        }
+        return Ok(());
+    }
+    Err(last_error.unwrap_or_else(|| RelayError::new(502, t!("relay.no_upstream"))))
+}
+
+// 直接返回 ureq 的错误类型，调用方据此区分状态码和连接错误
+#[allow(clippy::result_large_err)]
+fn send(
+    runtime: &Runtime,
+    route: &Route,
+    upstream: &str,
+    rest: &str,
+    request: &IncomingRequest,
+) -> Result<ureq::Response, ureq::Error> {
+    let url = format!("{}{}", upstream.trim_end_matches('/'), rest);
+    let mut upstream_request = runtime.agent.request(&request.method, &url);
+    for (name, value) in &request.headers {
+        let lower = name.to_ascii_lowercase();
+        if SKIPPED_HEADERS.contains(&lower.as_str()) || lower == "authorization" || lower == "x-api-key" {
+            continue;
+        }
+        upstream_request = upstream_request.set(name, value);
+    }
+    // 按客户端使用的认证方式注入密钥
+    upstream_request = if request.header("x-api-key").is_some() {
+        upstream_request.set("x-api-key", &route.api_key)
+    } else {
+        upstream_request.set("Authorization", &format!("Bearer {}", route.api_key))
+    };
+
+    if request.body.is_empty() && matches!(request.method.as_str(), "GET" | "HEAD" | "DELETE" | "OPTIONS") {
+        upstream_request.call()
+    } else {
+        upstream_request.send_bytes(&request.body)
+    }
+}
+
+/// 读取一个 HTTP/1.1 请求；连接在发送任何数据前关闭时返回 None
+fn read_request(reader: &mut BufReader<TcpStream>, writer: &mut TcpStream) -> Result<Option<IncomingRequest>, RelayError> {
+    let bad_request = |_| RelayError::new(400, t!("relay.bad_request"));
+    let mut header_bytes = 0;
+    let mut read_line = |reader: &mut BufReader<TcpStream>| -> Result<String, RelayError> {
+        let mut line = String::new();
+        let n = reader.read_line(&mut line).map_err(bad_request)?;
+        header_bytes += n;
+        if header_bytes > MAX_HEADER_BYTES {
+            return Err(RelayError::new(431, t!("relay.bad_request")));
+        }
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    };
+
+    let request_line = read_line(reader)?;
+    if request_line.is_empty() {
+        return Ok(None);
+    }
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path), Some(_version)) = (parts.next(), parts.next(), parts.next()) else {
+        return Err(RelayError::new(400, t!("relay.bad_request")));
+    };
+
+    let mut headers = Vec::new();
+    loop {
+        let line = read_line(reader)?;
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line
+            .split_once(':')
+            .ok_or_else(|| RelayError::new(400, t!("relay.bad_request")))?;
+        headers.push((name.trim().to_string(), value.trim().to_string()));
+    }
+
+    let mut request = IncomingRequest {
+        method: method.to_ascii_uppercase(),
+        path: path.to_string(),
+        headers,
+        body: Vec::new(),
+    };
+
+    if request
+        .header("expect")
+        .is_some_and(|v| v.eq_ignore_ascii_case("100-continue"))
+    {
+        writer.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").map_err(bad_request)?;
+    }
+
+    let chunked = request
+        .header("transfer-encoding")
+        .is_some_and(|v| v.to_ascii_lowercase().contains("chunked"));
+    if chunked {
+        request.body = read_chunked(reader)?;
+    } else if let Some(length) = request.header("content-length") {
+        let length: usize = length.parse().map_err(|_| RelayError::new(400, t!("relay.bad_request")))?;
+        if length > MAX_BODY_BYTES {
+            return Err(RelayError::new(413, t!("relay.body_too_large")));
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).map_err(bad_request)?;
+        request.body = body;
+    }
+    Ok(Some(request))
+}
+
+fn read_chunked(reader: &mut BufReader<TcpStream>) -> Result<Vec<u8>, RelayError> {
+    let bad_request = |_| RelayError::new(400, t!("relay.bad_request"));
+    let mut body = Vec::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).map_err(bad_request)?;
+        let size = line.trim().split(';').next().unwrap_or_default();
+        let size = usize::from_str_radix(size, 16).map_err(|_| RelayError::new(400, t!("relay.bad_request")))?;
+        if size == 0 {
+            // 跳过 trailer 直到空行
+            loop {
+                line.clear();
+                let n = reader.read_line(&mut line).map_err(bad_request)?;
+                if n == 0 || line.trim().is_empty() {
+                    return Ok(body);
+                }
+            }
+        }
+        if body.len() + size > MAX_BODY_BYTES {
+            return Err(RelayError::new(413, t!("relay.body_too_large")));
+        }
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..]).map_err(bad_request)?;
+        let mut crlf = [0; 2];
+        reader.read_exact(&mut crlf).map_err(bad_request)?;
+    }
+}
+
+/// 将上游响应原样写回客户端（流式响应边收边发）
+fn write_response(writer: &mut TcpStream, response: ureq::Response) -> io::Result<u64> {
+    let mut head = format!("HTTP/1.1 {} {}\r\n", response.status(), response.status_text());
+    for name in response.headers_names() {
+        if SKIPPED_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+            continue;
+        }
+        for value in response.all(&name) {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+    }
+    head.push_str("Connection: close\r\n\r\n");
+    writer.write_all(head.as_bytes())?;
+
+    let mut reader = response.into_reader();
+    let mut buffer = [0; 8192];
+    let mut total = 0;
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        // 逐块写出并刷新，保证 SSE 事件及时到达客户端
+        writer.write_all(&buffer[..n])?;
+        writer.flush()?;
+        total += n as u64;
+    }
+    Ok(total)
+}
+
+/// 中转自身产生的错误，格式与 Anthropic/OpenAI 错误响应相近
+fn write_error(writer: &mut TcpStream, error: &RelayError) -> io::Result<()> {
+    let body = serde_json::json!({
+        "type": "error",
+        "error": { "type": "relay_error", "message": error.message },
+    })
+    .to_string();
+    let reason = match error.status {
+        400 => "Bad Request",
+        403 => "Forbidden",
+        404 => "Not Found",
+        413 => "Payload Too Large",
+        431 => "Request Header Fields Too Large",
+        _ => "Bad Gateway",
+    };
+    write!(
+        writer,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        error.status,
+        reason,
+        body.len(),
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc::{self, Receiver};
+
+    /// 上游收到的请求
+    struct Captured {
+        request_line: String,
+        headers: Vec<(String, String)>,
+        body: Vec<u8>,
+    }
+
+    impl Captured {
+        fn header(&self, name: &str) -> Option<&str> {
+            self.headers
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, v)| v.as_str())
+        }
+    }
+
+    /// 本地上游：记录每个请求后交给 respond 写出响应
+    fn upstream<F>(respond: F) -> (String, Receiver<Captured>)
+    where
+        F: Fn(&mut TcpStream) + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://127.0.0.1:{}", listener.local_addr().unwrap().port());
+        let (sender, receiver) = mpsc::channel();
+        let respond = Arc::new(respond);
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut headers = Vec::new();
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let Some((name, value)) = line.trim_end().split_once(':') else { break };
+                    headers.push((name.trim().to_string(), value.trim().to_string()));
+                }
+                let mut captured = Captured {
+                    request_line: request_line.trim_end().to_string(),
+                    headers,
+                    body: Vec::new(),
+                };
+                let length = captured.header("content-length").map_or(0, |v| v.parse().unwrap());
+                captured.body = vec![0; length];
+                reader.read_exact(&mut captured.body).unwrap();
+                let _ = sender.send(captured);
+                respond(&mut stream);
+            }
+        });
+        (url, receiver)
+    }
+
+    fn reply(status: u16, body: &'static str) -> impl Fn(&mut TcpStream) + Send + Sync + 'static {
+        move |stream: &mut TcpStream| {
+            let _ = write!(
+                stream,
+                "HTTP/1.1 {} Stub\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+        }
+    }
+
+    /// 没有服务监听的本地地址
+    fn refused() -> String {
+        let port = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        format!("http://127.0.0.1:{}", port)
+    }
+
+    /// 在随机端口启动中转，只转发 Claude Code 请求
+    fn spawn_relay(endpoints: &[String]) -> Arc<Shared> {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let runtime = Runtime {
+            routes: vec![build_route(UrlTarget::Claude, endpoints, "relay-key".to_string()).unwrap()],
+            proxy: None,
+            agent: ureq::AgentBuilder::new()
+                .timeout_connect(Duration::from_secs(2))
+                .timeout_read(Duration::from_secs(10))
+                .redirects(0)
+                .build(),
+        };
+        let shared = Arc::new(Shared {
+            token: new_token(),
+            port: listener.local_addr().unwrap().port(),
+            runtime: RwLock::new(Arc::new(runtime)),
+            log: Mutex::new(VecDeque::new()),
+            requests: AtomicU64::new(0),
+            connections: AtomicUsize::new(0),
+        });
+        let accept = shared.clone();
+        thread::spawn(move || accept_loop(listener, accept, Arc::new(AtomicBool::new(false))));
+        shared
+    }
+
+    fn connect(shared: &Shared) -> TcpStream {
+        let stream = TcpStream::connect(("127.0.0.1", shared.port)).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        stream
+    }
+
+    /// 发送原始请求并读取完整响应（中转总是以 Connection: close 结束响应）
+    fn exchange(shared: &Shared, raw: &str) -> (u16, String) {
+        let mut stream = connect(shared);
+        stream.write_all(raw.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response.split_whitespace().nth(1).unwrap().parse().unwrap();
+        let body = response.split_once("\r\n\r\n").map(|(_, b)| b.to_string()).unwrap_or_default();
+        (status, body)
+    }
+
+    fn post(shared: &Shared, path: &str, headers: &str, body: &str) -> (u16, String) {
+        exchange(
+            shared,
+            &format!(
+                "POST /{}{} HTTP/1.1\r\nHost: 127.0.0.1:{}\r\n{}Content-Length: {}\r\n\r\n{}",
+                shared.token,
+                path,
+                shared.port,
+                headers,
+                body.len(),
+                body
+            ),
+        )
+    }
+
+    fn last_log(shared: &Shared) -> RelayLogEntry {
+        shared.log.lock().unwrap().back().cloned().unwrap()
+    }
+
+    #[test]
+    fn injects_key_for_the_auth_scheme_in_use() {
+        let (url, requests) = upstream(reply(200, r#"{"ok":true}"#));
+        let shared = spawn_relay(&[url]);
+
+        let (status, body) = post(&shared, "/claude/v1/messages?beta=true", "x-api-key: client-key\r\n", "{}");
+        assert_eq!((status, body.as_str()), (200, r#"{"ok":true}"#));
+        let captured = requests.recv().unwrap();
+        assert_eq!(captured.request_line, "POST /v1/messages?beta=true HTTP/1.1");
+        assert_eq!(captured.header("x-api-key"), Some("relay-key"));
+        assert_eq!(captured.header("authorization"), None);
+        assert_eq!(captured.body, b"{}");
+
+        post(&shared, "/claude/v1/messages", "Authorization: Bearer client-key\r\n", "{}");
+        let captured = requests.recv().unwrap();
+        assert_eq!(captured.header("authorization"), Some("Bearer relay-key"));
+        assert_eq!(captured.header("x-api-key"), None);
+
+        // 记录中不包含访问令牌
+        let entry = last_log(&shared);
+        assert_eq!(entry.path, "/claude/v1/messages");
+        assert_eq!((entry.client, entry.status, entry.attempts), (Some(UrlTarget::Claude), Some(200), 1));
+    }
+
+    #[test]
+    fn fails_over_on_server_errors() {
+        let (broken, _broken_requests) = upstream(reply(503, "overloaded"));
+        let (healthy, requests) = upstream(reply(200, "ok"));
+        let shared = spawn_relay(&[broken, healthy.clone()]);
+
+        assert_eq!(post(&shared, "/claude/v1/messages", "", "{}"), (200, "ok".to_string()));
+        let entry = last_log(&shared);
+        assert_eq!((entry.attempts, entry.upstream.as_deref()), (2, Some(healthy.as_str())));
+        assert_eq!(requests.recv().unwrap().body, b"{}");
+
+        // 之后的请求直接使用上次成功的上游
+        post(&shared, "/claude/v1/messages", "", "{}");
+        assert_eq!(last_log(&shared).attempts, 1);
+    }
+
+    #[test]
+    fn fails_over_on_refused_connections() {
+        let (healthy, _requests) = upstream(reply(200, "ok"));
+        let shared = spawn_relay(&[refused(), healthy.clone()]);
+
+        assert_eq!(post(&shared, "/claude/v1/messages", "", "{}"), (200, "ok".to_string()));
+        let entry = last_log(&shared);
+        assert_eq!((entry.attempts, entry.upstream.as_deref()), (2, Some(healthy.as_str())));
+    }
+
+    #[test]
+    fn reports_bad_gateway_when_all_upstreams_fail() {
+        let (broken, _requests) = upstream(reply(500, ""));
+        let shared = spawn_relay(&[broken, refused()]);
+
+        let (status, body) = post(&shared, "/claude/v1/messages", "", "{}");
+        assert_eq!(status, 502);
+        assert!(body.contains("relay_error"));
+        assert_eq!(last_log(&shared).attempts, 2);
+    }
+
+    #[test]
+    fn client_errors_are_passed_through() {
+        let (url, _requests) = upstream(reply(429, r#"{"error":"rate limited"}"#));
+        let shared = spawn_relay(&[url]);
+        assert_eq!(post(&shared, "/claude/v1/messages", "", "{}"), (429, r#"{"error":"rate limited"}"#.to_string()));
+        assert_eq!(last_log(&shared).attempts, 1);
+    }
+
+    #[test]
+    fn streams_server_sent_events() {
+        let (next, wait) = mpsc::channel::<()>();
+        let wait = Mutex::new(wait);
+        let (url, _requests) = upstream(move |stream: &mut TcpStream| {
+            let _ = stream.write_all(b"HTTP/1.1 200 OK\r\nContent-Type: text/event-stream\r\nConnection: close\r\n\r\n");
+            let _ = stream.write_all(b"data: first\n\n");
+            let _ = stream.flush();
+            // 客户端收到第一个事件后才发送第二个，中转缓冲整个响应时测试会超时失败
+            let _ = wait.lock().unwrap().recv_timeout(Duration::from_secs(5));
+            let _ = stream.write_all(b"data: second\n\n");
+        });
+        let shared = spawn_relay(&[url]);
+
+        let mut stream = connect(&shared);
+        write!(
+            stream,
+            "POST /{}/claude/v1/messages HTTP/1.1\r\nHost: localhost:{}\r\nContent-Length: 2\r\n\r\n{{}}",
+            shared.token, shared.port
+        )
+        .unwrap();
+        let mut reader = BufReader::new(stream);
+        let mut head = String::new();
+        loop {
+            let mut line = String::new();
+            reader.read_line(&mut line).unwrap();
+            if line == "\r\n" {
+                break;
+            }
+            head.push_str(&line);
+        }
+        assert!(head.starts_with("HTTP/1.1 200"));
+        assert!(head.contains("text/event-stream"));
+
+        let mut first = String::new();
+        reader.read_line(&mut first).unwrap();
+        assert_eq!(first, "data: first\n");
+        next.send(()).unwrap();
+        let mut rest = String::new();
+        reader.read_to_string(&mut rest).unwrap();
+        assert_eq!(rest, "\ndata: second\n\n");
+    }
+
+    #[test]
+    fn forwards_chunked_request_bodies() {
+        let (url, requests) = upstream(reply(200, "ok"));
+        let shared = spawn_relay(&[url]);
+
+        let (status, _) = exchange(
+            &shared,
+            &format!(
+                "POST /{}/claude/v1/messages HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nTransfer-Encoding: chunked\r\n\r\n\
+                 5\r\nhello\r\n7;ext=1\r\n, world\r\n0\r\nX-Trailer: 1\r\n\r\n",
+                shared.token, shared.port
+            ),
+        );
+        assert_eq!(status, 200);
+        let captured = requests.recv().unwrap();
+        assert_eq!(captured.body, b"hello, world");
+        assert_eq!(captured.header("content-length"), Some("12"));
+        assert_eq!(captured.header("transfer-encoding"), None);
+    }
+
+    #[test]
+    fn unknown_routes_return_not_found() {
+        let (url, _requests) = upstream(reply(200, "ok"));
+        let shared = spawn_relay(&[url]);
+
+        for path in ["/unknown", "/claudex/v1/messages", "/codex/v1/responses"] {
+            let (status, body) = post(&shared, path, "", "{}");
+            assert_eq!(status, 404, "{}", path);
+            assert!(body.contains(&t!("relay.unknown_route", path = path)));
+        }
+    }
+
+    #[test]
+    fn rejects_requests_without_token_or_from_other_origins() {
+        let (url, requests) = upstream(reply(200, "ok"));
+        let shared = spawn_relay(&[url]);
+        let port = shared.port;
+        let token = shared.token.clone();
+
+        let cases = [
+            format!("GET /claude/v1/models HTTP/1.1\r\nHost: 127.0.0.1:{}\r\n\r\n", port),
+            format!("GET /{}x/claude/v1/models HTTP/1.1\r\nHost: 127.0.0.1:{}\r\n\r\n", token, port),
+            format!("GET /{}/claude/v1/models HTTP/1.1\r\nHost: evil.example:{}\r\n\r\n", token, port),
+            format!("GET /{}/claude/v1/models HTTP/1.1\r\n\r\n", token),
+            format!(
+                "GET /{}/claude/v1/models HTTP/1.1\r\nHost: 127.0.0.1:{}\r\nOrigin: https://evil.example\r\n\r\n",
+                token, port
+            ),
+        ];
+        for raw in &cases {
+            assert_eq!(exchange(&shared, raw).0, 403, "{}", raw);
+        }
+        assert!(requests.try_recv().is_err());
+
+        let allowed = format!("GET /{}/claude/v1/models HTTP/1.1\r\nHost: LOCALHOST:{}\r\n\r\n", token, port);
+        assert_eq!(exchange(&shared, &allowed).0, 200);
+    }
+
+    #[test]
+    fn tokens_are_random_and_part_of_the_local_url() {
+        let (a, b) = (new_token(), new_token());
+        assert_eq!(a.len(), 32);
+        assert_ne!(a, b);
+        assert_eq!(
+            local_url(18888, &a, UrlTarget::Codex),
+            format!("http://127.0.0.1:18888/{}/codex/v1", a)
+        );
+        assert!(is_local(&local_url(18888, &a, UrlTarget::Claude)));
+        assert!(is_local("http://localhost:18888/claude"));
+        assert!(!is_local("https://88code.org/claude"));
+    }
+
+    #[test]
+    fn restores_the_original_client_config() {
+        let route = build_route(UrlTarget::Claude, &["https://88code.org/api".to_string()], "relay-key".to_string()).unwrap();
+        let original = ("https://example.com/api".to_string(), "original-key".to_string());
+        assert_eq!(restore_target(Some(&original), Some(&route)), Some(original.clone()));
+        assert_eq!(restore_target(Some(&original), None), Some(original));
+
+        // 启动前仍指向中转时改用当前上游
+        let expected = Some((route.active_url().to_string(), "relay-key".to_string()));
+        let stale = ("http://127.0.0.1:18888/abc/claude".to_string(), "old".to_string());
+        assert_eq!(restore_target(Some(&stale), Some(&route)), expected);
+        assert_eq!(restore_target(Some(&stale), None), None);
+
+        // 启动前未配置时不写入上游密钥，由调用方移除中转写入的配置
+        assert_eq!(restore_target(None, Some(&route)), None);
+        assert_eq!(restore_target(None, None), None);
+    }
+
+    #[test]
+    fn caps_concurrent_connections() {
+        let (url, _requests) = upstream(reply(200, "ok"));
+        let shared = spawn_relay(&[url]);
+
+        // 占满连接名额：这些连接不发送请求，处理线程阻塞在读取上
+        let held: Vec<TcpStream> = (0..MAX_CONNECTIONS).map(|_| connect(&shared)).collect();
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while shared.connections.load(Ordering::SeqCst) < MAX_CONNECTIONS {
+            assert!(Instant::now() < deadline);
+            thread::sleep(Duration::from_millis(10));
+        }
+        let waiting = {
+            let shared = shared.clone();
+            thread::spawn(move || post(&shared, "/claude/v1/messages", "", "{}"))
+        };
+        thread::sleep(Duration::from_millis(300));
+        assert!(!waiting.is_finished());
+        assert_eq!(shared.connections.load(Ordering::SeqCst), MAX_CONNECTIONS);
+
+        // 连接关闭后名额释放，等待中的请求得到处理
+        drop(held);
+        assert_eq!(waiting.join().unwrap(), (200, "ok".to_string()));
+    }
+}
@@ -0,0 +1,542 @@
+use crate::error::{AppError, AppResult};
+use crate::i18n::t;
+use serde::Serialize;
+use serde_json::Value;
+
+/// 诊断级别：错误会阻止写入，警告只提示
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// 高级配置校验结果中的单条诊断
+#[derive(Debug, Clone, Serialize)]
+pub struct Diagnostic {
+    /// 键路径，如 "permissions.allow[0]"，空字符串表示整个文件
+    pub path: String,
+    pub message: String,
+    pub severity: Severity,
+    pub suggestion: Option<String>,
+}
+
+/// Codex 高级配置两个文件的诊断
+#[derive(Debug, Clone, Serialize)]
+pub struct CodexDiagnostics {
+    pub auth: Vec<Diagnostic>,
+    pub config: Vec<Diagnostic>,
+}
+
+/// 值的类型约束
+enum Kind {
+    /// 不检查
+    Any,
+    String,
+    Bool,
+    Integer,
+    Number,
+    /// 已知取值，未知取值给出警告（客户端新版本可能增加取值）
+    Enum(&'static [&'static str]),
+    StringArray,
+    /// 允许 null 的值
+    Nullable(&'static Kind),
+    /// 固定字段的对象，未知字段给出警告
+    Object(&'static [Field]),
+    /// 任意键的对象，值为同一类型
+    Map(&'static Kind),
+}
+
+struct Field {
+    name: &'static str,
+    kind: Kind,
+}
+
+const fn field(name: &'static str, kind: Kind) -> Field {
+    Field { name, kind }
+}
+
+/// Claude Code settings.json
+const CLAUDE_SETTINGS: Kind = Kind::Object(&[
+    field("$schema", Kind::String),
+    field("apiKeyHelper", Kind::String),
+    field("awsAuthRefresh", Kind::String),
+    field("awsCredentialExport", Kind::String),
+    field("cleanupPeriodDays", Kind::Integer),
+    field("companyAnnouncements", Kind::StringArray),
+    field("disableAllHooks", Kind::Bool),
+    field("alwaysThinkingEnabled", Kind::Bool),
+    field("enableAllProjectMcpServers", Kind::Bool),
+    field("enabledMcpjsonServers", Kind::StringArray),
+    field("disabledMcpjsonServers", Kind::StringArray),
+    // Claude Code 只接受字符串形式的环境变量
+    field("env", Kind::Map(&Kind::String)),
+    field("forceLoginMethod", Kind::Enum(&["claudeai", "console"])),
+    field("forceLoginOrgUUID", Kind::String),
+    field("hooks", Kind::Any),
+    field("includeCoAuthoredBy", Kind::Bool),
+    field("model", Kind::String),
+    field("outputStyle", Kind::String),
+    field(
+        "permissions",
+        Kind::Object(&[
+            field("allow", Kind::StringArray),
+            field("ask", Kind::StringArray),
+            field("deny", Kind::StringArray),
+            field("additionalDirectories", Kind::StringArray),
+            field(
+                "defaultMode",
+                Kind::Enum(&["default", "acceptEdits", "plan", "bypassPermissions"]),
+            ),
+            field("disableBypassPermissionsMode", Kind::Enum(&["disable"])),
+        ]),
+    ),
+    field(
+        "statusLine",
+        Kind::Object(&[
+            field("type", Kind::Enum(&["command"])),
+            field("command", Kind::String),
+            field("padding", Kind::Integer),
+        ]),
+    ),
+]);
+
+/// Codex auth.json
+const CODEX_AUTH: Kind = Kind::Object(&[
+    field("OPENAI_API_KEY", Kind::Nullable(&Kind::String)),
+    field("tokens", Kind::Any),
+    field("last_refresh", Kind::Any),
+]);
+
+const MCP_SERVER: Kind = Kind::Object(&[
+    field("command", Kind::String),
+    field("args", Kind::StringArray),
+    field("env", Kind::Map(&Kind::String)),
+    field("url", Kind::String),
+    field("bearer_token", Kind::String),
+    field("enabled", Kind::Bool),
+    field("startup_timeout_ms", Kind::Integer),
+    field("startup_timeout_sec", Kind::Number),
+    field("tool_timeout_sec", Kind::Number),
+]);
+
+const MODEL_PROVIDER: Kind = Kind::Object(&[
+    field("name", Kind::String),
+    field("base_url", Kind::String),
+    field("env_key", Kind::String),
+    field("env_key_instructions", Kind::String),
+    field("wire_api", Kind::Enum(&["chat", "responses"])),
+    field("query_params", Kind::Map(&Kind::String)),
+    field("http_headers", Kind::Map(&Kind::String)),
+    field("env_http_headers", Kind::Map(&Kind::String)),
+    field("request_max_retries", Kind::Integer),
+    field("stream_max_retries", Kind::Integer),
+    field("stream_idle_timeout_ms", Kind::Integer),
+    field("requires_openai_auth", Kind::Bool),
+]);
+
+const REASONING_EFFORT: Kind = Kind::Enum(&["minimal", "low", "medium", "high"]);
+const REASONING_SUMMARY: Kind = Kind::Enum(&["auto", "concise", "detailed", "none"]);
+const APPROVAL_POLICY: Kind = Kind::Enum(&["untrusted", "on-failure", "on-request", "never"]);
+const SANDBOX_MODE: Kind = Kind::Enum(&["read-only", "workspace-write", "danger-full-access"]);
+
+/// profiles 中允许覆盖的字段
+const CODEX_PROFILE: Kind = Kind::Object(&[
+    field("model", Kind::String),
+    field("model_provider", Kind::String),
+    field("model_reasoning_effort", REASONING_EFFORT),
+    field("model_reasoning_summary", REASONING_SUMMARY),
+    field("model_verbosity", Kind::Enum(&["low", "medium", "high"])),
+    field("approval_policy", APPROVAL_POLICY),
+    field("sandbox_mode", SANDBOX_MODE),
+    field("disable_response_storage", Kind::Bool),
+]);
+
+/// Codex config.toml
+const CODEX_CONFIG: Kind = Kind::Object(&[
+    field("model", Kind::String),
+    field("model_provider", Kind::String),
+    field("model_context_window", Kind::Integer),
+    field("model_max_output_tokens", Kind::Integer),
+    field("model_reasoning_effort", REASONING_EFFORT),
+    field("model_reasoning_summary", REASONING_SUMMARY),
+    field("model_verbosity", Kind::Enum(&["low", "medium", "high"])),
+    field("approval_policy", APPROVAL_POLICY),
+    field("sandbox_mode", SANDBOX_MODE),
+    field(
+        "sandbox_workspace_write",
+        Kind::Object(&[
+            field("writable_roots", Kind::StringArray),
+            field("network_access", Kind::Bool),
+            field("exclude_tmpdir_env_var", Kind::Bool),
+            field("exclude_slash_tmp", Kind::Bool),
+        ]),
+    ),
+    field("disable_response_storage", Kind::Bool),
+    field("hide_agent_reasoning", Kind::Bool),
+    field("show_raw_agent_reasoning", Kind::Bool),
+    field(
+        "file_opener",
+        Kind::Enum(&["vscode", "vscode-insiders", "windsurf", "cursor", "none"]),
+    ),
+    field("notify", Kind::StringArray),
+    field("instructions", Kind::String),
+    field("project_doc_max_bytes", Kind::Integer),
+    field("preferred_auth_method", Kind::Enum(&["chatgpt", "apikey"])),
+    field("profile", Kind::String),
+    field("profiles", Kind::Map(&CODEX_PROFILE)),
+    field(
+        "history",
+        Kind::Object(&[
+            field("persistence", Kind::Enum(&["save-all", "none"])),
+            field("max_bytes", Kind::Integer),
+        ]),
+    ),
+    field(
+        "shell_environment_policy",
+        Kind::Object(&[
+            field("inherit", Kind::Enum(&["all", "core", "none"])),
+            field("ignore_default_excludes", Kind::Bool),
+            field("exclude", Kind::StringArray),
+            field("include_only", Kind::StringArray),
+            field("set", Kind::Map(&Kind::String)),
+            field("experimental_use_profile", Kind::Bool),
+        ]),
+    ),
+    field("tools", Kind::Object(&[field("web_search", Kind::Bool)])),
+    field("tui", Kind::Any),
+    field("mcp_servers", Kind::Map(&MCP_SERVER)),
+    field("model_providers", Kind::Map(&MODEL_PROVIDER)),
+    field(
+        "projects",
+        Kind::Map(&Kind::Object(&[field(
+            "trust_level",
+            Kind::Enum(&["trusted", "untrusted"]),
+        )])),
+    ),
+]);
+
+/// Codex 内置的 provider，无需在 model_providers 中定义
+const BUILTIN_PROVIDERS: &[&str] = &["openai", "oss"];
+
+/// 编辑距离，用于猜测拼错的键或枚举值
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut cur = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            cur.push((prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1));
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// 在候选中找与 input 最接近的（最多相差三分之一的字符，至少允许 2 个）
+fn closest<'a>(input: &str, candidates: impl Iterator<Item = &'a str>) -> Option<&'a str> {
+    let limit = (input.chars().count() / 3).max(2);
+    candidates
+        .map(|c| (edit_distance(input, c), c))
+        .filter(|(d, _)| *d <= limit)
+        .min_by_key(|(d, _)| *d)
+        .map(|(_, c)| c)
+}
+
+fn type_name(kind: &Kind) -> String {
+    match kind {
+        Kind::Any => t!("schema.type.any"),
+        Kind::String | Kind::Enum(_) => t!("schema.type.string"),
+        Kind::Bool => t!("schema.type.bool"),
+        Kind::Integer => t!("schema.type.integer"),
+        Kind::Number => t!("schema.type.number"),
+        Kind::StringArray => t!("schema.type.string_array"),
+        Kind::Nullable(inner) => t!("schema.type.nullable", kind = type_name(inner)),
+        Kind::Object(_) | Kind::Map(_) => t!("schema.type.object"),
+    }
+}
+
+fn join_path(parent: &str, key: &str) -> String {
+    if parent.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", parent, key)
+    }
+}
+
+/// 类型错误时，根据实际值给出修改建议（如 "true" -> true）
+fn type_suggestion(kind: &Kind, value: &Value) -> Option<String> {
+    let s = value.as_str()?;
+    match kind {
+        Kind::Bool if s == "true" || s == "false" => Some(t!("schema.unquote", value = s)),
+        Kind::Integer if s.parse::<i64>().is_ok() => Some(t!("schema.unquote", value = s)),
+        Kind::Number if s.parse::<f64>().is_ok() => Some(t!("schema.unquote", value = s)),
+        Kind::StringArray => Some(t!("schema.wrap_array", value = s)),
+        _ => None,
+    }
+}
+
+fn check(kind: &Kind, value: &Value, path: &str, out: &mut Vec<Diagnostic>) {
+    let mismatch = |out: &mut Vec<Diagnostic>| {
+        out.push(Diagnostic {
+            path: path.to_string(),
+            message: t!("schema.type_mismatch", kind = type_name(kind)),
+            severity: Severity::Error,
+            suggestion: type_suggestion(kind, value),
+        })
+    };
+
+    match kind {
+        Kind::Any => {}
+        Kind::String if !value.is_string() => mismatch(out),
+        Kind::Bool if !value.is_boolean() => mismatch(out),
+        Kind::Integer if !(value.is_i64() || value.is_u64()) => mismatch(out),
+        Kind::Number if !value.is_number() => mismatch(out),
+        Kind::String | Kind::Bool | Kind::Integer | Kind::Number => {}
+        Kind::Enum(options) => match value.as_str() {
+            None => mismatch(out),
+            Some(s) if !options.contains(&s) => out.push(Diagnostic {
+                path: path.to_string(),
+                message: t!("schema.invalid_value", value = s, options = options.join(", ")),
+                severity: Severity::Warning,
+                suggestion: closest(s, options.iter().copied()).map(|c| t!("schema.did_you_mean", value = c)),
+            }),
+            Some(_) => {}
+        },
+        Kind::StringArray => match value.as_array() {
+            None => mismatch(out),
+            Some(items) => {
+                for (i, item) in items.iter().enumerate() {
+                    check(&Kind::String, item, &format!("{}[{}]", path, i), out);
+                }
+            }
+        },
+        Kind::Nullable(inner) => {
+            if !value.is_null() {
+                let start = out.len();
+                check(inner, value, path, out);
+                // 内层类型不符时，提示中注明同样可以为 null
+                for diagnostic in &mut out[start..] {
+                    if diagnostic.path == path && diagnostic.severity == Severity::Error {
+                        diagnostic.message = t!("schema.type_mismatch", kind = type_name(kind));
+                    }
+                }
+            }
+        }
+        Kind::Map(inner) => match value.as_object() {
+            None => mismatch(out),
+            Some(obj) => {
+                for (key, item) in obj {
+                    check(inner, item, &join_path(path, key), out);
+                }
+            }
+        },
+        Kind::Object(fields) => match value.as_object() {
+            None => mismatch(out),
+            Some(obj) => {
+                for (key, item) in obj {
+                    let child = join_path(path, key);
+                    match fields.iter().find(|f| f.name == key) {
+                        Some(f) => check(&f.kind, item, &child, out),
+                        None => out.push(Diagnostic {
+                            path: child,
+                            message: t!("schema.unknown_key", key = key),
+                            severity: Severity::Warning,
+                            suggestion: closest(key, fields.iter().map(|f| f.name))
+                                .map(|c| t!("schema.did_you_mean", value = c)),
+                        }),
+                    }
+                }
+            }
+        },
+    }
+}
+
+fn parse_error(message: String) -> Vec<Diagnostic> {
+    vec![Diagnostic {
+        path: String::new(),
+        message,
+        severity: Severity::Error,
+        suggestion: None,
+    }]
+}
+
+/// 校验 Claude Code settings.json 内容
+pub fn validate_claude_settings(content: &str) -> Vec<Diagnostic> {
+    let value: Value = match serde_json::from_str(content) {
+        Ok(value) => value,
+        Err(e) => return parse_error(t!("schema.json_error", error = e)),
+    };
+    let mut out = Vec::new();
+    check(&CLAUDE_SETTINGS, &value, "", &mut out);
+    out
+}
+
+/// 校验 Codex auth.json 内容
+pub fn validate_codex_auth(content: &str) -> Vec<Diagnostic> {
+    let value: Value = match serde_json::from_str(content) {
+        Ok(value) => value,
+        Err(e) => return parse_error(t!("schema.json_error", error = e)),
+    };
+    let mut out = Vec::new();
+    check(&CODEX_AUTH, &value, "", &mut out);
+    out
+}
+
+/// 校验 Codex config.toml 内容（含 model_provider、profile 的引用检查）
+pub fn validate_codex_config(content: &str) -> Vec<Diagnostic> {
+    let value = match toml::from_str::<toml::Value>(content) {
+        Ok(value) => serde_json::to_value(value).unwrap_or_default(),
+        Err(e) => return parse_error(t!("schema.toml_error", error = e)),
+    };
+    let mut out = Vec::new();
+    check(&CODEX_CONFIG, &value, "", &mut out);
+
+    let providers: Vec<&str> = value
+        .get("model_providers")
+        .and_then(|v| v.as_object())
+        .map(|obj| obj.keys().map(|k| k.as_str()).collect())
+        .unwrap_or_default();
+    if let Some(provider) = value.get("model_provider").and_then(|v| v.as_str()) {
+        if !providers.contains(&provider) && !BUILTIN_PROVIDERS.contains(&provider) {
+            out.push(Diagnostic {
+                path: "model_provider".to_string(),
+                message: t!("schema.undefined_provider", name = provider),
+                severity: Severity::Error,
+                suggestion: closest(provider, providers.iter().copied())
+                    .map(|c| t!("schema.did_you_mean", value = c))
+                    .or_else(|| Some(t!("schema.add_table", table = format!("model_providers.{}", provider)))),
+            });
+        }
+    }
+
+    if let Some(profile) = value.get("profile").and_then(|v| v.as_str()) {
+        let defined = value
+            .get("profiles")
+            .and_then(|v| v.get(profile))
+            .is_some();
+        if !defined {
+            out.push(Diagnostic {
+                path: "profile".to_string(),
+                message: t!("schema.undefined_profile", name = profile),
+                severity: Severity::Error,
+                suggestion: Some(t!("schema.add_table", table = format!("profiles.{}", profile))),
+            });
+        }
+    }
+
+    out
+}
+
+/// 存在错误级诊断时拒绝写入，返回汇总的错误信息
+pub fn ensure_valid(file: &str, diagnostics: &[Diagnostic]) -> AppResult<()> {
+    let errors: Vec<String> = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .map(|d| {
+            let mut line = if d.path.is_empty() {
+                d.message.clone()
+            } else {
+                format!("{}: {}", d.path, d.message)
+            };
+            if let Some(suggestion) = &d.suggestion {
+                line.push_str(&format!("（{}）", suggestion));
+            }
+            line
+        })
+        .collect();
+
+    if errors.is_empty() {
+        return Ok(());
+    }
+    Err(AppError::invalid_input(t!("schema.validation_failed", file = file)).with_source(errors.join("\n")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_enum_value_is_a_warning() {
+        let diagnostics = validate_codex_config("model_reasoning_effort = \"hihg\"\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "model_reasoning_effort");
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].suggestion, Some(t!("schema.did_you_mean", value = "high")));
+        assert!(ensure_valid("config.toml", &diagnostics).is_ok());
+    }
+
+    #[test]
+    fn non_string_enum_value_is_an_error() {
+        let diagnostics = validate_claude_settings(r#"{"permissions": {"defaultMode": 1}}"#);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert!(ensure_valid("settings.json", &diagnostics).is_err());
+    }
+
+    #[test]
+    fn misspelled_key_suggests_known_field() {
+        let diagnostics = validate_claude_settings(r#"{"permisions": {"allow": []}}"#);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "permisions");
+        assert_eq!(diagnostics[0].severity, Severity::Warning);
+        assert_eq!(diagnostics[0].suggestion, Some(t!("schema.did_you_mean", value = "permissions")));
+    }
+
+    #[test]
+    fn quoted_bool_is_an_error_with_unquote_suggestion() {
+        let diagnostics = validate_codex_config("disable_response_storage = \"true\"\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].path, "disable_response_storage");
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].suggestion, Some(t!("schema.unquote", value = "true")));
+        assert!(ensure_valid("config.toml", &diagnostics).is_err());
+    }
+
+    #[test]
+    fn undefined_provider_and_profile_are_errors() {
+        let diagnostics = validate_codex_config(
+            "model_provider = \"88cod\"\nprofile = \"fast\"\n[model_providers.88code]\nname = \"88code\"\n",
+        );
+        let found: Vec<(&str, Severity)> = diagnostics.iter().map(|d| (d.path.as_str(), d.severity)).collect();
+        assert_eq!(found, vec![("model_provider", Severity::Error), ("profile", Severity::Error)]);
+        assert_eq!(diagnostics[0].suggestion, Some(t!("schema.did_you_mean", value = "88code")));
+        assert_eq!(diagnostics[1].suggestion, Some(t!("schema.add_table", table = "profiles.fast")));
+
+        let diagnostics = validate_codex_config("model_provider = \"custom\"\n");
+        assert_eq!(diagnostics[0].suggestion, Some(t!("schema.add_table", table = "model_providers.custom")));
+
+        let defined = "model_provider = \"openai\"\nprofile = \"fast\"\n[profiles.fast]\nmodel = \"o3\"\n";
+        assert!(validate_codex_config(defined).is_empty());
+    }
+
+    #[test]
+    fn checks_nullable_and_nested_maps() {
+        assert!(validate_codex_auth(r#"{"OPENAI_API_KEY": null}"#).is_empty());
+        let diagnostics = validate_codex_auth(r#"{"OPENAI_API_KEY": 1}"#);
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, Severity::Error);
+        assert_eq!(diagnostics[0].message, t!("schema.type_mismatch", kind = t!("schema.type.nullable", kind = t!("schema.type.string"))));
+
+        let diagnostics = validate_codex_config(
+            "[profiles.fast]\nmodel_reasoning_effort = \"hgh\"\n\
+             [model_providers.custom.http_headers]\nX-Team = 1\n\
+             [mcp_servers.docs]\ncommand = \"npx\"\nenv = { TOKEN = true }\n",
+        );
+        let found: Vec<(&str, Severity)> = diagnostics.iter().map(|d| (d.path.as_str(), d.severity)).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("mcp_servers.docs.env.TOKEN", Severity::Error),
+                ("model_providers.custom.http_headers.X-Team", Severity::Error),
+                ("profiles.fast.model_reasoning_effort", Severity::Warning),
+            ]
+        );
+
+        let diagnostics = validate_claude_settings(r#"{"env": "ANTHROPIC_MODEL=opus"}"#);
+        assert_eq!(diagnostics[0].path, "env");
+        assert_eq!(diagnostics[0].message, t!("schema.type_mismatch", kind = t!("schema.type.object")));
+    }
+}
@@ -0,0 +1,642 @@
+use crate::base_url::UrlTarget;
+use crate::config::{get_claude_config_dir, get_codex_config_dir};
+use crate::error::{AppError, AppResult};
+use crate::i18n::t;
+use crate::usage::{jsonl_files, read_jsonl};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::SystemTime;
+
+/// 会话标题的最大字符数
+const TITLE_MAX_CHARS: usize = 80;
+/// 工具输出在会话记录中保留的最大字符数
+const TOOL_OUTPUT_MAX_CHARS: usize = 2000;
+/// 搜索结果摘要中匹配位置前后保留的字符数
+const SNIPPET_CONTEXT_CHARS: usize = 60;
+const DEFAULT_SEARCH_LIMIT: usize = 100;
+/// 会话列表只读取日志开头和结尾的字节数；不超过二者之和的文件完整解析
+const HEAD_BYTES: u64 = 64 * 1024;
+const TAIL_BYTES: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MessageRole {
+    User,
+    Assistant,
+    /// 工具调用及其输出
+    Tool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionMessage {
+    pub role: MessageRole,
+    pub timestamp: Option<String>,
+    pub text: String,
+}
+
+/// 会话列表项
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionSummary {
+    pub id: String,
+    pub client: UrlTarget,
+    /// 日志文件路径，用于查看和导出
+    pub path: String,
+    pub project: String,
+    pub title: String,
+    pub model: Option<String>,
+    pub started_at: Option<String>,
+    pub updated_at: Option<String>,
+    /// 消息条数；会话列表中只扫描了大文件的开头和结尾时为空
+    pub message_count: Option<usize>,
+}
+
+/// 完整会话记录
+#[derive(Debug, Clone, Serialize)]
+pub struct Transcript {
+    #[serde(flatten)]
+    pub summary: SessionSummary,
+    pub messages: Vec<SessionMessage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit {
+    pub session: SessionSummary,
+    pub role: MessageRole,
+    pub timestamp: Option<String>,
+    /// 匹配位置附近的文本
+    pub snippet: String,
+}
+
+fn claude_sessions_dir() -> PathBuf {
+    get_claude_config_dir().join("projects")
+}
+
+fn codex_sessions_dir() -> PathBuf {
+    get_codex_config_dir().join("sessions")
+}
+
+/// 读取所有会话（可按客户端和项目过滤），按最近更新时间倒序
+fn load_all(client: Option<UrlTarget>, project: Option<&str>) -> Vec<Transcript> {
+    let mut sessions = Vec::new();
+    for (target, dir) in [
+        (UrlTarget::Claude, claude_sessions_dir()),
+        (UrlTarget::Codex, codex_sessions_dir()),
+    ] {
+        if client.is_some_and(|c| c != target) {
+            continue;
+        }
+        sessions.extend(jsonl_files(&dir).iter().filter_map(|path| parse(target, path)));
+    }
+    sessions.retain(|s| project.is_none_or(|p| s.summary.project == p));
+    sessions.sort_by(|a, b| b.summary.updated_at.cmp(&a.summary.updated_at));
+    sessions
+}
+
+/// 列出会话（不含消息内容），按最近更新时间倒序
+pub fn list(client: Option<UrlTarget>, project: Option<&str>) -> Vec<SessionSummary> {
+    let mut sessions = Vec::new();
+    for (target, dir) in [
+        (UrlTarget::Claude, claude_sessions_dir()),
+        (UrlTarget::Codex, codex_sessions_dir()),
+    ] {
+        if client.is_some_and(|c| c != target) {
+            continue;
+        }
+        sessions.extend(jsonl_files(&dir).iter().filter_map(|path| summarize(target, path)));
+    }
+    sessions.retain(|s| project.is_none_or(|p| s.project == p));
+    sessions.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+    sessions
+}
+
+struct CachedSummary {
+    modified: SystemTime,
+    len: u64,
+    summary: SessionSummary,
+}
+
+/// 按日志路径缓存的会话摘要，文件修改时间或大小变化后重新扫描
+fn summary_cache() -> &'static Mutex<HashMap<PathBuf, CachedSummary>> {
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, CachedSummary>>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 读取会话摘要：小文件完整解析，大文件只解析开头（标题、项目、开始时间）和结尾（更新时间、模型）
+fn summarize(client: UrlTarget, path: &Path) -> Option<SessionSummary> {
+    let meta = fs::metadata(path).ok()?;
+    let modified = meta.modified().ok()?;
+    let len = meta.len();
+    if let Some(cached) = summary_cache()
+        .lock()
+        .unwrap()
+        .get(path)
+        .filter(|c| c.modified == modified && c.len == len)
+    {
+        return Some(cached.summary.clone());
+    }
+
+    let summary = if len <= HEAD_BYTES + TAIL_BYTES {
+        parse(client, path)?.summary
+    } else {
+        let mut file = fs::File::open(path).ok()?;
+        let head = build(client, path, &read_entries(&mut file, 0, HEAD_BYTES)?)?.summary;
+        let tail = build(client, path, &read_entries(&mut file, len - TAIL_BYTES, TAIL_BYTES)?)?.summary;
+        SessionSummary {
+            project: if head.project.is_empty() { tail.project } else { head.project },
+            model: tail.model.or(head.model),
+            updated_at: tail.updated_at.or(head.updated_at),
+            message_count: None,
+            ..head
+        }
+    };
+
+    summary_cache().lock().unwrap().insert(
+        path.to_path_buf(),
+        CachedSummary {
+            modified,
+            len,
+            summary: summary.clone(),
+        },
+    );
+    Some(summary)
+}
+
+/// 读取文件中 [offset, offset + limit) 范围内的完整 JSONL 行；从文件中间开始时丢弃第一行的残余部分
+fn read_entries(file: &mut fs::File, offset: u64, limit: u64) -> Option<Vec<Value>> {
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    let mut bytes = Vec::new();
+    file.by_ref().take(limit).read_to_end(&mut bytes).ok()?;
+    let text = String::from_utf8_lossy(&bytes);
+    let lines = text.lines().skip(usize::from(offset > 0));
+    Some(lines.filter_map(|line| serde_json::from_str(line).ok()).collect())
+}
+
+/// 在所有会话的消息中搜索（不区分大小写），每条匹配的消息返回一个结果
+pub fn search(
+    query: &str,
+    client: Option<UrlTarget>,
+    project: Option<&str>,
+    limit: Option<usize>,
+) -> AppResult<Vec<SearchHit>> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Err(AppError::invalid_input(t!("session.empty_query")));
+    }
+    let needle = query.to_lowercase();
+    let limit = limit.unwrap_or(DEFAULT_SEARCH_LIMIT);
+
+    let mut hits = Vec::new();
+    for session in load_all(client, project) {
+        for message in &session.messages {
+            if let Some(snippet) = snippet(&message.text, &needle) {
+                hits.push(SearchHit {
+                    session: session.summary.clone(),
+                    role: message.role,
+                    timestamp: message.timestamp.clone(),
+                    snippet,
+                });
+                if hits.len() >= limit {
+                    return Ok(hits);
+                }
+            }
+        }
+    }
+    Ok(hits)
+}
+
+/// 读取单个会话；只允许读取 Claude Code 与 Codex 会话目录下的日志
+pub fn get(path: &Path) -> AppResult<Transcript> {
+    let not_found = || AppError::not_found(t!("session.not_found")).with_path(path);
+    let canonical = fs::canonicalize(path).map_err(|_| not_found())?;
+    let target = [
+        (UrlTarget::Claude, claude_sessions_dir()),
+        (UrlTarget::Codex, codex_sessions_dir()),
+    ]
+    .into_iter()
+    .find(|(_, dir)| fs::canonicalize(dir).is_ok_and(|dir| canonical.starts_with(dir)))
+    .map(|(target, _)| target)
+    .ok_or_else(|| AppError::invalid_input(t!("session.outside_dirs")).with_path(path))?;
+
+    parse(target, &canonical).ok_or_else(not_found)
+}
+
+/// 将会话导出为 Markdown
+pub fn export_markdown(path: &Path, target: &Path) -> AppResult<()> {
+    let transcript = get(path)?;
+    let summary = &transcript.summary;
+    let client = match summary.client {
+        UrlTarget::Claude => "Claude Code",
+        UrlTarget::Codex => "Codex",
+    };
+
+    let title = if summary.title.is_empty() { &summary.id } else { &summary.title };
+    let mut md = format!("# {}\n\n", title);
+    md.push_str(&format!("- {}: {}\n", t!("session.md_client"), client));
+    md.push_str(&format!("- {}: {}\n", t!("session.md_project"), summary.project));
+    md.push_str(&format!("- {}: {}\n", t!("session.md_session"), summary.id));
+    if let Some(model) = &summary.model {
+        md.push_str(&format!("- {}: {}\n", t!("session.md_model"), model));
+    }
+    if let Some(started_at) = &summary.started_at {
+        md.push_str(&format!("- {}: {}\n", t!("session.md_started"), started_at));
+    }
+
+    for message in &transcript.messages {
+        let role = match message.role {
+            MessageRole::User => t!("session.md_user"),
+            MessageRole::Assistant => t!("session.md_assistant"),
+            MessageRole::Tool => t!("session.md_tool"),
+        };
+        match &message.timestamp {
+            Some(ts) => md.push_str(&format!("\n## {} · {}\n\n", role, ts)),
+            None => md.push_str(&format!("\n## {}\n\n", role)),
+        }
+        if message.role == MessageRole::Tool {
+            md.push_str(&format!("```\n{}\n```\n", message.text.replace("```", "` ` `")));
+        } else {
+            md.push_str(message.text.trim());
+            md.push('\n');
+        }
+    }
+
+    fs::write(target, md).map_err(|e| AppError::io(t!("session.export_failed"), target, e))
+}
+
+/// 截取匹配位置附近的文本，未匹配时返回 None
+fn snippet(text: &str, needle: &str) -> Option<String> {
+    let lower = text.to_lowercase();
+    let byte_index = lower.find(needle)?;
+    // 小写转换可能改变字节长度，按字符位置换算回原文
+    let char_index = lower[..byte_index].chars().count();
+    let chars: Vec<char> = text.chars().collect();
+    let start = char_index.saturating_sub(SNIPPET_CONTEXT_CHARS);
+    let end = (char_index + needle.chars().count() + SNIPPET_CONTEXT_CHARS).min(chars.len());
+    let mut snippet: String = chars[start..end].iter().collect();
+    snippet = snippet.split_whitespace().collect::<Vec<_>>().join(" ");
+    if start > 0 {
+        snippet.insert(0, '…');
+    }
+    if end < chars.len() {
+        snippet.push('…');
+    }
+    Some(snippet)
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((index, _)) => format!("{}…", &text[..index]),
+        None => text.to_string(),
+    }
+}
+
+fn str_field<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value.get(key).and_then(|v| v.as_str())
+}
+
+/// 单个内容块中的文本：字符串或 {type: text/input_text/output_text, text}
+fn text_of(block: &Value) -> Option<&str> {
+    match block {
+        Value::String(s) => Some(s),
+        _ => match str_field(block, "type") {
+            Some("text" | "input_text" | "output_text") => str_field(block, "text"),
+            _ => None,
+        },
+    }
+}
+
+/// 提取消息内容中的文本（单个内容块或内容块数组）
+fn block_text(content: &Value) -> String {
+    match content {
+        Value::Array(blocks) => blocks.iter().filter_map(text_of).collect::<Vec<_>>().join("\n"),
+        other => text_of(other).unwrap_or("").to_string(),
+    }
+}
+
+/// 客户端注入的上下文（环境信息、项目说明等），不是用户输入
+fn is_injected(text: &str) -> bool {
+    let text = text.trim_start();
+    ["<environment_context>", "<user_instructions>", "<command-", "<local-command-", "Caveat:"]
+        .iter()
+        .any(|prefix| text.starts_with(prefix))
+}
+
+fn parse(client: UrlTarget, path: &Path) -> Option<Transcript> {
+    let (entries, _) = read_jsonl(path)?;
+    build(client, path, &entries)
+}
+
+/// 由日志条目生成会话记录；没有用户文本消息的会话标题为空
+fn build(client: UrlTarget, path: &Path, entries: &[Value]) -> Option<Transcript> {
+    let mut transcript = Transcript {
+        summary: SessionSummary {
+            id: path.file_stem()?.to_string_lossy().to_string(),
+            client,
+            path: path.to_string_lossy().to_string(),
+            project: String::new(),
+            title: String::new(),
+            model: None,
+            started_at: None,
+            updated_at: None,
+            message_count: None,
+        },
+        messages: Vec::new(),
+    };
+
+    match client {
+        UrlTarget::Claude => parse_claude(entries, &mut transcript),
+        UrlTarget::Codex => parse_codex(entries, &mut transcript),
+    }
+
+    let summary = &mut transcript.summary;
+    if summary.project.is_empty() && client == UrlTarget::Claude {
+        // 没有 cwd 字段时使用项目目录名
+        summary.project = path.parent()?.file_name()?.to_string_lossy().to_string();
+    }
+    if summary.title.is_empty() {
+        if let Some(first) = transcript.messages.iter().find(|m| m.role == MessageRole::User) {
+            summary.title = truncate(first.text.lines().next().unwrap_or("").trim(), TITLE_MAX_CHARS);
+        }
+    }
+    summary.started_at = transcript.messages.iter().find_map(|m| m.timestamp.clone());
+    summary.updated_at = transcript.messages.iter().rev().find_map(|m| m.timestamp.clone());
+    summary.message_count = Some(
+        transcript
+            .messages
+            .iter()
+            .filter(|m| m.role != MessageRole::Tool)
+            .count(),
+    );
+    Some(transcript)
+}
+
+/// Claude Code：user/assistant 行的 message.content，summary 行为会话标题
+fn parse_claude(entries: &[Value], transcript: &mut Transcript) {
+    let mut last_message_id = None;
+    for entry in entries {
+        let timestamp = str_field(entry, "timestamp").map(|s| s.to_string());
+        let kind = str_field(entry, "type");
+        if kind == Some("summary") {
+            if let Some(summary) = str_field(entry, "summary") {
+                transcript.summary.title = truncate(summary, TITLE_MAX_CHARS);
+            }
+            continue;
+        }
+        if kind != Some("user") && kind != Some("assistant") {
+            continue;
+        }
+        if entry.get("isMeta").and_then(|v| v.as_bool()) == Some(true) {
+            continue;
+        }
+        let Some(message) = entry.get("message") else {
+            continue;
+        };
+        if let Some(id) = str_field(entry, "sessionId") {
+            transcript.summary.id = id.to_string();
+        }
+        if let Some(cwd) = str_field(entry, "cwd") {
+            transcript.summary.project = cwd.to_string();
+        }
+        if let Some(model) = str_field(message, "model").filter(|m| !m.starts_with('<')) {
+            transcript.summary.model = Some(model.to_string());
+        }
+
+        // 同一条助手消息流式输出时会按内容块拆成多行，合并到上一条
+        let message_id = str_field(message, "id").map(|s| s.to_string());
+        let continues = kind == Some("assistant") && message_id.is_some() && message_id == last_message_id;
+        last_message_id = message_id;
+
+        let content = message.get("content").unwrap_or(&Value::Null);
+        let blocks: Vec<Value> = match content {
+            Value::Array(blocks) => blocks.clone(),
+            other => vec![other.clone()],
+        };
+        for block in &blocks {
+            let (role, text) = match (kind, str_field(block, "type")) {
+                (Some("user"), Some("tool_result")) => {
+                    let output = block_text(block.get("content").unwrap_or(&Value::Null));
+                    (MessageRole::Tool, truncate(&output, TOOL_OUTPUT_MAX_CHARS))
+                }
+                (_, Some("tool_use")) => {
+                    let name = str_field(block, "name").unwrap_or("tool");
+                    let input = block.get("input").map(|v| v.to_string()).unwrap_or_default();
+                    (MessageRole::Tool, truncate(&format!("{} {}", name, input), TOOL_OUTPUT_MAX_CHARS))
+                }
+                (Some("user"), _) => (MessageRole::User, text_of(block).unwrap_or("").to_string()),
+                _ => (MessageRole::Assistant, text_of(block).unwrap_or("").to_string()),
+            };
+            if text.trim().is_empty() || (role == MessageRole::User && is_injected(&text)) {
+                continue;
+            }
+            match transcript.messages.last_mut() {
+                Some(last) if continues && role == MessageRole::Assistant && last.role == MessageRole::Assistant => {
+                    last.text.push_str("\n\n");
+                    last.text.push_str(&text);
+                }
+                _ => transcript.messages.push(SessionMessage {
+                    role,
+                    timestamp: timestamp.clone(),
+                    text,
+                }),
+            }
+        }
+    }
+}
+
+/// Codex：response_item 行的 message / function_call / function_call_output，
+/// 旧版日志没有 payload 包装，直接是这些条目
+fn parse_codex(entries: &[Value], transcript: &mut Transcript) {
+    for entry in entries {
+        let timestamp = str_field(entry, "timestamp").map(|s| s.to_string());
+        let (kind, item) = match entry.get("payload") {
+            Some(payload) => (str_field(entry, "type"), payload),
+            None => (Some("response_item"), entry),
+        };
+        match kind {
+            Some("session_meta") => {
+                if let Some(id) = str_field(item, "id") {
+                    transcript.summary.id = id.to_string();
+                }
+                if let Some(cwd) = str_field(item, "cwd") {
+                    transcript.summary.project = cwd.to_string();
+                }
+            }
+            Some("turn_context") => {
+                if let Some(model) = str_field(item, "model") {
+                    transcript.summary.model = Some(model.to_string());
+                }
+            }
+            Some("response_item") => {
+                let (role, text) = match (str_field(item, "type"), str_field(item, "role")) {
+                    (Some("message"), Some("user")) => (MessageRole::User, block_text(item.get("content").unwrap_or(&Value::Null))),
+                    (Some("message"), Some("assistant")) => {
+                        (MessageRole::Assistant, block_text(item.get("content").unwrap_or(&Value::Null)))
+                    }
+                    (Some("function_call" | "custom_tool_call"), _) => {
+                        let name = str_field(item, "name").unwrap_or("tool");
+                        let input = str_field(item, "arguments").or(str_field(item, "input")).unwrap_or("");
+                        (MessageRole::Tool, truncate(&format!("{} {}", name, input), TOOL_OUTPUT_MAX_CHARS))
+                    }
+                    (Some("function_call_output" | "custom_tool_call_output"), _) => {
+                        // 新版日志的 output 是 {"output": ..., "metadata": ...} 序列化后的字符串
+                        let output = match item.get("output") {
+                            Some(Value::String(s)) => serde_json::from_str::<Value>(s)
+                                .ok()
+                                .and_then(|v| str_field(&v, "output").map(|o| o.to_string()))
+                                .unwrap_or_else(|| s.clone()),
+                            Some(other) => str_field(other, "content").map(|s| s.to_string()).unwrap_or_else(|| other.to_string()),
+                            None => String::new(),
+                        };
+                        (MessageRole::Tool, truncate(&output, TOOL_OUTPUT_MAX_CHARS))
+                    }
+                    _ => continue,
+                };
+                if text.trim().is_empty() || (role == MessageRole::User && is_injected(&text)) {
+                    continue;
+                }
+                transcript.messages.push(SessionMessage { role, timestamp, text });
+            }
+            _ => {}
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    const CLAUDE_SESSION: &str = r#"{"type":"user","timestamp":"2025-06-01T08:00:00Z","sessionId":"s1","cwd":"/work/app","message":{"role":"user","content":"<command-name>/clear</command-name>"}}
+{"type":"user","timestamp":"2025-06-01T08:00:01Z","sessionId":"s1","cwd":"/work/app","isMeta":true,"message":{"role":"user","content":"meta"}}
+{"type":"user","timestamp":"2025-06-01T08:00:02Z","sessionId":"s1","cwd":"/work/app","message":{"role":"user","content":"Fix the login bug\nin auth.rs"}}
+{"type":"assistant","timestamp":"2025-06-01T08:00:03Z","sessionId":"s1","cwd":"/work/app","message":{"id":"m1","model":"claude-sonnet-4-5","content":[{"type":"text","text":"Looking at it."}]}}
+{"type":"assistant","timestamp":"2025-06-01T08:00:04Z","sessionId":"s1","cwd":"/work/app","message":{"id":"m1","model":"claude-sonnet-4-5","content":[{"type":"text","text":"Found it."},{"type":"tool_use","name":"Read","input":{"file":"auth.rs"}}]}}
+{"type":"user","timestamp":"2025-06-01T08:00:05Z","sessionId":"s1","cwd":"/work/app","message":{"role":"user","content":[{"type":"tool_result","content":"fn login() {}"}]}}
+"#;
+
+    const CODEX_SESSION: &str = r#"{"timestamp":"2025-06-02T10:00:00Z","type":"session_meta","payload":{"id":"c1","cwd":"/work/cli"}}
+{"timestamp":"2025-06-02T10:00:01Z","type":"turn_context","payload":{"model":"gpt-5"}}
+{"timestamp":"2025-06-02T10:00:02Z","type":"response_item","payload":{"type":"message","role":"user","content":[{"type":"input_text","text":"<environment_context>cwd</environment_context>"}]}}
+{"timestamp":"2025-06-02T10:00:03Z","type":"response_item","payload":{"type":"message","role":"user","content":[{"type":"input_text","text":"List the files"}]}}
+{"timestamp":"2025-06-02T10:00:04Z","type":"response_item","payload":{"type":"function_call","name":"shell","arguments":"{\"command\":[\"ls\"]}"}}
+{"timestamp":"2025-06-02T10:00:05Z","type":"response_item","payload":{"type":"function_call_output","output":"{\"output\":\"main.rs\\n\",\"metadata\":{}}"}}
+{"timestamp":"2025-06-02T10:00:06Z","type":"response_item","payload":{"type":"message","role":"assistant","content":[{"type":"output_text","text":"There is one file."}]}}
+"#;
+
+    fn write(dir: &tempfile::TempDir, name: &str, content: &str) -> PathBuf {
+        let path = dir.path().join(name);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn parses_claude_transcript() {
+        let dir = tempfile::tempdir().unwrap();
+        let transcript = parse(UrlTarget::Claude, &write(&dir, "-work-app/s1.jsonl", CLAUDE_SESSION)).unwrap();
+        let summary = &transcript.summary;
+        assert_eq!((summary.id.as_str(), summary.project.as_str()), ("s1", "/work/app"));
+        assert_eq!(summary.title, "Fix the login bug");
+        assert_eq!(summary.model.as_deref(), Some("claude-sonnet-4-5"));
+        assert_eq!(summary.started_at.as_deref(), Some("2025-06-01T08:00:02Z"));
+        assert_eq!(summary.updated_at.as_deref(), Some("2025-06-01T08:00:05Z"));
+        assert_eq!(summary.message_count, Some(2));
+
+        // 注入的命令和 isMeta 消息被忽略，流式输出的助手消息合并为一条
+        let roles: Vec<MessageRole> = transcript.messages.iter().map(|m| m.role).collect();
+        assert_eq!(roles, [MessageRole::User, MessageRole::Assistant, MessageRole::Tool, MessageRole::Tool]);
+        assert_eq!(transcript.messages[1].text, "Looking at it.\n\nFound it.");
+        assert_eq!(transcript.messages[2].text, r#"Read {"file":"auth.rs"}"#);
+        assert_eq!(transcript.messages[3].text, "fn login() {}");
+    }
+
+    #[test]
+    fn parses_codex_transcript() {
+        let dir = tempfile::tempdir().unwrap();
+        let transcript = parse(UrlTarget::Codex, &write(&dir, "2025/06/02/rollout-1.jsonl", CODEX_SESSION)).unwrap();
+        let summary = &transcript.summary;
+        assert_eq!((summary.id.as_str(), summary.project.as_str()), ("c1", "/work/cli"));
+        assert_eq!(summary.title, "List the files");
+        assert_eq!(summary.model.as_deref(), Some("gpt-5"));
+        assert_eq!(summary.message_count, Some(2));
+
+        let texts: Vec<&str> = transcript.messages.iter().map(|m| m.text.as_str()).collect();
+        assert_eq!(texts, ["List the files", r#"shell {"command":["ls"]}"#, "main.rs\n", "There is one file."]);
+    }
+
+    #[test]
+    fn sessions_without_user_text_keep_an_empty_title() {
+        let dir = tempfile::tempdir().unwrap();
+        let content = r#"{"type":"assistant","timestamp":"2025-06-01T08:00:00Z","message":{"id":"m1","content":[{"type":"text","text":"Hello"}]}}"#;
+        let path = write(&dir, "-work-app/s2.jsonl", content);
+
+        let summary = summarize(UrlTarget::Claude, &path).unwrap();
+        assert_eq!(summary.title, "");
+        assert_eq!(summary.id, "s2");
+        // 没有 cwd 时使用项目目录名
+        assert_eq!(summary.project, "-work-app");
+        assert_eq!(summary.message_count, Some(1));
+    }
+
+    #[test]
+    fn large_sessions_are_summarized_from_head_and_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut content = String::from(
+            r#"{"type":"user","timestamp":"2025-06-01T08:00:00Z","cwd":"/work/app","message":{"role":"user","content":"First question"}}"#,
+        );
+        content.push('\n');
+        let filler = format!(
+            r#"{{"type":"assistant","timestamp":"2025-06-01T09:00:00Z","message":{{"model":"claude-sonnet-4-5","content":"{}"}}}}"#,
+            "x".repeat(1000)
+        );
+        while (content.len() as u64) < HEAD_BYTES + TAIL_BYTES {
+            content.push_str(&filler);
+            content.push('\n');
+        }
+        content.push_str(r#"{"type":"assistant","timestamp":"2025-06-03T12:00:00Z","message":{"model":"claude-opus-4-5","content":"Done"}}"#);
+        let path = write(&dir, "-work-app/big.jsonl", &content);
+
+        let summary = summarize(UrlTarget::Claude, &path).unwrap();
+        assert_eq!(summary.title, "First question");
+        assert_eq!(summary.project, "/work/app");
+        assert_eq!(summary.started_at.as_deref(), Some("2025-06-01T08:00:00Z"));
+        assert_eq!(summary.updated_at.as_deref(), Some("2025-06-03T12:00:00Z"));
+        assert_eq!(summary.model.as_deref(), Some("claude-opus-4-5"));
+        assert_eq!(summary.message_count, None);
+    }
+
+    #[test]
+    fn summaries_are_cached_until_the_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write(&dir, "-work-app/s3.jsonl", &CLAUDE_SESSION.replace("Fix the login bug", "Title A"));
+        let modified = fs::metadata(&path).unwrap().modified().unwrap();
+        assert_eq!(summarize(UrlTarget::Claude, &path).unwrap().title, "Title A");
+
+        // 修改时间和大小都未变化时使用缓存
+        fs::write(&path, CLAUDE_SESSION.replace("Fix the login bug", "Title B")).unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(modified).unwrap();
+        assert_eq!(summarize(UrlTarget::Claude, &path).unwrap().title, "Title A");
+
+        file.set_modified(modified + Duration::from_secs(10)).unwrap();
+        assert_eq!(summarize(UrlTarget::Claude, &path).unwrap().title, "Title B");
+    }
+
+    #[test]
+    fn builds_snippets_around_matches() {
+        assert_eq!(snippet("Fix the   Login\nbug", "login"), Some("Fix the Login bug".to_string()));
+        assert_eq!(snippet("nothing here", "login"), None);
+
+        let long = format!("{}needle{}", "a".repeat(100), "b".repeat(100));
+        let found = snippet(&long, "needle").unwrap();
+        assert!(found.starts_with('…') && found.ends_with('…'));
+        assert_eq!(found.chars().count(), SNIPPET_CONTEXT_CHARS * 2 + "needle".len() + 2);
+
+        assert_eq!(truncate("你好世界", 2), "你好…");
+        assert_eq!(truncate("short", 10), "short");
+    }
+}
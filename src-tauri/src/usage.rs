@@ -0,0 +1,582 @@
+use crate::base_url::UrlTarget;
+use crate::config::{get_claude_config_dir, get_codex_config_dir};
+use crate::error::{AppError, AppResult};
+use crate::i18n::t;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// 官方标价（美元 / 百万 Token）：输入、输出、缓存写入、缓存读取。
+/// 按模型名前缀匹配，更具体的前缀放在前面
+const PRICES: &[(&str, [f64; 4])] = &[
+    ("claude-opus-4-5", [5.0, 25.0, 6.25, 0.5]),
+    ("claude-opus-4", [15.0, 75.0, 18.75, 1.5]),
+    ("claude-3-opus", [15.0, 75.0, 18.75, 1.5]),
+    ("claude-sonnet-4", [3.0, 15.0, 3.75, 0.3]),
+    ("claude-3-7-sonnet", [3.0, 15.0, 3.75, 0.3]),
+    ("claude-3-5-sonnet", [3.0, 15.0, 3.75, 0.3]),
+    ("claude-haiku-4-5", [1.0, 5.0, 1.25, 0.1]),
+    ("claude-3-5-haiku", [0.8, 4.0, 1.0, 0.08]),
+    ("gpt-5-mini", [0.25, 2.0, 0.0, 0.025]),
+    ("gpt-5-nano", [0.05, 0.4, 0.0, 0.005]),
+    ("gpt-5", [1.25, 10.0, 0.0, 0.125]),
+    ("gpt-4.1", [2.0, 8.0, 0.0, 0.5]),
+    ("o4-mini", [1.1, 4.4, 0.0, 0.275]),
+    ("o3", [2.0, 8.0, 0.0, 0.5]),
+];
+
+/// Token 用量（input_tokens 不含缓存部分）
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct TokenUsage {
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+}
+
+impl TokenUsage {
+    fn add(&mut self, other: &TokenUsage) {
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.cache_creation_tokens += other.cache_creation_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+    }
+
+    /// 按官方标价估算费用（美元），未知模型返回 None
+    fn cost(&self, model: &str) -> Option<f64> {
+        let (_, [input, output, cache_creation, cache_read]) =
+            PRICES.iter().find(|(prefix, _)| model.starts_with(prefix))?;
+        let tokens = |count: u64, price: &f64| count as f64 * price / 1_000_000.0;
+        Some(
+            tokens(self.input_tokens, input)
+                + tokens(self.output_tokens, output)
+                + tokens(self.cache_creation_tokens, cache_creation)
+                + tokens(self.cache_read_tokens, cache_read),
+        )
+    }
+}
+
+/// 日志中的一次模型请求
+struct UsageRecord {
+    client: UrlTarget,
+    /// UTC 日期（YYYY-MM-DD）
+    date: String,
+    project: String,
+    model: String,
+    session: String,
+    tokens: TokenUsage,
+}
+
+/// 明细行：按日期、客户端、项目、模型汇总
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageRow {
+    pub date: String,
+    pub client: UrlTarget,
+    pub project: String,
+    pub model: String,
+    pub requests: u64,
+    #[serde(flatten)]
+    pub tokens: TokenUsage,
+    pub cost: f64,
+}
+
+/// 按单一维度汇总
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct UsageSummary {
+    pub key: String,
+    pub requests: u64,
+    pub sessions: u64,
+    #[serde(flatten)]
+    pub tokens: TokenUsage,
+    pub cost: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageReport {
+    pub total: UsageSummary,
+    pub by_model: Vec<UsageSummary>,
+    pub by_day: Vec<UsageSummary>,
+    pub by_project: Vec<UsageSummary>,
+    pub rows: Vec<UsageRow>,
+    /// 没有价格信息、未计入费用的模型
+    pub unpriced_models: Vec<String>,
+    pub files_scanned: u64,
+    /// 无法解析而跳过的行数
+    pub skipped_lines: u64,
+}
+
+#[derive(Default)]
+struct Bucket {
+    requests: u64,
+    sessions: HashSet<String>,
+    tokens: TokenUsage,
+    cost: f64,
+}
+
+impl Bucket {
+    fn add(&mut self, record: &UsageRecord, cost: f64) {
+        self.requests += 1;
+        self.sessions.insert(format!("{:?}/{}", record.client, record.session));
+        self.tokens.add(&record.tokens);
+        self.cost += cost;
+    }
+
+    fn summary(&self, key: String) -> UsageSummary {
+        UsageSummary {
+            key,
+            requests: self.requests,
+            sessions: self.sessions.len() as u64,
+            tokens: self.tokens,
+            cost: self.cost,
+        }
+    }
+}
+
+/// 扫描 Claude Code 与 Codex 的会话日志并汇总用量（完全离线）。
+/// since、until 为包含边界的 UTC 日期（YYYY-MM-DD），未指定时不限制
+pub fn collect(since: Option<&str>, until: Option<&str>) -> AppResult<UsageReport> {
+    for date in [since, until].into_iter().flatten() {
+        if !is_date(date) {
+            return Err(AppError::invalid_input(t!("usage.invalid_date", date = date)));
+        }
+    }
+
+    let mut scan = Scan::default();
+    for file in jsonl_files(&get_claude_config_dir().join("projects")) {
+        scan.claude_file(&file);
+    }
+    for file in jsonl_files(&get_codex_config_dir().join("sessions")) {
+        scan.codex_file(&file);
+    }
+    Ok(report(scan, since, until))
+}
+
+/// 按日期范围过滤扫描结果并按模型、日期、项目汇总
+fn report(scan: Scan, since: Option<&str>, until: Option<&str>) -> UsageReport {
+    let in_range = |date: &str| since.is_none_or(|s| date >= s) && until.is_none_or(|u| date <= u);
+    let mut total = Bucket::default();
+    let mut by_model: BTreeMap<String, Bucket> = BTreeMap::new();
+    let mut by_day: BTreeMap<String, Bucket> = BTreeMap::new();
+    let mut by_project: BTreeMap<String, Bucket> = BTreeMap::new();
+    let mut rows: BTreeMap<(String, String, String, String), (UrlTarget, Bucket)> = BTreeMap::new();
+    let mut unpriced = std::collections::BTreeSet::new();
+
+    for record in scan.records.iter().filter(|r| in_range(&r.date)) {
+        let cost = record.tokens.cost(&record.model).unwrap_or_else(|| {
+            unpriced.insert(record.model.clone());
+            0.0
+        });
+        total.add(record, cost);
+        by_model.entry(record.model.clone()).or_default().add(record, cost);
+        by_day.entry(record.date.clone()).or_default().add(record, cost);
+        by_project.entry(record.project.clone()).or_default().add(record, cost);
+        let key = (
+            record.date.clone(),
+            format!("{:?}", record.client),
+            record.project.clone(),
+            record.model.clone(),
+        );
+        rows.entry(key)
+            .or_insert_with(|| (record.client, Bucket::default()))
+            .1
+            .add(record, cost);
+    }
+
+    // 模型和项目按费用从高到低，日期按时间顺序
+    let by_cost = |map: BTreeMap<String, Bucket>| {
+        let mut list: Vec<UsageSummary> = map.into_iter().map(|(key, b)| b.summary(key)).collect();
+        list.sort_by(|a, b| b.cost.total_cmp(&a.cost).then(b.requests.cmp(&a.requests)));
+        list
+    };
+
+    UsageReport {
+        total: total.summary(String::new()),
+        by_model: by_cost(by_model),
+        by_day: by_day.into_iter().map(|(key, b)| b.summary(key)).collect(),
+        by_project: by_cost(by_project),
+        rows: rows
+            .into_iter()
+            .map(|((date, _, project, model), (client, b))| UsageRow {
+                date,
+                client,
+                project,
+                model,
+                requests: b.requests,
+                tokens: b.tokens,
+                cost: b.cost,
+            })
+            .collect(),
+        unpriced_models: unpriced.into_iter().collect(),
+        files_scanned: scan.files,
+        skipped_lines: scan.skipped,
+    }
+}
+
+/// 将明细行导出为 CSV
+pub fn export_csv(path: &Path, since: Option<&str>, until: Option<&str>) -> AppResult<usize> {
+    let report = collect(since, until)?;
+    let mut csv = String::from(
+        "date,client,project,model,requests,input_tokens,output_tokens,cache_creation_tokens,cache_read_tokens,cost_usd\n",
+    );
+    for row in &report.rows {
+        let client = match row.client {
+            UrlTarget::Claude => "claude",
+            UrlTarget::Codex => "codex",
+        };
+        csv.push_str(&format!(
+            "{},{},{},{},{},{},{},{},{},{:.6}\n",
+            row.date,
+            client,
+            csv_field(&row.project),
+            csv_field(&row.model),
+            row.requests,
+            row.tokens.input_tokens,
+            row.tokens.output_tokens,
+            row.tokens.cache_creation_tokens,
+            row.tokens.cache_read_tokens,
+            row.cost
+        ));
+    }
+    fs::write(path, csv).map_err(|e| AppError::io(t!("usage.export_failed"), path, e))?;
+    Ok(report.rows.len())
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn is_date(value: &str) -> bool {
+    let bytes = value.as_bytes();
+    bytes.len() == 10
+        && bytes.iter().enumerate().all(|(i, b)| match i {
+            4 | 7 => *b == b'-',
+            _ => b.is_ascii_digit(),
+        })
+}
+
+/// 逐行解析 JSONL 文件，返回解析成功的行和跳过的行数；文件无法打开时返回 None
+pub(crate) fn read_jsonl(path: &Path) -> Option<(Vec<Value>, u64)> {
+    let file = fs::File::open(path).ok()?;
+    let mut values = Vec::new();
+    let mut skipped = 0;
+    for line in BufReader::new(file).lines() {
+        let Ok(line) = line else {
+            skipped += 1;
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Value>(&line) {
+            Ok(value) => values.push(value),
+            Err(_) => skipped += 1,
+        }
+    }
+    Some((values, skipped))
+}
+
+/// 递归列出目录下的 .jsonl 文件，目录不存在时返回空列表
+pub(crate) fn jsonl_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                pending.push(path);
+            } else if path.extension().is_some_and(|ext| ext == "jsonl") {
+                files.push(path);
+            }
+        }
+    }
+    files.sort();
+    files
+}
+
+fn str_field<'a>(value: &'a Value, key: &str) -> Option<&'a str> {
+    value.get(key).and_then(|v| v.as_str())
+}
+
+fn u64_field(value: &Value, key: &str) -> u64 {
+    value.get(key).and_then(|v| v.as_u64()).unwrap_or(0)
+}
+
+#[derive(Default)]
+struct Scan {
+    records: Vec<UsageRecord>,
+    /// Claude Code 恢复会话时会把之前的消息复制到新文件，按消息 ID 去重
+    seen_messages: HashSet<String>,
+    files: u64,
+    skipped: u64,
+}
+
+impl Scan {
+    /// 逐行读取 JSONL，无法解析的行计入 skipped
+    fn lines(&mut self, path: &Path) -> Vec<Value> {
+        let Some((values, skipped)) = read_jsonl(path) else {
+            return Vec::new();
+        };
+        self.files += 1;
+        self.skipped += skipped;
+        values
+    }
+
+    /// Claude Code：~/.claude/projects/<项目>/<会话>.jsonl，assistant 消息带有 message.usage
+    fn claude_file(&mut self, path: &Path) {
+        let folder = path
+            .parent()
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let file_session = path
+            .file_stem()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        for entry in self.lines(path) {
+            if str_field(&entry, "type") != Some("assistant") {
+                continue;
+            }
+            let Some(message) = entry.get("message") else {
+                continue;
+            };
+            let (Some(usage), Some(model)) = (message.get("usage"), str_field(message, "model")) else {
+                continue;
+            };
+            // 客户端本地生成的消息（如中断提示）不计入
+            if model.starts_with('<') {
+                continue;
+            }
+            // 同一条消息流式输出时会写成多行，按消息 ID + 请求 ID 去重
+            if let Some(id) = str_field(message, "id") {
+                let key = format!("{}/{}", id, str_field(&entry, "requestId").unwrap_or(""));
+                if !self.seen_messages.insert(key) {
+                    continue;
+                }
+            }
+            let Some(date) = str_field(&entry, "timestamp").and_then(|ts| ts.get(..10)) else {
+                self.skipped += 1;
+                continue;
+            };
+
+            self.records.push(UsageRecord {
+                client: UrlTarget::Claude,
+                date: date.to_string(),
+                project: str_field(&entry, "cwd").unwrap_or(&folder).to_string(),
+                model: model.to_string(),
+                session: str_field(&entry, "sessionId").unwrap_or(&file_session).to_string(),
+                tokens: TokenUsage {
+                    input_tokens: u64_field(usage, "input_tokens"),
+                    output_tokens: u64_field(usage, "output_tokens"),
+                    cache_creation_tokens: u64_field(usage, "cache_creation_input_tokens"),
+                    cache_read_tokens: u64_field(usage, "cache_read_input_tokens"),
+                },
+            });
+        }
+    }
+
+    /// Codex：~/.codex/sessions/YYYY/MM/DD/rollout-*.jsonl，token_count 事件带有本次请求的用量，
+    /// 模型和工作目录来自 session_meta / turn_context
+    fn codex_file(&mut self, path: &Path) {
+        let mut session = path
+            .file_stem()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut project = String::new();
+        let mut model = "unknown".to_string();
+        let mut last_total: Option<Value> = None;
+
+        for entry in self.lines(path) {
+            let payload = entry.get("payload").unwrap_or(&Value::Null);
+            match str_field(&entry, "type") {
+                Some("session_meta") => {
+                    if let Some(id) = str_field(payload, "id") {
+                        session = id.to_string();
+                    }
+                    if let Some(cwd) = str_field(payload, "cwd") {
+                        project = cwd.to_string();
+                    }
+                }
+                Some("turn_context") => {
+                    if let Some(m) = str_field(payload, "model") {
+                        model = m.to_string();
+                    }
+                    if let Some(cwd) = str_field(payload, "cwd") {
+                        project = cwd.to_string();
+                    }
+                }
+                Some("event_msg") if str_field(payload, "type") == Some("token_count") => {
+                    let Some(info) = payload.get("info").filter(|i| !i.is_null()) else {
+                        continue;
+                    };
+                    // 累计用量没有变化的重复事件不计入
+                    let total = info.get("total_token_usage").cloned();
+                    if total.is_some() && total == last_total {
+                        continue;
+                    }
+                    last_total = total;
+                    let Some(last) = info.get("last_token_usage") else {
+                        continue;
+                    };
+                    let Some(date) = str_field(&entry, "timestamp").and_then(|ts| ts.get(..10)) else {
+                        self.skipped += 1;
+                        continue;
+                    };
+
+                    // Codex 的 input_tokens 包含缓存命中部分
+                    let cached = u64_field(last, "cached_input_tokens");
+                    self.records.push(UsageRecord {
+                        client: UrlTarget::Codex,
+                        date: date.to_string(),
+                        project: project.clone(),
+                        model: model.clone(),
+                        session: session.clone(),
+                        tokens: TokenUsage {
+                            input_tokens: u64_field(last, "input_tokens").saturating_sub(cached),
+                            output_tokens: u64_field(last, "output_tokens"),
+                            cache_creation_tokens: 0,
+                            cache_read_tokens: cached,
+                        },
+                    });
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLAUDE_SESSION: &str = r#"{"type":"user","timestamp":"2025-06-01T08:00:00.000Z","message":{"role":"user","content":"hi"}}
+{"type":"assistant","timestamp":"2025-06-01T08:00:01.000Z","sessionId":"s1","cwd":"/work/app","requestId":"r1","message":{"id":"m1","model":"claude-sonnet-4-5","usage":{"input_tokens":1000,"output_tokens":200,"cache_creation_input_tokens":100,"cache_read_input_tokens":5000}}}
+{"type":"assistant","timestamp":"2025-06-01T08:00:02.000Z","sessionId":"s1","cwd":"/work/app","requestId":"r1","message":{"id":"m1","model":"claude-sonnet-4-5","usage":{"input_tokens":1000,"output_tokens":200,"cache_creation_input_tokens":100,"cache_read_input_tokens":5000}}}
+not json
+{"type":"assistant","timestamp":"2025-06-02T09:00:00.000Z","sessionId":"s1","cwd":"/work/app","requestId":"r2","message":{"id":"m2","model":"<synthetic>","usage":{"input_tokens":0,"output_tokens":0}}}
+{"type":"assistant","timestamp":"2025-06-02T09:00:01.000Z","sessionId":"s1","cwd":"/work/app","requestId":"r3","message":{"id":"m3","model":"custom-model","usage":{"input_tokens":10,"output_tokens":5}}}
+{"type":"assistant","sessionId":"s1","requestId":"r4","message":{"id":"m4","model":"claude-sonnet-4-5","usage":{"input_tokens":1}}}
+"#;
+
+    const CODEX_SESSION: &str = r#"{"timestamp":"2025-06-01T10:00:00.000Z","type":"session_meta","payload":{"id":"c1","cwd":"/work/cli"}}
+{"timestamp":"2025-06-01T10:00:01.000Z","type":"turn_context","payload":{"model":"gpt-5","cwd":"/work/cli"}}
+{"timestamp":"2025-06-01T10:00:02.000Z","type":"event_msg","payload":{"type":"token_count","info":null}}
+{"timestamp":"2025-06-01T10:00:03.000Z","type":"event_msg","payload":{"type":"token_count","info":{"total_token_usage":{"input_tokens":3000},"last_token_usage":{"input_tokens":3000,"cached_input_tokens":1000,"output_tokens":400}}}}
+{"timestamp":"2025-06-01T10:00:04.000Z","type":"event_msg","payload":{"type":"token_count","info":{"total_token_usage":{"input_tokens":3000},"last_token_usage":{"input_tokens":3000,"cached_input_tokens":1000,"output_tokens":400}}}}
+{"timestamp":"2025-06-03T10:00:05.000Z","type":"event_msg","payload":{"type":"token_count","info":{"total_token_usage":{"input_tokens":5000},"last_token_usage":{"input_tokens":2000,"output_tokens":100}}}}
+"#;
+
+    fn scan(files: &[(&str, &str)]) -> Scan {
+        let dir = tempfile::tempdir().unwrap();
+        let mut scan = Scan::default();
+        for (name, content) in files {
+            let path = dir.path().join(name);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, content).unwrap();
+            if name.starts_with("claude") {
+                scan.claude_file(&path);
+            } else {
+                scan.codex_file(&path);
+            }
+        }
+        scan
+    }
+
+    #[test]
+    fn parses_claude_sessions() {
+        let scan = scan(&[("claude/-work-app/s1.jsonl", CLAUDE_SESSION)]);
+        // 重复的流式行、本地消息被忽略；无法解析的行和缺少时间戳的记录计入 skipped
+        assert_eq!(scan.records.len(), 2);
+        assert_eq!((scan.files, scan.skipped), (1, 2));
+
+        let first = &scan.records[0];
+        assert_eq!(first.client, UrlTarget::Claude);
+        assert_eq!((first.date.as_str(), first.project.as_str()), ("2025-06-01", "/work/app"));
+        assert_eq!((first.model.as_str(), first.session.as_str()), ("claude-sonnet-4-5", "s1"));
+        assert_eq!(first.tokens.input_tokens, 1000);
+        assert_eq!(first.tokens.cache_creation_tokens, 100);
+        assert_eq!(first.tokens.cache_read_tokens, 5000);
+    }
+
+    #[test]
+    fn resumed_claude_sessions_are_not_counted_twice() {
+        let scan = scan(&[
+            ("claude/-work-app/s1.jsonl", CLAUDE_SESSION),
+            ("claude/-work-app/s2.jsonl", CLAUDE_SESSION),
+        ]);
+        assert_eq!(scan.records.len(), 2);
+        assert_eq!(scan.files, 2);
+    }
+
+    #[test]
+    fn parses_codex_sessions() {
+        let scan = scan(&[("codex/2025/06/01/rollout-1.jsonl", CODEX_SESSION)]);
+        assert_eq!(scan.records.len(), 2);
+
+        let first = &scan.records[0];
+        assert_eq!(first.client, UrlTarget::Codex);
+        assert_eq!((first.model.as_str(), first.project.as_str(), first.session.as_str()), ("gpt-5", "/work/cli", "c1"));
+        // input_tokens 不含缓存命中部分
+        assert_eq!(first.tokens.input_tokens, 2000);
+        assert_eq!(first.tokens.cache_read_tokens, 1000);
+        assert_eq!(first.tokens.output_tokens, 400);
+        assert_eq!(scan.records[1].date, "2025-06-03");
+    }
+
+    #[test]
+    fn summarizes_and_filters_by_date() {
+        let files = [
+            ("claude/-work-app/s1.jsonl", CLAUDE_SESSION),
+            ("codex/2025/06/01/rollout-1.jsonl", CODEX_SESSION),
+        ];
+        let all = report(scan(&files), None, None);
+        assert_eq!(all.total.requests, 4);
+        assert_eq!(all.total.sessions, 2);
+        assert_eq!(all.by_day.iter().map(|d| d.key.as_str()).collect::<Vec<_>>(), ["2025-06-01", "2025-06-02", "2025-06-03"]);
+        assert_eq!(all.unpriced_models, ["custom-model"]);
+        assert_eq!(all.rows.len(), 4);
+        // 按费用从高到低
+        assert!(all.by_model.windows(2).all(|w| w[0].cost >= w[1].cost));
+
+        let day = report(scan(&files), Some("2025-06-01"), Some("2025-06-01"));
+        assert_eq!(day.total.requests, 2);
+        assert_eq!(day.by_project.len(), 2);
+        assert!(day.unpriced_models.is_empty());
+    }
+
+    #[test]
+    fn estimates_cost_by_model_prefix() {
+        let tokens = TokenUsage {
+            input_tokens: 1_000_000,
+            output_tokens: 1_000_000,
+            cache_creation_tokens: 0,
+            cache_read_tokens: 1_000_000,
+        };
+        assert_eq!(tokens.cost("claude-sonnet-4-5-20250929"), Some(18.3));
+        assert_eq!(tokens.cost("claude-opus-4-5"), Some(30.5));
+        assert_eq!(tokens.cost("gpt-5-mini"), Some(2.275));
+        assert_eq!(tokens.cost("unknown"), None);
+    }
+
+    #[test]
+    fn validates_dates_and_escapes_csv() {
+        assert!(is_date("2025-06-01"));
+        assert!(!is_date("2025-6-1"));
+        assert!(!is_date("2025/06/01"));
+        assert!(collect(Some("yesterday"), None).is_err());
+
+        assert_eq!(csv_field("/work/app"), "/work/app");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    }
+}
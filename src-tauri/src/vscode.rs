@@ -1,171 +1,71 @@
-use std::path::PathBuf;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use serde::Serialize;
 use serde_json::{json, Value};
 use std::fs;
 use crate::config::{get_claude_config_dir, get_claude_settings_path, write_json_file};
+use crate::error::{AppError, AppResult};
+use crate::i18n::t;
+use crate::jsonc;
 
-/// 移除 JSON/JSONC 中的注释（简单实现）
-/// 处理单行注释 // 和多行注释 /* */
-fn strip_json_comments(content: &str) -> String {
-    let mut result = String::new();
-    let mut chars = content.chars().peekable();
-    let mut in_string = false;
-    let mut escape_next = false;
-
-    while let Some(ch) = chars.next() {
-        if escape_next {
-            result.push(ch);
-            escape_next = false;
-            continue;
-        }
-
-        if ch == '\\' && in_string {
-            result.push(ch);
-            escape_next = true;
-            continue;
-        }
-
-        if ch == '"' {
-            in_string = !in_string;
-            result.push(ch);
-            continue;
-        }
-
-        if in_string {
-            result.push(ch);
-            continue;
-        }
-
-        // 处理注释（仅在非字符串中）
-        if ch == '/' {
-            if let Some(&next_ch) = chars.peek() {
-                if next_ch == '/' {
-                    // 单行注释，跳到行尾
-                    chars.next(); // 消费第二个 /
-                    for c in chars.by_ref() {
-                        if c == '\n' {
-                            result.push('\n');
-                            break;
-                        }
-                    }
-                    continue;
-                } else if next_ch == '*' {
-                    // 多行注释，跳到 */
-                    chars.next(); // 消费 *
-                    let mut found_end = false;
-                    while let Some(c) = chars.next() {
-                        if c == '*' {
-                            if let Some(&n) = chars.peek() {
-                                if n == '/' {
-                                    chars.next(); // 消费 /
-                                    found_end = true;
-                                    break;
-                                }
-                            }
-                        }
-                    }
-                    if found_end {
-                        result.push(' '); // 用空格替代注释
-                    }
-                    continue;
-                }
-            }
-        }
-
-        result.push(ch);
-    }
-
-    result
+/// 枚举可能的 VS Code 系编辑器配置目录名称（目录名, 显示名称）
+fn vscode_product_dirs() -> Vec<(&'static str, &'static str)> {
+    vec![
+        ("Code", "VS Code"),                    // VS Code Stable
+        ("Code - Insiders", "VS Code Insiders"), // VS Code Insiders
+        ("VSCodium", "VSCodium"),                // VSCodium
+        ("Code - OSS", "Code - OSS"),            // OSS 发行版
+        ("Cursor", "Cursor"),                    // Cursor
+        ("Windsurf", "Windsurf"),                // Windsurf
+        ("Trae", "Trae"),                        // Trae
+    ]
 }
 
-/// 修复 JSON 中的尾部逗号（对象和数组最后一个元素后的逗号）
-fn fix_json_trailing_commas(content: &str) -> String {
-    let mut result = String::new();
-    let mut chars = content.chars().peekable();
-    let mut in_string = false;
-    let mut escape_next = false;
-
-    while let Some(ch) = chars.next() {
-        if escape_next {
-            result.push(ch);
-            escape_next = false;
-            continue;
-        }
-
-        if ch == '\\' && in_string {
-            result.push(ch);
-            escape_next = true;
-            continue;
-        }
-
-        if ch == '"' {
-            in_string = !in_string;
-            result.push(ch);
-            continue;
-        }
-
-        if in_string {
-            result.push(ch);
-            continue;
-        }
-
-        // 检查是否是尾部逗号（逗号后面只有空白字符，然后是 } 或 ]）
-        if ch == ',' {
-            result.push(ch);
-
-            // 向前看，检查后面是否只有空白和 } 或 ]
-            let mut temp_chars = chars.clone();
-            let mut found_closing = false;
-            let mut is_trailing = false;
-
-            while let Some(&next) = temp_chars.peek() {
-                if next.is_whitespace() {
-                    temp_chars.next();
-                    continue;
-                }
-                if next == '}' || next == ']' {
-                    found_closing = true;
-                    is_trailing = true;
-                }
-                break;
-            }
-
-            // 如果是尾部逗号，从result中移除最后一个字符（逗号）
-            if is_trailing && found_closing {
-                result.pop();
-            }
-        } else {
-            result.push(ch);
-        }
-    }
+/// 检测到的编辑器安装（一个 settings.json 对应一个安装）
+#[derive(Debug, Clone, Serialize)]
+pub struct EditorInstallation {
+    /// 显示名称，例如 "VS Code (Flatpak)"
+    pub name: String,
+    pub settings_path: String,
+    /// 用户配置目录是否存在（视为已安装）
+    pub installed: bool,
+    pub settings_exists: bool,
+    /// 除默认配置外的用户配置文件（Profiles）
+    pub profiles: Vec<EditorProfile>,
+}
 
-    result
+/// VS Code 用户配置文件（Profile），设置位于 User/profiles/<id>/settings.json
+#[derive(Debug, Clone, Serialize)]
+pub struct EditorProfile {
+    pub id: String,
+    /// 显示名称，来自 User/globalStorage/storage.json，缺失时使用 id
+    pub name: String,
+    pub settings_path: String,
+    pub settings_exists: bool,
 }
 
-/// 枚举可能的 VS Code 发行版配置目录名称
-fn vscode_product_dirs() -> Vec<&'static str> {
-    vec![
-        "Code",            // VS Code Stable
-        "Code - Insiders", // VS Code Insiders
-        "VSCodium",        // VSCodium
-        "Code - OSS",      // OSS 发行版
-    ]
+/// 获取所有候选编辑器安装（显示名称, settings.json 路径），按优先级排序
+fn candidate_installations() -> Vec<(String, PathBuf)> {
+    candidate_installations_in(dirs::home_dir().as_deref(), dirs::config_dir().as_deref())
 }
 
-/// 获取 VS Code 用户 settings.json 的候选路径列表（按优先级排序）
-pub fn candidate_settings_paths() -> Vec<PathBuf> {
-    let mut paths = Vec::new();
+/// 基于给定的主目录与系统配置目录（Linux 的 ~/.config、Windows 的 %APPDATA%）枚举候选安装
+#[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(unused_variables))]
+fn candidate_installations_in(home: Option<&Path>, config: Option<&Path>) -> Vec<(String, PathBuf)> {
+    let mut candidates = Vec::new();
 
     #[cfg(target_os = "macos")]
     {
-        if let Some(home) = dirs::home_dir() {
-            for prod in vscode_product_dirs() {
-                paths.push(
+        if let Some(home) = home {
+            for (prod, name) in vscode_product_dirs() {
+                candidates.push((
+                    name.to_string(),
                     home.join("Library")
                         .join("Application Support")
                         .join(prod)
                         .join("User")
                         .join("settings.json"),
-                );
+                ));
             }
         }
     }
@@ -173,17 +73,15 @@ pub fn candidate_settings_paths() -> Vec<PathBuf> {
     #[cfg(target_os = "windows")]
     {
         // Windows: %APPDATA%\Code\User\settings.json
-        if let Some(roaming) = dirs::config_dir() {
-            for prod in vscode_product_dirs() {
-                paths.push(roaming.join(prod).join("User").join("settings.json"));
-            }
-        } else {
-            // 备用方案：尝试从环境变量直接读取
-            if let Ok(appdata) = std::env::var("APPDATA") {
-                let appdata_path = PathBuf::from(appdata);
-                for prod in vscode_product_dirs() {
-                    paths.push(appdata_path.join(prod).join("User").join("settings.json"));
-                }
+        let roaming = config
+            .map(Path::to_path_buf)
+            .or_else(|| std::env::var("APPDATA").ok().map(PathBuf::from)); // 备用方案：从环境变量读取
+        if let Some(roaming) = roaming {
+            for (prod, name) in vscode_product_dirs() {
+                candidates.push((
+                    name.to_string(),
+                    roaming.join(prod).join("User").join("settings.json"),
+                ));
             }
         }
     }
@@ -191,43 +89,195 @@ pub fn candidate_settings_paths() -> Vec<PathBuf> {
     #[cfg(all(unix, not(target_os = "macos")))]
     {
         // Linux: ~/.config/Code/User/settings.json
-        if let Some(config) = dirs::config_dir() {
-            for prod in vscode_product_dirs() {
-                paths.push(config.join(prod).join("User").join("settings.json"));
+        if let Some(config) = config {
+            for (prod, name) in vscode_product_dirs() {
+                candidates.push((
+                    name.to_string(),
+                    config.join(prod).join("User").join("settings.json"),
+                ));
+            }
+        }
+
+        if let Some(home) = home {
+            // Flatpak: ~/.var/app/<app-id>/config/<产品目录>/User/settings.json
+            let flatpak_apps = [
+                ("com.visualstudio.code", "Code", "VS Code (Flatpak)"),
+                ("com.visualstudio.code.insiders", "Code - Insiders", "VS Code Insiders (Flatpak)"),
+                ("com.vscodium.codium", "VSCodium", "VSCodium (Flatpak)"),
+            ];
+            for (app_id, prod, name) in flatpak_apps {
+                candidates.push((
+                    name.to_string(),
+                    home.join(".var")
+                        .join("app")
+                        .join(app_id)
+                        .join("config")
+                        .join(prod)
+                        .join("User")
+                        .join("settings.json"),
+                ));
+            }
+
+            // Snap: ~/snap/<snap 名称>/current/.config/<产品目录>/User/settings.json
+            let snap_apps = [
+                ("code", "Code", "VS Code (Snap)"),
+                ("code-insiders", "Code - Insiders", "VS Code Insiders (Snap)"),
+                ("codium", "VSCodium", "VSCodium (Snap)"),
+            ];
+            for (snap, prod, name) in snap_apps {
+                candidates.push((
+                    name.to_string(),
+                    home.join("snap")
+                        .join(snap)
+                        .join("current")
+                        .join(".config")
+                        .join(prod)
+                        .join("User")
+                        .join("settings.json"),
+                ));
             }
         }
     }
 
-    paths
+    #[cfg(unix)]
+    {
+        // 远程开发服务端（Remote-SSH / WSL / Dev Containers）的机器级配置
+        if let Some(home) = home {
+            let server_dirs = [
+                (".vscode-server", "VS Code Server"),
+                (".cursor-server", "Cursor Server"),
+                (".windsurf-server", "Windsurf Server"),
+            ];
+            for (dir, name) in server_dirs {
+                candidates.push((
+                    name.to_string(),
+                    home.join(dir)
+                        .join("data")
+                        .join("Machine")
+                        .join("settings.json"),
+                ));
+            }
+        }
+    }
+
+    candidates
+}
+
+/// 获取 VS Code 用户 settings.json 的候选路径列表（按优先级排序）
+pub fn candidate_settings_paths() -> Vec<PathBuf> {
+    candidate_installations()
+        .into_iter()
+        .map(|(_, path)| path)
+        .collect()
+}
+
+/// 读取 storage.json 中记录的 Profile 名称（id -> 名称）
+fn read_profile_names(user_dir: &Path) -> HashMap<String, String> {
+    let mut names = HashMap::new();
+    let storage_path = user_dir.join("globalStorage").join("storage.json");
+
+    let Ok(content) = fs::read_to_string(&storage_path) else {
+        return names;
+    };
+    let Ok(storage) = serde_json::from_str::<Value>(&content) else {
+        log::warn!("解析 VSCode storage.json 失败: {:?}", storage_path);
+        return names;
+    };
+
+    if let Some(profiles) = storage.get("userDataProfiles").and_then(|v| v.as_array()) {
+        for profile in profiles {
+            let location = profile.get("location").and_then(|v| v.as_str());
+            let name = profile.get("name").and_then(|v| v.as_str());
+            if let (Some(location), Some(name)) = (location, name) {
+                names.insert(location.to_string(), name.to_string());
+            }
+        }
+    }
+
+    names
+}
+
+/// 枚举 User 目录下的所有 Profile
+pub fn detect_profiles(user_dir: &Path) -> Vec<EditorProfile> {
+    let mut profiles = Vec::new();
+    let Ok(entries) = fs::read_dir(user_dir.join("profiles")) else {
+        return profiles;
+    };
+    let names = read_profile_names(user_dir);
+
+    for entry in entries.flatten() {
+        let dir = entry.path();
+        if !dir.is_dir() {
+            continue;
+        }
+        let id = entry.file_name().to_string_lossy().to_string();
+        let settings_path = dir.join("settings.json");
+        profiles.push(EditorProfile {
+            name: names.get(&id).cloned().unwrap_or_else(|| id.clone()),
+            id,
+            settings_exists: settings_path.is_file(),
+            settings_path: settings_path.to_string_lossy().to_string(),
+        });
+    }
+
+    profiles.sort_by(|a, b| a.name.cmp(&b.name));
+    profiles
+}
+
+/// 检测所有候选编辑器安装及其配置文件状态
+pub fn detect_installations() -> Vec<EditorInstallation> {
+    inspect_installations(candidate_installations())
 }
 
-/// 返回第一个存在的 settings.json 路径
-pub fn find_existing_settings() -> Option<PathBuf> {
-    for p in candidate_settings_paths() {
-        if let Ok(meta) = std::fs::metadata(&p) {
-            if meta.is_file() {
-                return Some(p);
+fn inspect_installations(candidates: Vec<(String, PathBuf)>) -> Vec<EditorInstallation> {
+    candidates
+        .into_iter()
+        .map(|(name, path)| {
+            // 只有 User 目录下的配置支持 Profile（远程服务端的 Machine 配置没有）
+            let profiles = match path.parent() {
+                Some(dir) if dir.ends_with("User") => detect_profiles(dir),
+                _ => Vec::new(),
+            };
+            EditorInstallation {
+                name,
+                installed: path.parent().is_some_and(|dir| dir.is_dir()),
+                settings_exists: path.is_file(),
+                settings_path: path.to_string_lossy().to_string(),
+                profiles,
             }
+        })
+        .collect()
+}
+
+/// 所有允许写入的 settings.json：候选安装的默认配置及其 Profile 配置
+fn allowed_settings_paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    for inst in detect_installations() {
+        paths.push(PathBuf::from(inst.settings_path));
+        for profile in inst.profiles {
+            paths.push(PathBuf::from(profile.settings_path));
         }
     }
-    None
+    paths
 }
 
 /// 配置 VSCode Claude 扩展
 /// 功能：在 ~/.claude/config.json 中写入 {"primaryApiKey": "key"}
-pub fn configure_vscode_claude(api_key: String, _base_url: String) -> Result<String, String> {
+pub fn configure_vscode_claude(api_key: String, _base_url: String) -> AppResult<String> {
     // 1. 检查客户端配置是否存在
     let settings_path = get_claude_settings_path();
     if !settings_path.exists() {
-        return Err("请先配置 Claude Code 客户端！需要先完成客户端配置才能配置 VSCode。".to_string());
+        return Err(AppError::not_found(t!("vscode.claude_required"))
+            .with_path(&settings_path));
     }
 
     // 2. 获取 ~/.claude/config.json 路径
     let config_dir = get_claude_config_dir();
     let config_path = config_dir.join("config.json");
 
-    // 首次配置前创建备份
+    // 首次配置前创建备份；该文件会被整体替换，因此记录整个文件的原内容
     crate::config::create_backup_if_not_exists(&config_path)?;
+    crate::changes::remember(crate::changes::ManagedFormat::Json, &config_path, &[&[]]);
 
     // 3. 创建配置内容
     let config_content = json!({
@@ -237,71 +287,254 @@ pub fn configure_vscode_claude(api_key: String, _base_url: String) -> Result<Str
     // 4. 写入配置文件
     write_json_file(&config_path, &config_content)?;
 
-    Ok(format!(
-        "VSCode 配置成功！已写入: {}\n请重新加载 VSCode 窗口以使配置生效。",
-        config_path.display()
-    ))
+    Ok(t!("vscode.configured", path = config_path.display()))
+}
+
+/// 单个目标的配置结果
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum TargetOutcome {
+    /// 已修改现有文件
+    Updated,
+    /// 新建了配置文件
+    Created,
+    /// 未写入（例如配置已是最新）
+    Skipped { reason: String },
+    /// 现有文件无法解析
+    ParseError {
+        message: String,
+        line: usize,
+        column: usize,
+    },
+    /// 读取或写入失败
+    Failed { message: String },
+}
+
+/// 单个 settings.json 的配置结果
+#[derive(Debug, Clone, Serialize)]
+pub struct TargetResult {
+    pub path: String,
+    #[serde(flatten)]
+    pub outcome: TargetOutcome,
+}
+
+/// 未指定目标时的默认 settings.json：全部已安装的编辑器，都未安装时使用第一个候选路径（通常是 Code Stable）
+pub(crate) fn default_settings_targets() -> AppResult<Vec<PathBuf>> {
+    let installed: Vec<PathBuf> = detect_installations()
+        .into_iter()
+        .filter(|inst| inst.installed)
+        .map(|inst| PathBuf::from(inst.settings_path))
+        .collect();
+    if !installed.is_empty() {
+        return Ok(installed);
+    }
+
+    candidate_settings_paths()
+        .into_iter()
+        .next()
+        .map(|path| vec![path])
+        .ok_or_else(|| AppError::not_found(t!("vscode.config_dir_unknown")))
+}
+
+/// 解析用户选择的目标 settings.json（只允许检测到的候选路径及其 Profile）
+fn resolve_settings_targets(targets: Vec<String>) -> AppResult<Vec<PathBuf>> {
+    if targets.is_empty() {
+        return default_settings_targets();
+    }
+
+    let candidates = allowed_settings_paths();
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for target in targets {
+        let path = PathBuf::from(&target);
+        if !candidates.contains(&path) {
+            return Err(AppError::invalid_input(t!("vscode.unsupported_path")).with_path(&path));
+        }
+        if !paths.contains(&path) {
+            paths.push(path);
+        }
+    }
+    Ok(paths)
 }
 
 /// 配置 VSCode Codex 扩展（配置 ChatGPT 扩展）
-/// 功能：在 VSCode settings.json 中写入 ChatGPT 扩展配置
-pub fn configure_vscode_codex(base_url: String, api_key: String) -> Result<String, String> {
-    // 查找或创建 settings.json 路径
-    let settings_path = if let Some(path) = find_existing_settings() {
-        // 首次配置前创建备份
-        crate::config::create_backup_if_not_exists(&path)?;
-        path
+/// 功能：在每个所选 settings.json（默认配置或 Profile 配置）中写入 ChatGPT 扩展配置，
+/// targets 为空时配置全部已安装编辑器的默认配置
+pub fn configure_vscode_codex(
+    base_url: String,
+    _api_key: String,
+    targets: Vec<String>,
+) -> AppResult<Vec<TargetResult>> {
+    let results: Vec<TargetResult> = resolve_settings_targets(targets)?
+        .into_iter()
+        .map(|settings_path| TargetResult {
+            path: settings_path.to_string_lossy().to_string(),
+            outcome: write_codex_settings(&settings_path, &base_url),
+        })
+        .collect();
+
+    log::info!("已配置 ChatGPT 扩展使用自定义服务: {}, 请确保已设置环境变量 key88", base_url);
+    for result in &results {
+        log::info!("  {}: {:?}", result.path, result.outcome);
+    }
+
+    Ok(results)
+}
+
+/// 读取第一个已配置的编辑器中的 chatgpt.apiBase
+pub fn current_codex_api_base() -> Option<String> {
+    allowed_settings_paths().into_iter().find_map(|path| {
+        let content = fs::read_to_string(path).ok()?;
+        jsonc::parse_jsonc(&content)
+            .ok()?
+            .get("chatgpt.apiBase")?
+            .as_str()
+            .map(|s| s.to_string())
+    })
+}
+
+/// 移除 88code 写入的 ChatGPT 扩展配置（targets 为空时处理全部已存在的配置文件）
+pub fn remove_vscode_codex(targets: Vec<String>) -> AppResult<Vec<TargetResult>> {
+    let paths = if targets.is_empty() {
+        allowed_settings_paths()
+            .into_iter()
+            .filter(|path| path.exists())
+            .collect()
     } else {
-        // 如果找不到现有配置，使用第一个候选路径（通常是 Code Stable）
-        let candidates = candidate_settings_paths();
-        if candidates.is_empty() {
-            return Err(
-                "无法确定 VSCode 配置目录路径。\n\
-                 请检查：\n\
-                 1. 是否已安装 VSCode\n\
-                 2. Windows 系统环境变量 %APPDATA% 是否设置正确\n\
-                 3. 可以尝试手动创建配置文件：%APPDATA%\\Code\\User\\settings.json"
-                    .to_string(),
-            );
-        }
-        let path = candidates[0].clone();
+        resolve_settings_targets(targets)?
+    };
+
+    let results: Vec<TargetResult> = paths
+        .into_iter()
+        .map(|settings_path| TargetResult {
+            path: settings_path.to_string_lossy().to_string(),
+            outcome: remove_codex_settings(&settings_path),
+        })
+        .collect();
+
+    for result in &results {
+        log::info!("移除 ChatGPT 扩展配置 {}: {:?}", result.path, result.outcome);
+    }
+    Ok(results)
+}
+
+/// 从单个 settings.json 中移除 chatgpt.apiBase，以及 88code 写入的 chatgpt.config
+fn remove_codex_settings(settings_path: &Path) -> TargetOutcome {
+    if !settings_path.exists() {
+        return TargetOutcome::Skipped {
+            reason: t!("vscode.settings_missing"),
+        };
+    }
 
-        // 确保父目录存在
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)
-                .map_err(|e| format!("创建 VSCode 配置目录失败: {}", e))?;
+    let has_api_base = fs::read_to_string(settings_path)
+        .ok()
+        .and_then(|content| jsonc::parse_jsonc(&content).ok())
+        .is_some_and(|v| v.get("chatgpt.apiBase").is_some());
+    if !has_api_base {
+        return TargetOutcome::Skipped {
+            reason: t!("config.not_found_88code"),
+        };
+    }
+
+    edit_jsonc_file(settings_path, &[], |content| Ok(strip_codex_settings(content)))
+}
+
+/// 在原文本基础上删除 chatgpt.apiBase；chatgpt.config 仅在与应用写入的内容一致时删除
+fn strip_codex_settings(content: &str) -> String {
+    let mut result = jsonc::remove_member(content, &["chatgpt.apiBase"])
+        .unwrap_or_else(|| content.to_string());
+
+    let config_is_ours = jsonc::parse_jsonc(&result)
+        .ok()
+        .and_then(|v| v.get("chatgpt.config").cloned())
+        .is_some_and(|config| config == json!({ "preferred_auth_method": "apikey" }));
+    if config_is_ours {
+        if let Some(updated) = jsonc::remove_member(&result, &["chatgpt.config"]) {
+            result = updated;
         }
+    }
+
+    result
+}
+
+/// 在单个 settings.json 中写入 ChatGPT 扩展配置
+fn write_codex_settings(settings_path: &Path, base_url: &str) -> TargetOutcome {
+    edit_jsonc_file(
+        settings_path,
+        &[&["chatgpt.apiBase"], &["chatgpt.config"]],
+        |content| Ok(apply_codex_settings(content, base_url)),
+    )
+}
 
-        log::info!("VSCode settings.json 不存在，将创建新文件: {:?}", path);
-        path
+/// 读取 JSONC 文件、校验格式、按 edit 修改后原子写入（内容无变化时跳过）
+/// keys 为 edit 会写入的键，写入前记录原值以便撤销
+fn edit_jsonc_file(
+    settings_path: &Path,
+    keys: &[&[&str]],
+    edit: impl FnOnce(&str) -> AppResult<String>,
+) -> TargetOutcome {
+    // 持有写锁直到写入完成，避免读取后、写入前被其他进程修改
+    let _lock = match crate::config::lock_for_update(settings_path) {
+        Ok(lock) => lock,
+        Err(e) => return TargetOutcome::Failed { message: e.to_string() },
     };
+    let existed = settings_path.exists();
 
     // 读取现有设置内容
-    let original_content = if settings_path.exists() {
-        fs::read_to_string(&settings_path)
-            .map_err(|e| format!("读取 VSCode 设置失败: {}", e))?
+    let original_content = if existed {
+        match fs::read_to_string(settings_path) {
+            Ok(content) => content,
+            Err(e) => {
+                return TargetOutcome::Failed {
+                    message: t!("vscode.read_failed", error = e),
+                }
+            }
+        }
     } else {
+        log::info!("VSCode settings.json 不存在，将创建新文件: {:?}", settings_path);
         String::from("{\n}")
     };
 
-    // 验证 JSON 格式是否正确（移除注释和尾部逗号后验证）
-    let cleaned_content = strip_json_comments(&original_content);
-    let fixed_content = fix_json_trailing_commas(&cleaned_content);
-
-    // 尝试解析以验证格式（仅用于验证，不用于重构）
-    let _validation: Value = serde_json::from_str(&fixed_content).map_err(|e| {
-        format!(
-            "无法解析 VSCode settings.json 文件。\n\
-             原因: {}\n\
-             文件路径: {:?}\n\n\
-             请检查配置文件格式是否正确。",
-            e,
-            settings_path
-        )
-    })?;
+    // 验证 JSON 格式是否正确（移除注释和尾部逗号后验证，仅用于验证，不用于重构）
+    if let Err(e) = jsonc::parse_jsonc(&original_content) {
+        return TargetOutcome::ParseError {
+            message: t!("vscode.parse_failed", error = e),
+            line: e.line(),
+            column: jsonc::char_column(&original_content, e.line(), e.column()),
+        };
+    }
+
+    let final_content = match edit(&original_content) {
+        Ok(content) => content,
+        Err(e) => return TargetOutcome::Failed { message: e.to_string() },
+    };
+    if existed && final_content == original_content {
+        return TargetOutcome::Skipped {
+            reason: t!("vscode.up_to_date"),
+        };
+    }
+
+    // 首次配置前创建备份（新建文件时无需备份，atomic_write 会创建父目录）
+    if let Err(e) = crate::config::create_backup_if_not_exists(settings_path) {
+        return TargetOutcome::Failed { message: e.to_string() };
+    }
+    crate::changes::remember(crate::changes::ManagedFormat::Jsonc, settings_path, keys);
+
+    // 写入配置
+    if let Err(e) = crate::config::atomic_write(settings_path, final_content.as_bytes()) {
+        return TargetOutcome::Failed { message: e.to_string() };
+    }
+
+    if existed {
+        TargetOutcome::Updated
+    } else {
+        TargetOutcome::Created
+    }
+}
 
+/// 在 settings.json 原文本中写入 chatgpt.apiBase 与 chatgpt.config（完全保持原顺序）
+pub(crate) fn apply_codex_settings(original_content: &str, base_url: &str) -> String {
     // 在原文本基础上修改，完全保持原顺序
-    let mut final_content = original_content.clone();
+    let mut final_content = original_content.to_string();
 
     // 检查是否已存在配置
     let has_api_base = final_content.contains(r#""chatgpt.apiBase""#);
@@ -333,14 +566,13 @@ pub fn configure_vscode_codex(base_url: String, api_key: String) -> Result<Strin
         let lines: Vec<&str> = final_content.lines().collect();
         let mut new_lines = Vec::new();
         let mut in_chatgpt_config = false;
-        let mut config_indent = String::new();
         let mut brace_count = 0;
         let mut has_comma_after = false;
 
         for (i, line) in lines.iter().enumerate() {
             if line.contains(r#""chatgpt.config""#) && line.contains(':') {
                 in_chatgpt_config = true;
-                config_indent = line.chars().take_while(|c| c.is_whitespace()).collect::<String>();
+                let config_indent = line.chars().take_while(|c| c.is_whitespace()).collect::<String>();
                 brace_count = 0;
 
                 // 检查是否有逗号在闭合括号后
@@ -382,69 +614,15 @@ pub fn configure_vscode_codex(base_url: String, api_key: String) -> Result<Strin
         final_content = new_lines.join("\n");
     }
 
+    let api_base_entry = format!(r#""chatgpt.apiBase": "{}""#, base_url);
+    let config_entry = "\"chatgpt.config\": {\n  \"preferred_auth_method\": \"apikey\"\n}".to_string();
+
     // 如果都不存在，在末尾添加
     if !has_api_base && !has_config {
-        // 查找最后一个 }
-        if let Some(last_brace_pos) = final_content.rfind('}') {
-            // 检查倒数第二行的内容，判断是否需要逗号
-            let before_brace = &final_content[..last_brace_pos];
-            let lines: Vec<&str> = before_brace.lines().collect();
-
-            // 获取缩进（从倒数第二行）
-            let indent = if let Some(last_line) = lines.last() {
-                last_line.chars().take_while(|c| c.is_whitespace()).collect::<String>()
-            } else {
-                String::from("  ")
-            };
-
-            // 检查是否需要逗号
-            let needs_comma = if let Some(last_line) = lines.last() {
-                let trimmed = last_line.trim();
-                !trimmed.is_empty() && !trimmed.ends_with(',') && trimmed != "{"
-            } else {
-                false
-            };
-
-            let comma_prefix = if needs_comma { "," } else { "" };
-
-            let insertion = format!(
-                "{}\n{}\"{}\": \"{}\",\n{}\"chatgpt.config\": {{\n{}  \"preferred_auth_method\": \"apikey\"\n{}}}\n",
-                comma_prefix,
-                indent,
-                "chatgpt.apiBase",
-                base_url,
-                indent,
-                indent,
-                indent
-            );
-
-            final_content.insert_str(last_brace_pos, &insertion);
-        }
+        jsonc::insert_before_closing_brace(&mut final_content, &[api_base_entry, config_entry]);
     } else if has_api_base && !has_config {
         // 只有 apiBase，添加 config
-        if let Some(last_brace_pos) = final_content.rfind('}') {
-            let before_brace = &final_content[..last_brace_pos];
-            let lines: Vec<&str> = before_brace.lines().collect();
-            let indent = if let Some(last_line) = lines.last() {
-                last_line.chars().take_while(|c| c.is_whitespace()).collect::<String>()
-            } else {
-                String::from("  ")
-            };
-            let needs_comma = if let Some(last_line) = lines.last() {
-                !last_line.trim_end().ends_with(',')
-            } else {
-                false
-            };
-
-            let insertion = format!(
-                "{}\n{}\"chatgpt.config\": {{\n{}  \"preferred_auth_method\": \"apikey\"\n{}}}\n",
-                if needs_comma { "," } else { "" },
-                indent,
-                indent,
-                indent
-            );
-            final_content.insert_str(last_brace_pos, &insertion);
-        }
+        jsonc::insert_before_closing_brace(&mut final_content, &[config_entry]);
     } else if !has_api_base && has_config {
         // 只有 config，添加 apiBase（在 config 之前）
         // 找到 chatgpt.config 的位置
@@ -461,16 +639,155 @@ pub fn configure_vscode_codex(base_url: String, api_key: String) -> Result<Strin
         }
     }
 
-    // 写入配置
-    crate::config::atomic_write(&settings_path, final_content.as_bytes())?;
+    final_content
+}
 
-    log::info!("已配置 ChatGPT 扩展使用自定义服务: {}, 请确保环境变量 key88={}", base_url, api_key);
+/// 工作区设置中 ChatGPT 扩展配置的来源
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SettingScope {
+    /// 文件夹设置：<folder>/.vscode/settings.json
+    Folder,
+    /// 工作区设置：*.code-workspace 中的 settings
+    Workspace,
+    /// 用户设置：User/settings.json
+    User,
+}
+
+/// 单层设置中的 chatgpt.apiBase
+#[derive(Debug, Clone, Serialize)]
+pub struct SettingLayer {
+    pub scope: SettingScope,
+    pub path: String,
+    pub exists: bool,
+    pub api_base: Option<String>,
+}
+
+/// 生效的 chatgpt.apiBase 及其来源
+#[derive(Debug, Clone, Serialize)]
+pub struct EffectiveSetting {
+    pub api_base: Option<String>,
+    pub source: Option<SettingScope>,
+    pub source_path: Option<String>,
+    /// 按优先级从高到低排列的各层设置
+    pub layers: Vec<SettingLayer>,
+}
 
-    Ok(format!(
-        "VSCode 配置成功！路径: {}\n已配置 ChatGPT 扩展使用自定义服务: {}\n请重新加载 VSCode 窗口以使配置生效。",
-        settings_path.display(),
+/// 判断路径是否为 .code-workspace 文件
+fn is_workspace_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "code-workspace")
+}
+
+/// 在 .code-workspace 原文本的 settings 块中写入 ChatGPT 扩展配置
+fn apply_codex_workspace_settings(content: &str, base_url: &str) -> AppResult<String> {
+    // 已有 settings 块：只修改该块
+    if let Some((start, end)) = jsonc::find_value_span(content, &["settings"])
+        .filter(|(start, _)| content[*start..].starts_with('{'))
+    {
+        let settings = apply_codex_settings(&content[start..end], base_url);
+        return Ok(format!("{}{}{}", &content[..start], settings, &content[end..]));
+    }
+
+    // 没有 settings 块：在顶层对象末尾添加
+    let last_brace_pos = content
+        .rfind('}')
+        .ok_or_else(|| AppError::new(crate::error::ErrorKind::Parse, t!("vscode.workspace_no_root")))?;
+    let before_brace = content[..last_brace_pos].trim_end();
+    let needs_comma = !before_brace.ends_with('{') && !before_brace.ends_with(',');
+
+    let mut new_content = before_brace.to_string();
+    if needs_comma {
+        new_content.push(',');
+    }
+    new_content.push_str(&format!(
+        "\n  \"settings\": {{\n    \"chatgpt.apiBase\": \"{}\",\n    \"chatgpt.config\": {{\n      \"preferred_auth_method\": \"apikey\"\n    }}\n  }}\n",
         base_url
-    ))
+    ));
+    new_content.push_str(&content[last_brace_pos..]);
+    Ok(new_content)
+}
+
+/// 获取工作区目标对应的设置文件：文件夹使用 .vscode/settings.json，.code-workspace 直接使用该文件
+fn workspace_settings_path(target: &Path) -> AppResult<(SettingScope, PathBuf)> {
+    if is_workspace_file(target) {
+        if !target.is_file() {
+            return Err(AppError::not_found(t!("vscode.workspace_missing")).with_path(target));
+        }
+        Ok((SettingScope::Workspace, target.to_path_buf()))
+    } else if target.is_dir() {
+        Ok((
+            SettingScope::Folder,
+            target.join(".vscode").join("settings.json"),
+        ))
+    } else {
+        Err(AppError::invalid_input(t!("vscode.pick_folder")).with_path(target))
+    }
+}
+
+/// 为指定项目配置 ChatGPT 扩展（文件夹的 .vscode/settings.json 或 .code-workspace 的 settings 块）
+pub fn configure_vscode_workspace_codex(base_url: String, target: String) -> AppResult<TargetResult> {
+    let (scope, settings_path) = workspace_settings_path(Path::new(&target))?;
+
+    let outcome = match scope {
+        SettingScope::Workspace => edit_jsonc_file(
+            &settings_path,
+            &[&["settings", "chatgpt.apiBase"], &["settings", "chatgpt.config"]],
+            |content| apply_codex_workspace_settings(content, &base_url),
+        ),
+        _ => write_codex_settings(&settings_path, &base_url),
+    };
+    log::info!("已配置工作区 ChatGPT 扩展: {:?}, {:?}", settings_path, outcome);
+
+    Ok(TargetResult {
+        path: settings_path.to_string_lossy().to_string(),
+        outcome,
+    })
+}
+
+/// 读取单层设置中的 chatgpt.apiBase
+fn read_setting_layer(scope: SettingScope, path: &Path) -> SettingLayer {
+    let content = fs::read_to_string(path).ok();
+    let api_base = content
+        .as_deref()
+        .and_then(|c| jsonc::parse_jsonc(c).ok())
+        .and_then(|v| match scope {
+            SettingScope::Workspace => v.get("settings").cloned(),
+            _ => Some(v),
+        })
+        .and_then(|v| v.get("chatgpt.apiBase").and_then(|s| s.as_str()).map(|s| s.to_string()));
+
+    SettingLayer {
+        scope,
+        path: path.to_string_lossy().to_string(),
+        exists: content.is_some(),
+        api_base,
+    }
+}
+
+/// 计算项目中生效的 chatgpt.apiBase 及其来源（优先级：文件夹 > 工作区 > 用户）
+/// user_settings 为空时使用第一个已安装编辑器的用户设置
+pub fn effective_codex_setting(
+    target: String,
+    user_settings: Option<String>,
+) -> AppResult<EffectiveSetting> {
+    let (scope, settings_path) = workspace_settings_path(Path::new(&target))?;
+    let mut layers = vec![read_setting_layer(scope, &settings_path)];
+
+    let user_path = match user_settings {
+        Some(path) => Some(PathBuf::from(path)),
+        None => default_settings_targets()?.into_iter().next(),
+    };
+    if let Some(user_path) = user_path {
+        layers.push(read_setting_layer(SettingScope::User, &user_path));
+    }
+
+    let effective = layers.iter().find(|layer| layer.api_base.is_some());
+    Ok(EffectiveSetting {
+        api_base: effective.and_then(|layer| layer.api_base.clone()),
+        source: effective.map(|layer| layer.scope),
+        source_path: effective.map(|layer| layer.path.clone()),
+        layers,
+    })
 }
 
 /// 获取 VSCode 配置路径信息
@@ -480,3 +797,244 @@ pub fn get_vscode_paths_info() -> Vec<String> {
         .map(|p| p.to_string_lossy().to_string())
         .collect()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn detects_linux_installations_under_home() {
+        let home = tempfile::tempdir().unwrap();
+        let config = home.path().join(".config");
+        let user_dirs = [
+            config.join("Cursor").join("User"),
+            config.join("Windsurf").join("User"),
+            config.join("Trae").join("User"),
+            home.path().join(".var/app/com.visualstudio.code/config/Code/User"),
+            home.path().join("snap/codium/current/.config/VSCodium/User"),
+            home.path().join(".cursor-server/data/Machine"),
+        ];
+        for dir in &user_dirs {
+            fs::create_dir_all(dir).unwrap();
+        }
+        fs::write(user_dirs[0].join("settings.json"), "{}").unwrap();
+        fs::write(user_dirs[5].join("settings.json"), "{}").unwrap();
+        fs::create_dir_all(user_dirs[3].join("profiles").join("abc123")).unwrap();
+
+        let candidates = candidate_installations_in(Some(home.path()), Some(&config));
+        for dir in &user_dirs {
+            assert!(candidates.iter().any(|(_, path)| path == &dir.join("settings.json")), "{dir:?}");
+        }
+
+        let installed: Vec<(String, bool, usize)> = inspect_installations(candidates)
+            .into_iter()
+            .filter(|inst| inst.installed)
+            .map(|inst| (inst.name, inst.settings_exists, inst.profiles.len()))
+            .collect();
+        assert_eq!(
+            installed,
+            vec![
+                ("Cursor".to_string(), true, 0),
+                ("Windsurf".to_string(), false, 0),
+                ("Trae".to_string(), false, 0),
+                ("VS Code (Flatpak)".to_string(), false, 1),
+                ("VSCodium (Snap)".to_string(), false, 0),
+                ("Cursor Server".to_string(), true, 0),
+            ]
+        );
+    }
+
+    #[cfg(all(unix, not(target_os = "macos")))]
+    #[test]
+    fn candidates_without_home_only_use_config_dir() {
+        let config = tempfile::tempdir().unwrap();
+        let candidates = candidate_installations_in(None, Some(config.path()));
+        assert_eq!(candidates.len(), vscode_product_dirs().len());
+        assert!(candidates.iter().all(|(_, path)| path.starts_with(config.path())));
+        assert!(candidate_installations_in(None, None).is_empty());
+    }
+
+    #[test]
+    fn reports_per_target_outcomes() {
+        let dir = tempfile::tempdir().unwrap();
+        let base_url = "https://example.com/openai";
+
+        let created = dir.path().join("created").join("settings.json");
+        assert!(matches!(write_codex_settings(&created, base_url), TargetOutcome::Created));
+        let value = jsonc::parse_jsonc(&fs::read_to_string(&created).unwrap()).unwrap();
+        assert_eq!(value["chatgpt.apiBase"], base_url);
+
+        let updated = dir.path().join("settings.json");
+        fs::write(&updated, "{\n  // 用户注释\n  \"editor.fontSize\": 14\n}").unwrap();
+        assert!(matches!(write_codex_settings(&updated, base_url), TargetOutcome::Updated));
+        let content = fs::read_to_string(&updated).unwrap();
+        assert!(content.contains("// 用户注释"));
+        assert!(content.contains("\"editor.fontSize\": 14"));
+
+        assert!(matches!(write_codex_settings(&updated, base_url), TargetOutcome::Skipped { .. }));
+
+        let broken = dir.path().join("broken.json");
+        fs::write(&broken, "{\n  \"a\": 1,\n  \"b\" 2\n}").unwrap();
+        match write_codex_settings(&broken, base_url) {
+            TargetOutcome::ParseError { line, column, .. } => assert_eq!((line, column), (3, 7)),
+            other => panic!("unexpected outcome: {other:?}"),
+        }
+        assert_eq!(fs::read_to_string(&broken).unwrap(), "{\n  \"a\": 1,\n  \"b\" 2\n}");
+
+        let not_a_file = dir.path().join("directory.json");
+        fs::create_dir(&not_a_file).unwrap();
+        assert!(matches!(write_codex_settings(&not_a_file, base_url), TargetOutcome::Failed { .. }));
+    }
+
+    #[test]
+    fn rejects_targets_outside_detected_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = dir.path().join("settings.json");
+        let target = outside.to_string_lossy().to_string();
+
+        let err = resolve_settings_targets(vec![target.clone()]).unwrap_err();
+        assert_eq!(err.kind, crate::error::ErrorKind::InvalidInput);
+
+        let err = configure_vscode_codex("https://example.com".to_string(), String::new(), vec![target.clone()])
+            .unwrap_err();
+        assert_eq!(err.kind, crate::error::ErrorKind::InvalidInput);
+        assert!(remove_vscode_codex(vec![target]).is_err());
+        assert!(!outside.exists());
+    }
+
+    fn profile_fixture(storage: Option<&str>) -> tempfile::TempDir {
+        let user = tempfile::tempdir().unwrap();
+        for id in ["-6f2a1b", "3c9d0e"] {
+            fs::create_dir_all(user.path().join("profiles").join(id)).unwrap();
+        }
+        fs::write(user.path().join("profiles/3c9d0e/settings.json"), "{}").unwrap();
+        fs::write(user.path().join("profiles/not-a-profile"), "").unwrap();
+        if let Some(storage) = storage {
+            fs::create_dir_all(user.path().join("globalStorage")).unwrap();
+            fs::write(user.path().join("globalStorage/storage.json"), storage).unwrap();
+        }
+        user
+    }
+
+    fn profile_summary(user: &Path) -> Vec<(String, String, bool)> {
+        detect_profiles(user)
+            .into_iter()
+            .map(|p| {
+                assert!(p.settings_path.ends_with("settings.json"));
+                assert!(p.settings_path.contains(&p.id));
+                (p.id, p.name, p.settings_exists)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn names_profiles_from_storage_json() {
+        let user = profile_fixture(Some(
+            r#"{"userDataProfiles": [
+                {"location": "3c9d0e", "name": "Python"},
+                {"location": "-6f2a1b", "name": "Rust"},
+                {"location": "deleted", "name": "Gone"}
+            ]}"#,
+        ));
+        assert_eq!(
+            profile_summary(user.path()),
+            vec![
+                ("3c9d0e".to_string(), "Python".to_string(), true),
+                ("-6f2a1b".to_string(), "Rust".to_string(), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_profile_ids_without_usable_storage_json() {
+        let expected = vec![
+            ("-6f2a1b".to_string(), "-6f2a1b".to_string(), false),
+            ("3c9d0e".to_string(), "3c9d0e".to_string(), true),
+        ];
+        let missing = profile_fixture(None);
+        assert_eq!(profile_summary(missing.path()), expected);
+        let malformed = profile_fixture(Some("{\"userDataProfiles\": ["));
+        assert_eq!(profile_summary(malformed.path()), expected);
+
+        let empty = tempfile::tempdir().unwrap();
+        assert!(detect_profiles(empty.path()).is_empty());
+    }
+
+    #[test]
+    fn configures_folder_settings() {
+        let project = tempfile::tempdir().unwrap();
+        let target = project.path().to_string_lossy().to_string();
+
+        let result = configure_vscode_workspace_codex("https://example.com/openai".to_string(), target).unwrap();
+        assert!(matches!(result.outcome, TargetOutcome::Created));
+        let settings = project.path().join(".vscode").join("settings.json");
+        assert_eq!(PathBuf::from(&result.path), settings);
+        let value = jsonc::parse_jsonc(&fs::read_to_string(&settings).unwrap()).unwrap();
+        assert_eq!(value["chatgpt.apiBase"], "https://example.com/openai");
+        assert_eq!(value["chatgpt.config"]["preferred_auth_method"], "apikey");
+    }
+
+    #[test]
+    fn configures_workspace_settings_block_keeping_comments() {
+        let with_block = "{\n  // 项目目录\n  \"folders\": [{ \"path\": \".\" }],\n  \"settings\": {\n    // 字号\n    \"editor.fontSize\": 13\n  }\n}\n";
+        let updated = apply_codex_workspace_settings(with_block, "https://example.com/openai").unwrap();
+        assert!(updated.contains("// 项目目录"));
+        assert!(updated.contains("// 字号"));
+        let value = jsonc::parse_jsonc(&updated).unwrap();
+        assert_eq!(value["settings"]["chatgpt.apiBase"], "https://example.com/openai");
+        assert_eq!(value["settings"]["editor.fontSize"], 13);
+        assert!(value.get("chatgpt.apiBase").is_none());
+        assert_eq!(value["folders"][0]["path"], ".");
+
+        let without_block = "{\n  // 只有目录\n  \"folders\": []\n}";
+        let added = apply_codex_workspace_settings(without_block, "https://example.com/openai").unwrap();
+        assert!(added.contains("// 只有目录"));
+        let value = jsonc::parse_jsonc(&added).unwrap();
+        assert_eq!(value["settings"]["chatgpt.apiBase"], "https://example.com/openai");
+
+        let dir = tempfile::tempdir().unwrap();
+        let workspace = dir.path().join("app.code-workspace");
+        fs::write(&workspace, with_block).unwrap();
+        let result = configure_vscode_workspace_codex(
+            "https://example.com/openai".to_string(),
+            workspace.to_string_lossy().to_string(),
+        )
+        .unwrap();
+        assert!(matches!(result.outcome, TargetOutcome::Updated));
+        assert_eq!(fs::read_to_string(&workspace).unwrap(), updated);
+    }
+
+    #[test]
+    fn effective_setting_prefers_folder_then_workspace_then_user() {
+        let dir = tempfile::tempdir().unwrap();
+        let user = dir.path().join("user-settings.json");
+        fs::write(&user, "{ \"chatgpt.apiBase\": \"https://user.example\" }").unwrap();
+        let user_arg = Some(user.to_string_lossy().to_string());
+
+        let folder = dir.path().join("project");
+        fs::create_dir_all(folder.join(".vscode")).unwrap();
+        let folder_arg = folder.to_string_lossy().to_string();
+        let effective = effective_codex_setting(folder_arg.clone(), user_arg.clone()).unwrap();
+        assert_eq!(effective.source, Some(SettingScope::User));
+        assert_eq!(effective.api_base.as_deref(), Some("https://user.example"));
+        assert!(!effective.layers[0].exists);
+
+        fs::write(folder.join(".vscode/settings.json"), "{ \"chatgpt.apiBase\": \"https://folder.example\" }").unwrap();
+        let effective = effective_codex_setting(folder_arg, user_arg.clone()).unwrap();
+        assert_eq!(effective.source, Some(SettingScope::Folder));
+        assert_eq!(effective.api_base.as_deref(), Some("https://folder.example"));
+
+        let workspace = dir.path().join("app.code-workspace");
+        fs::write(&workspace, "{ \"chatgpt.apiBase\": \"https://ignored.example\", \"settings\": {} }").unwrap();
+        let workspace_arg = workspace.to_string_lossy().to_string();
+        let effective = effective_codex_setting(workspace_arg.clone(), user_arg.clone()).unwrap();
+        assert_eq!(effective.source, Some(SettingScope::User));
+
+        fs::write(&workspace, "{ \"settings\": { \"chatgpt.apiBase\": \"https://workspace.example\" } }").unwrap();
+        let effective = effective_codex_setting(workspace_arg, user_arg).unwrap();
+        assert_eq!(effective.source, Some(SettingScope::Workspace));
+        assert_eq!(effective.api_base.as_deref(), Some("https://workspace.example"));
+        assert_eq!(effective.source_path.as_deref(), Some(workspace.to_string_lossy().as_ref()));
+    }
+}
@@ -0,0 +1,505 @@
+use crate::config::{
+    get_claude_config_dir, get_claude_settings_path, get_codex_auth_path, get_codex_config_path,
+};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError};
+use std::time::{Duration, SystemTime};
+use tauri::{AppHandle, Emitter};
+
+/// 配置文件变化时发送给前端的事件名
+pub const CONFIG_CHANGED_EVENT: &str = "config-file-changed";
+
+/// 轮询间隔（轮询方式对 rename 替换、符号链接等情况最稳妥）
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// 每隔多少次轮询重新列出监听文件（新安装的编辑器、切换的 shell）
+const REFRESH_WATCHED_EVERY: u32 = 30;
+
+/// 被监听的配置文件类型
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WatchedKind {
+    ClaudeSettings,
+    ClaudeVscodeConfig,
+    CodexAuth,
+    CodexConfig,
+    VscodeSettings,
+    ShellRc,
+}
+
+/// 配置文件的解析摘要（不包含密钥明文）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConfigSummary {
+    pub base_url: Option<String>,
+    pub api_key_set: bool,
+    pub model_provider: Option<String>,
+    pub model: Option<String>,
+    pub parse_error: Option<String>,
+}
+
+/// 单个配置文件的状态（事件负载与查询命令共用）
+#[derive(Debug, Clone, Serialize)]
+pub struct ConfigFileStatus {
+    pub kind: WatchedKind,
+    pub path: String,
+    pub exists: bool,
+    pub summary: ConfigSummary,
+    /// 符号链接及 dotfiles 管理工具信息
+    pub link: crate::dotfiles::LinkInfo,
+}
+
+/// 文件状态：修改时间和大小未变时轮询不再读取内容
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct FileState {
+    modified: Option<SystemTime>,
+    len: u64,
+    hash: u64,
+}
+
+/// 文件指纹：None 表示文件不存在
+type Fingerprint = Option<FileState>;
+
+#[derive(Default)]
+struct WatchState {
+    /// 最近一次看到的指纹（监听线程发现的变化或应用自身的写入）
+    files: HashMap<PathBuf, Fingerprint>,
+    /// 应用正在写入的文件，轮询时跳过，避免把写入过程中的状态当作外部修改
+    writing: HashSet<PathBuf>,
+}
+
+fn state() -> MutexGuard<'static, WatchState> {
+    static STATE: OnceLock<Mutex<WatchState>> = OnceLock::new();
+    STATE
+        .get_or_init(|| Mutex::new(WatchState::default()))
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// 应用最近一次读取（或写入）时的内容哈希，用于检测并发修改
+fn last_read() -> MutexGuard<'static, HashMap<PathBuf, Option<u64>>> {
+    static LAST_READ: OnceLock<Mutex<HashMap<PathBuf, Option<u64>>>> = OnceLock::new();
+    LAST_READ
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .unwrap_or_else(PoisonError::into_inner)
+}
+
+/// 按给定内容生成指纹（先于内容读取元数据，读取期间文件被修改时下次轮询会重新计算）
+fn state_of(meta: Option<std::fs::Metadata>, data: Option<&[u8]>) -> Fingerprint {
+    data.map(|data| FileState {
+        modified: meta.and_then(|m| m.modified().ok()),
+        len: data.len() as u64,
+        hash: hash_bytes(data),
+    })
+}
+
+/// 计算文件指纹
+fn fingerprint(path: &Path) -> Fingerprint {
+    let meta = std::fs::metadata(path).ok();
+    state_of(meta, std::fs::read(path).ok().as_deref())
+}
+
+fn content_hash(fingerprint: Fingerprint) -> Option<u64> {
+    fingerprint.map(|s| s.hash)
+}
+
+fn hash_bytes(data: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// 列出所有需要监听的配置文件
+pub fn watched_files() -> Vec<(WatchedKind, PathBuf)> {
+    let mut files = vec![
+        (WatchedKind::ClaudeSettings, get_claude_settings_path()),
+        (
+            WatchedKind::ClaudeVscodeConfig,
+            get_claude_config_dir().join("config.json"),
+        ),
+        (WatchedKind::CodexAuth, get_codex_auth_path()),
+        (WatchedKind::CodexConfig, get_codex_config_path()),
+    ];
+
+    for path in crate::vscode::candidate_settings_paths() {
+        files.push((WatchedKind::VscodeSettings, path));
+    }
+
+    if let Some(rc) = crate::env_manager::shell_config_path() {
+        files.push((WatchedKind::ShellRc, rc));
+    }
+
+    files
+}
+
+/// 读取单个配置文件的当前状态
+pub fn file_status(kind: WatchedKind, path: &Path) -> ConfigFileStatus {
+    let content = std::fs::read_to_string(path).ok();
+    let summary = match &content {
+        Some(content) => summarize(kind, content),
+        None => ConfigSummary::default(),
+    };
+
+    ConfigFileStatus {
+        kind,
+        path: path.to_string_lossy().to_string(),
+        exists: content.is_some(),
+        summary,
+        link: crate::dotfiles::inspect(path),
+    }
+}
+
+/// 获取所有被监听文件的当前状态
+pub fn all_file_status() -> Vec<ConfigFileStatus> {
+    watched_files()
+        .into_iter()
+        .map(|(kind, path)| file_status(kind, &path))
+        .collect()
+}
+
+/// 按文件类型解析出摘要信息
+fn summarize(kind: WatchedKind, content: &str) -> ConfigSummary {
+    let mut summary = ConfigSummary::default();
+
+    match kind {
+        WatchedKind::ClaudeSettings => match serde_json::from_str::<Value>(content) {
+            Ok(v) => {
+                let env = v.get("env");
+                summary.base_url = env
+                    .and_then(|e| e.get("ANTHROPIC_BASE_URL"))
+                    .and_then(|s| s.as_str())
+                    .map(|s| s.to_string());
+                summary.api_key_set = env
+                    .and_then(|e| e.get("ANTHROPIC_AUTH_TOKEN"))
+                    .and_then(|s| s.as_str())
+                    .is_some_and(|s| !s.is_empty());
+            }
+            Err(e) => summary.parse_error = Some(e.to_string()),
+        },
+        WatchedKind::ClaudeVscodeConfig => match serde_json::from_str::<Value>(content) {
+            Ok(v) => {
+                summary.api_key_set = v
+                    .get("primaryApiKey")
+                    .and_then(|s| s.as_str())
+                    .is_some_and(|s| !s.is_empty());
+            }
+            Err(e) => summary.parse_error = Some(e.to_string()),
+        },
+        WatchedKind::CodexAuth => match serde_json::from_str::<Value>(content) {
+            Ok(v) => {
+                summary.api_key_set = v
+                    .get("OPENAI_API_KEY")
+                    .and_then(|s| s.as_str())
+                    .is_some_and(|s| !s.is_empty());
+            }
+            Err(e) => summary.parse_error = Some(e.to_string()),
+        },
+        WatchedKind::CodexConfig => match toml::from_str::<toml::Value>(content) {
+            Ok(v) => {
+                summary.model = v.get("model").and_then(|s| s.as_str()).map(|s| s.to_string());
+                summary.model_provider = v
+                    .get("model_provider")
+                    .and_then(|s| s.as_str())
+                    .map(|s| s.to_string());
+                if let Some(provider) = &summary.model_provider {
+                    summary.base_url = v
+                        .get("model_providers")
+                        .and_then(|p| p.get(provider))
+                        .and_then(|p| p.get("base_url"))
+                        .and_then(|s| s.as_str())
+                        .map(|s| s.to_string());
+                }
+            }
+            Err(e) => summary.parse_error = Some(e.to_string()),
+        },
+        WatchedKind::VscodeSettings => match crate::jsonc::parse_jsonc(content) {
+            Ok(v) => {
+                summary.base_url = v
+                    .get("chatgpt.apiBase")
+                    .and_then(|s| s.as_str())
+                    .map(|s| s.to_string());
+            }
+            Err(e) => summary.parse_error = Some(e.to_string()),
+        },
+        WatchedKind::ShellRc => {
+            summary.api_key_set = content
+                .lines()
+                .any(|line| line.trim().starts_with("export key88="));
+        }
+    }
+
+    summary
+}
+
+/// 记录应用读取文件时的指纹
+pub fn record_read(path: &Path) {
+    let hash = content_hash(fingerprint(path));
+    last_read().insert(path.to_path_buf(), hash);
+}
+
+/// 高级配置编辑器载入的文件内容（文件不存在时为空）
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EditableConfig {
+    pub settings_json: Option<String>,
+    pub auth_json: Option<String>,
+    pub config_toml: Option<String>,
+}
+
+/// 读取文件供编辑，并按读到的内容记录指纹，之后保存时据此判断是否被外部修改
+pub fn read_for_edit(path: &Path) -> crate::error::AppResult<Option<String>> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => Some(content),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+        Err(e) => {
+            return Err(crate::error::AppError::io(
+                crate::i18n::t!("fs.read_failed"),
+                path,
+                e,
+            ))
+        }
+    };
+    last_read().insert(path.to_path_buf(), content.as_deref().map(|c| hash_bytes(c.as_bytes())));
+    Ok(content)
+}
+
+/// 读取客户端的全部可编辑配置文件
+pub fn read_config_for_edit(target: crate::base_url::UrlTarget) -> crate::error::AppResult<EditableConfig> {
+    Ok(match target {
+        crate::base_url::UrlTarget::Claude => EditableConfig {
+            settings_json: read_for_edit(&get_claude_settings_path())?,
+            ..Default::default()
+        },
+        crate::base_url::UrlTarget::Codex => EditableConfig {
+            auth_json: read_for_edit(&get_codex_auth_path())?,
+            config_toml: read_for_edit(&get_codex_config_path())?,
+            ..Default::default()
+        },
+    })
+}
+
+fn external_change(path: &Path) -> crate::error::AppError {
+    crate::error::AppError::new(crate::error::ErrorKind::ExternalChange, crate::i18n::t!("fs.external_change"))
+        .with_path(path)
+        .with_action(crate::error::SuggestedAction::Reload)
+}
+
+/// 应用写入文件期间持有（由 config::atomic_write 在写锁内创建），轮询线程跳过该文件
+pub(crate) struct WriteGuard {
+    path: PathBuf,
+}
+
+/// 开始写入：文件内容与最近一次看到的不同（外部修改尚未被发现并通知界面）时拒绝覆盖。
+/// 从未见过的文件不做检查
+pub(crate) fn begin_write(path: &Path, current: Option<&[u8]>) -> crate::error::AppResult<WriteGuard> {
+    let mut state = state();
+    if let Some(seen) = state.files.get(path) {
+        if content_hash(*seen) != current.map(hash_bytes) {
+            return Err(external_change(path));
+        }
+    }
+    state.writing.insert(path.to_path_buf());
+    Ok(WriteGuard {
+        path: path.to_path_buf(),
+    })
+}
+
+impl WriteGuard {
+    /// 写入成功后记录新内容的指纹（data 为 None 表示文件已删除），之后不会被当作外部修改
+    pub(crate) fn finish(self, data: Option<&[u8]>) {
+        let fingerprint = state_of(std::fs::metadata(&self.path).ok(), data);
+        last_read().insert(self.path.clone(), content_hash(fingerprint));
+        state().files.insert(self.path.clone(), fingerprint);
+    }
+}
+
+impl Drop for WriteGuard {
+    fn drop(&mut self) {
+        state().writing.remove(&self.path);
+    }
+}
+
+/// 检查文件自上次读取后是否被外部修改
+/// 从未读取过的文件不做检查
+pub fn ensure_unchanged_since_read(path: &Path) -> crate::error::AppResult<()> {
+    let recorded = last_read().get(path).copied();
+    match recorded {
+        Some(recorded) if recorded != content_hash(fingerprint(path)) => Err(external_change(path)),
+        _ => Ok(()),
+    }
+}
+
+/// 对比监听文件的指纹，返回内容发生变化的文件
+/// 首次见到的文件只记录指纹，不视为变化；修改时间和大小都未变时不读取内容
+fn poll_changes(files: &[(WatchedKind, PathBuf)]) -> Vec<(WatchedKind, PathBuf)> {
+    let mut changed = Vec::new();
+    let mut state = state();
+
+    for (kind, path) in files {
+        if state.writing.contains(path) {
+            continue;
+        }
+        let previous = state.files.get(path).copied();
+        let meta = std::fs::metadata(path).ok();
+        match (previous, &meta) {
+            (Some(None), None) => continue,
+            (Some(Some(prev)), Some(meta))
+                if prev.modified.is_some() && prev.modified == meta.modified().ok() && prev.len == meta.len() =>
+            {
+                continue
+            }
+            _ => {}
+        }
+
+        let fingerprint = state_of(meta, std::fs::read(path).ok().as_deref());
+        if previous.is_some_and(|prev| content_hash(prev) != content_hash(fingerprint)) {
+            changed.push((*kind, path.clone()));
+        }
+        state.files.insert(path.clone(), fingerprint);
+    }
+
+    changed
+}
+
+/// 启动后台监听线程，配置文件被外部修改时向前端发送事件
+pub fn start(app: AppHandle) {
+    // 先记录初始状态
+    let mut files = watched_files();
+    poll_changes(&files);
+
+    std::thread::spawn(move || {
+        let mut ticks: u32 = 0;
+        loop {
+            std::thread::sleep(POLL_INTERVAL);
+            ticks = ticks.wrapping_add(1);
+            if ticks.is_multiple_of(REFRESH_WATCHED_EVERY) {
+                files = watched_files();
+            }
+            notify_changes(&app, &files);
+        }
+    });
+}
+
+/// 向前端发送发生变化的文件状态
+fn notify_changes(app: &AppHandle, files: &[(WatchedKind, PathBuf)]) {
+    for (kind, path) in poll_changes(files) {
+        let status = file_status(kind, &path);
+        log::info!("检测到配置文件变化: {:?}", path);
+        if let Err(e) = app.emit(CONFIG_CHANGED_EVENT, status) {
+            log::warn!("发送配置变化事件失败: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    fn watched(dir: &tempfile::TempDir) -> (PathBuf, Vec<(WatchedKind, PathBuf)>) {
+        let path = dir.path().join("settings.json");
+        (path.clone(), vec![(WatchedKind::ClaudeSettings, path)])
+    }
+
+    #[test]
+    fn reports_external_changes_but_not_app_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, files) = watched(&dir);
+        assert!(poll_changes(&files).is_empty());
+
+        crate::config::atomic_write(&path, b"{}").unwrap();
+        assert!(poll_changes(&files).is_empty());
+
+        std::fs::write(&path, br#"{"env":{}}"#).unwrap();
+        assert_eq!(poll_changes(&files), files);
+        assert!(poll_changes(&files).is_empty());
+
+        crate::config::remove_file(&path).unwrap();
+        assert!(poll_changes(&files).is_empty());
+        std::fs::write(&path, b"{}").unwrap();
+        assert_eq!(poll_changes(&files), files);
+    }
+
+    #[test]
+    fn ignores_metadata_only_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, files) = watched(&dir);
+        std::fs::write(&path, b"{}").unwrap();
+        poll_changes(&files);
+
+        let file = std::fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(SystemTime::now() + Duration::from_secs(60)).unwrap();
+        assert!(poll_changes(&files).is_empty());
+    }
+
+    #[test]
+    fn skips_files_while_the_app_writes_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, files) = watched(&dir);
+        std::fs::write(&path, b"{}").unwrap();
+        poll_changes(&files);
+
+        let guard = begin_write(&path, Some(b"{}")).unwrap();
+        std::fs::write(&path, b"{ }").unwrap();
+        assert!(poll_changes(&files).is_empty());
+        // 写入未完成（未调用 finish）时，释放后仍按外部修改处理
+        drop(guard);
+        assert_eq!(poll_changes(&files), files);
+    }
+
+    #[test]
+    fn writes_refuse_changes_not_yet_seen() {
+        let dir = tempfile::tempdir().unwrap();
+        let (path, files) = watched(&dir);
+        crate::config::atomic_write(&path, b"{}").unwrap();
+
+        std::fs::write(&path, br#"{"theme":"dark"}"#).unwrap();
+        let err = crate::config::atomic_write(&path, b"{ }").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::ExternalChange);
+        assert_eq!(std::fs::read(&path).unwrap(), br#"{"theme":"dark"}"#);
+
+        // 监听线程发现并通知界面后允许写入
+        poll_changes(&files);
+        crate::config::atomic_write(&path, b"{ }").unwrap();
+    }
+
+    #[test]
+    fn detects_changes_since_the_editor_read_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.toml");
+        assert_eq!(read_for_edit(&path).unwrap(), None);
+        ensure_unchanged_since_read(&path).unwrap();
+
+        std::fs::write(&path, "model = \"gpt-5\"\n").unwrap();
+        let err = ensure_unchanged_since_read(&path).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::ExternalChange);
+
+        assert!(read_for_edit(&path).unwrap().is_some());
+        ensure_unchanged_since_read(&path).unwrap();
+        crate::config::atomic_write(&path, b"model = \"o3\"\n").unwrap();
+        ensure_unchanged_since_read(&path).unwrap();
+    }
+
+    #[test]
+    fn summarizes_files_without_secrets() {
+        let summary = summarize(
+            WatchedKind::ClaudeSettings,
+            r#"{"env":{"ANTHROPIC_BASE_URL":"https://88code.org/api","ANTHROPIC_AUTH_TOKEN":"88_secret"}}"#,
+        );
+        assert_eq!(summary.base_url.as_deref(), Some("https://88code.org/api"));
+        assert!(summary.api_key_set);
+
+        let summary = summarize(
+            WatchedKind::CodexConfig,
+            "model_provider = \"88code\"\n[model_providers.88code]\nbase_url = \"https://88code.org/openai/v1\"\n",
+        );
+        assert_eq!(summary.model_provider.as_deref(), Some("88code"));
+        assert_eq!(summary.base_url.as_deref(), Some("https://88code.org/openai/v1"));
+
+        assert!(summarize(WatchedKind::CodexAuth, "{").parse_error.is_some());
+        assert!(summarize(WatchedKind::ShellRc, "export key88=\"88_x\"\n").api_key_set);
+    }
+}
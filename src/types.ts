@@ -35,3 +35,443 @@ export interface Notification {
 
 // 面板类型
 export type PanelType = "claude" | "codex";
+
+// 受管配置文件状态（get_config_file_status 返回值与 config-file-changed 事件负载）
+export type WatchedKind =
+  | "claude_settings"
+  | "claude_vscode_config"
+  | "codex_auth"
+  | "codex_config"
+  | "vscode_settings"
+  | "shell_rc";
+
+export interface ConfigSummary {
+  base_url: string | null;
+  api_key_set: boolean;
+  model_provider: string | null;
+  model: string | null;
+  parse_error: string | null;
+}
+
+// 高级配置编辑器载入的当前文件内容（文件不存在时为 null）
+export interface EditableConfig {
+  settings_json: string | null;
+  auth_json: string | null;
+  config_toml: string | null;
+}
+
+export interface ConfigFileStatus {
+  kind: WatchedKind;
+  path: string;
+  exists: boolean;
+  summary: ConfigSummary;
+  link: LinkInfo;
+}
+
+// dotfiles 管理工具
+export type DotfileManager = "stow" | "chezmoi" | "home_manager" | "git_repo";
+
+// 配置文件的符号链接与 dotfiles 管理信息
+export interface LinkInfo {
+  symlink_target: string | null;
+  manager: DotfileManager | null;
+  read_only: boolean;
+}
+
+// 检测到的 JetBrains IDE
+export interface JetBrainsIde {
+  product: string;
+  version: string;
+  config_dir: string;
+  terminal_options_path: string;
+  claude_configured: boolean;
+  codex_configured: boolean;
+}
+
+// 检测到的编辑器安装（VS Code 及其衍生版本）
+export interface EditorInstallation {
+  name: string;
+  settings_path: string;
+  installed: boolean;
+  settings_exists: boolean;
+  profiles: EditorProfile[];
+}
+
+// VS Code 用户配置文件（Profile）
+export interface EditorProfile {
+  id: string;
+  name: string;
+  settings_path: string;
+  settings_exists: boolean;
+}
+
+// 单个配置目标的结果（configure_vscode_codex 返回值）
+export type TargetResult = { path: string } & (
+  | { status: "updated" }
+  | { status: "created" }
+  | { status: "skipped"; reason: string }
+  | { status: "parse_error"; message: string; line: number; column: number }
+  | { status: "failed"; message: string }
+);
+
+// 工作区设置来源
+export type SettingScope = "folder" | "workspace" | "user";
+
+export interface SettingLayer {
+  scope: SettingScope;
+  path: string;
+  exists: boolean;
+  api_base: string | null;
+}
+
+// 项目中生效的 chatgpt.apiBase 及其来源
+export interface EffectiveSetting {
+  api_base: string | null;
+  source: SettingScope | null;
+  source_path: string | null;
+  layers: SettingLayer[];
+}
+
+// 被应用修改的文件格式
+export type ManagedFormat = "json" | "jsonc" | "toml" | "shell_rc" | "jet_brains_xml" | "windows_env";
+
+export interface KeyChange {
+  key: string[];
+  original: unknown | null;
+}
+
+export interface FileRecord {
+  format: ManagedFormat;
+  path: string;
+  existed_before: boolean;
+  first_changed_at: number;
+  changes: KeyChange[];
+}
+
+// 应用所做修改的记录（get_change_journal 返回值）
+export interface ChangeJournal {
+  version: number;
+  files: FileRecord[];
+}
+
+// 单个文件的撤销结果（revert_all_changes 返回值）
+export type RevertResult = { format: ManagedFormat; path: string } & (
+  | { status: "reverted" }
+  | { status: "deleted" }
+  | { status: "not_found" }
+  | { status: "failed"; message: string }
+);
+
+// 配置写入审计日志中的一条记录（get_history 返回值）
+export interface HistoryEntry {
+  timestamp: number;
+  operation: string;
+  path: string;
+  before_hash: string | null;
+  after_hash: string;
+  diff: string[];
+  truncated: boolean;
+}
+
+// 权限过于宽松的文件或目录（get_permission_issues / fix_permissions 返回值）
+export interface PermissionIssue {
+  path: string;
+  is_dir: boolean;
+  mode: string;
+  expected: string;
+}
+
+// 导出配置包时密钥的处理方式
+export type KeyMode = "omit" | "plain" | "encrypt";
+
+// 导入配置包预览中的单项变化（密钥已打码）
+export interface PlannedChange {
+  target: string;
+  field: string;
+  current: string | null;
+  incoming: string | null;
+  changed: boolean;
+}
+
+// 导入配置包预览（preview_bundle 返回值）
+export interface BundlePreview {
+  version: number;
+  app_version: string;
+  created_at: number;
+  changes: PlannedChange[];
+}
+
+// 团队配置清单与当前配置的单项差异（密钥已打码）
+export interface DriftItem {
+  target: string;
+  field: string;
+  current: string | null;
+  desired: string | null;
+  drifted: boolean;
+  error: string | null;
+  // 密钥来自命令且未获准执行（检查时从不执行命令）
+  unresolved: boolean;
+}
+
+// 清单检查结果（check_manifest 返回值）
+export interface DriftReport {
+  items: DriftItem[];
+  in_sync: boolean;
+}
+
+// 清单应用结果（apply_manifest 返回值）
+export interface ApplyReport {
+  drift: DriftItem[];
+  applied: string[];
+  remaining: DriftItem[];
+}
+
+// 高级配置校验诊断（validate_claude_advanced / validate_codex_advanced 返回值）
+export type Severity = "error" | "warning";
+
+export interface Diagnostic {
+  path: string;
+  message: string;
+  severity: Severity;
+  suggestion: string | null;
+}
+
+export interface CodexDiagnostics {
+  auth: Diagnostic[];
+  config: Diagnostic[];
+}
+
+// 后端命令的结构化错误（所有 Tauri 命令失败时的返回值）
+export type ErrorKind =
+  | "invalid_input"
+  | "not_found"
+  | "permission_denied"
+  | "io"
+  | "parse"
+  | "locked"
+  | "read_only"
+  | "external_change"
+  | "command"
+  | "other";
+
+export type SuggestedAction = "retry" | "fix_permissions" | "reload" | "edit_file" | "check_input";
+
+export interface AppError {
+  kind: ErrorKind;
+  message: string;
+  path: string | null;
+  line: number | null;
+  column: number | null;
+  source: string | null;
+  action: SuggestedAction | null;
+}
+
+// 后端消息语言（get_locale / set_locale 返回值）
+export type Locale = "zh-CN" | "en-US";
+
+// Base URL 检查结果（check_base_url 返回值）
+export type UrlTarget = "claude" | "codex";
+
+export interface UrlIssue {
+  message: string;
+  fixed: boolean;
+}
+
+export interface NormalizedUrl {
+  url: string;
+  issues: UrlIssue[];
+}
+
+// API 密钥检查结果（check_api_key 返回值）
+export type KeyFormat = "88code" | "anthropic" | "openai" | "unknown";
+
+export type KeyWarning =
+  | "quoted"
+  | "whitespace"
+  | "bearer_prefix"
+  | "placeholder"
+  | "unexpected_length"
+  | "open_ai_key_for_claude"
+  | "anthropic_key_for_codex"
+  | "not_code88_key";
+
+export interface KeyIssue {
+  code: KeyWarning;
+  message: string;
+  fixed: boolean;
+}
+
+export interface KeyCheck {
+  key: string;
+  format: KeyFormat;
+  issues: KeyIssue[];
+}
+
+// 服务地址预设（list_presets 返回值）
+export interface Preset {
+  id: string;
+  name: string;
+  description?: string;
+  claude_base_url: string | null;
+  codex_base_url: string | null;
+  account_api_url?: string;
+}
+
+export interface PresetList {
+  presets: Preset[];
+  default_id: string | null;
+  user_file: string;
+  error: string | null;
+}
+
+// 服务地址测速（benchmark_endpoints 返回值），耗时单位为毫秒
+export interface EndpointResult {
+  url: string;
+  probes: number;
+  errors: number;
+  error_rate: number;
+  connect_ms: number | null;
+  tls_ms: number | null;
+  first_byte_ms: number | null;
+  last_error: string | null;
+}
+
+export interface BenchmarkReport {
+  target: UrlTarget;
+  results: EndpointResult[];
+  winner: string | null;
+  applied: string | null;
+}
+
+// 账户信息（get_account_status 返回值）
+export interface ModelUsage {
+  model: string;
+  requests: number;
+  input_tokens: number;
+  output_tokens: number;
+  cost: number;
+}
+
+export interface AccountStatus {
+  target: UrlTarget;
+  plan: string | null;
+  credits_remaining: number | null;
+  credits_total: number | null;
+  reset_at: string | null;
+  usage: ModelUsage[];
+  key_hint: string;
+  api_url: string;
+  fetched_at: number;
+  cached: boolean;
+}
+
+// 本地用量统计（get_usage_stats 返回值），费用单位为美元
+export interface TokenUsage {
+  input_tokens: number;
+  output_tokens: number;
+  cache_creation_tokens: number;
+  cache_read_tokens: number;
+}
+
+export interface UsageSummary extends TokenUsage {
+  key: string;
+  requests: number;
+  sessions: number;
+  cost: number;
+}
+
+export interface UsageRow extends TokenUsage {
+  date: string;
+  client: UrlTarget;
+  project: string;
+  model: string;
+  requests: number;
+  cost: number;
+}
+
+export interface UsageReport {
+  total: UsageSummary;
+  by_model: UsageSummary[];
+  by_day: UsageSummary[];
+  by_project: UsageSummary[];
+  rows: UsageRow[];
+  unpriced_models: string[];
+  files_scanned: number;
+  skipped_lines: number;
+}
+
+// 会话记录（list_sessions / search_sessions / get_session 返回值）
+export type MessageRole = 'user' | 'assistant' | 'tool';
+
+export interface SessionMessage {
+  role: MessageRole;
+  timestamp: string | null;
+  text: string;
+}
+
+export interface SessionSummary {
+  id: string;
+  client: UrlTarget;
+  path: string;
+  project: string;
+  title: string;
+  model: string | null;
+  started_at: string | null;
+  updated_at: string | null;
+  // 会话列表中只扫描了大文件的开头和结尾时为 null
+  message_count: number | null;
+}
+
+export interface Transcript extends SessionSummary {
+  messages: SessionMessage[];
+}
+
+export interface SearchHit {
+  session: SessionSummary;
+  role: MessageRole;
+  timestamp: string | null;
+  snippet: string;
+}
+
+export interface RelayRouteConfig {
+  endpoints: string[];
+  api_key: string | null;
+  disabled: boolean;
+}
+
+export interface RelayConfig {
+  port: number | null;
+  proxy: string | null;
+  claude: RelayRouteConfig | null;
+  codex: RelayRouteConfig | null;
+}
+
+export interface RelayLogEntry {
+  timestamp: number;
+  client: UrlTarget | null;
+  method: string;
+  path: string;
+  upstream: string | null;
+  status: number | null;
+  duration_ms: number;
+  attempts: number;
+  error: string | null;
+}
+
+export interface RelayRouteStatus {
+  client: UrlTarget;
+  local_url: string;
+  endpoints: string[];
+  active: string;
+  key_hint: string;
+}
+
+export interface RelayStatus {
+  running: boolean;
+  port: number | null;
+  proxy: string | null;
+  routes: RelayRouteStatus[];
+  applied: UrlTarget[];
+  requests: number;
+  recent: RelayLogEntry[];
+}